@@ -1,6 +1,11 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::commands::find::SortOrder;
+use crate::commands::hotspots::ReportFormat;
+use crate::map::LineCounts;
+use crate::output::OutputFormat;
+
 #[derive(Parser)]
 #[command(name = "sysmap")]
 #[command(author, version, about = "Intelligent project mapping for AI agents and humans")]
@@ -34,18 +39,50 @@ pub enum Commands {
         /// Overwrite existing .sysmap directory
         #[arg(short, long)]
         force: bool,
+
+        /// Don't respect .gitignore, .ignore and git exclude files
+        #[arg(long)]
+        no_gitignore: bool,
+
+        /// Don't read commit history from the git repository
+        #[arg(long)]
+        no_git: bool,
+
+        /// Don't write a default .sysmap/config.toml
+        #[arg(long, conflicts_with = "template")]
+        no_config: bool,
+
+        /// Start from a config template (name in the template directory, or a path)
+        #[arg(long, value_name = "NAME")]
+        template: Option<String>,
+
+        /// Worker threads for scanning (default: one per CPU core)
+        #[arg(short = 'j', long, default_value = "0", hide_default_value = true)]
+        threads: usize,
     },
 
     /// Display compressed project summary
     #[command(visible_alias = "s")]
     Summary {
-        /// Output as JSON
-        #[arg(long)]
+        /// Output as JSON (same as --format json)
+        #[arg(long, conflicts_with_all = ["yaml", "format"])]
         json: bool,
 
-        /// Output as YAML
-        #[arg(long)]
+        /// Output as YAML (same as --format yaml)
+        #[arg(long, conflicts_with = "format")]
         yaml: bool,
+
+        /// Count generated and vendored files in the statistics
+        #[arg(long)]
+        include_generated: bool,
+
+        /// Refresh the map first if the project changed since it was scanned
+        #[arg(long)]
+        auto_update: bool,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
     },
 
     /// Display directory tree with pattern awareness
@@ -61,6 +98,25 @@ pub enum Commands {
         /// Show collapsed directories expanded
         #[arg(short, long)]
         all: bool,
+
+        /// Show code, comment and blank line counts instead of total lines
+        #[arg(long)]
+        counts: bool,
+
+        #[command(flatten)]
+        lines: LineFilter,
+
+        /// Refresh the map first if the project changed since it was scanned
+        #[arg(long)]
+        auto_update: bool,
+
+        /// Output as JSON (same as --format json)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
     },
 
     /// Update existing map incrementally
@@ -69,6 +125,18 @@ pub enum Commands {
         /// Force full rebuild instead of incremental
         #[arg(long)]
         full: bool,
+
+        /// Don't respect .gitignore, .ignore and git exclude files
+        #[arg(long)]
+        no_gitignore: bool,
+
+        /// Don't read commit history from the git repository
+        #[arg(long)]
+        no_git: bool,
+
+        /// Worker threads for scanning (default: one per CPU core)
+        #[arg(short = 'j', long, default_value = "0", hide_default_value = true)]
+        threads: usize,
     },
 
     /// Search the map for files
@@ -88,5 +156,196 @@ pub enum Commands {
         /// Filter by purpose (entry, module, test, config)
         #[arg(short = 'p', long = "purpose")]
         purpose: Option<String>,
+
+        #[command(flatten)]
+        lines: LineFilter,
+
+        /// Show code, comment and blank line counts instead of total lines
+        #[arg(long)]
+        counts: bool,
+
+        /// Also match generated and vendored files
+        #[arg(long)]
+        include_generated: bool,
+
+        /// Result order
+        #[arg(long, value_enum, default_value_t = SortOrder::Path)]
+        sort: SortOrder,
+
+        /// Refresh the map first if the project changed since it was scanned
+        #[arg(long)]
+        auto_update: bool,
+
+        /// Output as JSON (same as --format json)
+        #[arg(long, conflicts_with_all = ["format", "ndjson"])]
+        json: bool,
+
+        /// Stream matches as newline-delimited JSON, one object per line
+        #[arg(long, conflicts_with = "format")]
+        ndjson: bool,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+
+    /// Show import relationships between project files
+    #[command(visible_alias = "d")]
+    Deps {
+        /// File to analyze (shows all files if omitted)
+        file: Option<PathBuf>,
+
+        /// Show what depends on this file instead of what it imports
+        #[arg(short, long)]
+        reverse: bool,
+
+        /// How many levels of dependencies to follow
+        #[arg(short, long, default_value = "1")]
+        depth: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Keep the map up to date as files change
+    #[command(visible_alias = "w")]
+    Watch {
+        /// Milliseconds to wait for events to settle before patching the map
+        #[arg(long, default_value = "300")]
+        debounce: u64,
+
+        /// Seconds between writes of map.json
+        #[arg(long, default_value = "5")]
+        interval: u64,
+
+        /// Don't respect .gitignore, .ignore and git exclude files
+        #[arg(long)]
+        no_gitignore: bool,
+    },
+
+    /// Build a prompt-ready project overview that fits a token budget
+    #[command(visible_alias = "c")]
+    Context {
+        /// Approximate token budget (about four characters per token)
+        #[arg(short, long, default_value = "4000")]
+        budget: usize,
+
+        /// Output format (json and yaml include the plan)
+        #[arg(long, value_enum, default_value = "text")]
+        format: OutputFormat,
+    },
+
+    /// Compare two maps: added, removed, renamed and resized files
+    Diff {
+        /// Older map file or snapshot id (default: the saved map, compared with the working tree)
+        old: Option<PathBuf>,
+
+        /// Newer map file or snapshot id (default: the saved map)
+        new: Option<PathBuf>,
+
+        /// Output as JSON (same as --format json)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+
+    /// List and inspect snapshots of previous maps
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
+    },
+
+    /// Rank files and directories by commits times size, to find refactoring targets
+    Hotspots {
+        /// History window: 30d, 6w, 3m, 1y or a date (default: the window of the last scan)
+        #[arg(long, value_name = "WINDOW")]
+        since: Option<String>,
+
+        /// Rows to show per table
+        #[arg(short = 'n', long, default_value = "10")]
+        limit: usize,
+
+        /// Weigh commits by estimated complexity instead of lines of code
+        #[arg(long)]
+        complexity: bool,
+
+        /// Output as JSON (same as --format json)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+
+    /// Serve the map to AI agents over the Model Context Protocol (stdio)
+    Mcp {
+        /// Project directory (default: current directory)
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HistoryCommand {
+    /// List snapshots with their file and line totals, oldest first
+    List {
+        /// Output as JSON (same as --format json)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
     },
+
+    /// Show the summary of a snapshot
+    Show {
+        /// Snapshot id, a unique prefix of one, or `latest`
+        id: String,
+
+        /// Output as JSON (same as --format json)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+}
+
+/// Filters on the code and comment line counts of files
+#[derive(Args, Debug, Clone, Default)]
+pub struct LineFilter {
+    /// Only files with at least this many lines of code
+    #[arg(long, value_name = "N")]
+    pub min_code: Option<usize>,
+
+    /// Only files with at most this many lines of code
+    #[arg(long, value_name = "N")]
+    pub max_code: Option<usize>,
+
+    /// Only files with at least this many comment lines
+    #[arg(long, value_name = "N")]
+    pub min_comment: Option<usize>,
+}
+
+impl LineFilter {
+    pub fn is_active(&self) -> bool {
+        self.min_code.is_some() || self.max_code.is_some() || self.min_comment.is_some()
+    }
+
+    /// Whether a file passes; files without counts (binary or unreadable)
+    /// only pass when no filter is set
+    pub fn matches(&self, counts: Option<&LineCounts>) -> bool {
+        let Some(counts) = counts else {
+            return !self.is_active();
+        };
+        self.min_code.is_none_or(|min| counts.code >= min)
+            && self.max_code.is_none_or(|max| counts.code <= max)
+            && self.min_comment.is_none_or(|min| counts.comment >= min)
+    }
 }
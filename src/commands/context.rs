@@ -0,0 +1,60 @@
+use std::env;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::config::{find_sysmap_root, map_path};
+use crate::context::{build_context, ContextPack, SectionStatus};
+use crate::map::SystemMap;
+use crate::output::{print_structured, OutputFormat};
+
+/// Execute the context command
+pub fn execute(budget: usize, format: OutputFormat, verbosity: u8) -> Result<()> {
+    let cwd = env::current_dir()?;
+
+    let root = find_sysmap_root(&cwd)
+        .ok_or_else(|| anyhow::anyhow!(
+            "No sysmap found. Run 'sysmap init' first."
+        ))?;
+
+    let map = SystemMap::load(&map_path(&root))?;
+    let pack = build_context(&map, budget);
+
+    if format != OutputFormat::Text {
+        return print_structured(&pack, format);
+    }
+
+    // The document goes to stdout so it can be piped straight into a prompt;
+    // the plan goes to stderr
+    print!("{}", pack.document);
+    if verbosity > 0 {
+        print_plan(&pack);
+    }
+
+    Ok(())
+}
+
+fn print_plan(pack: &ContextPack) {
+    eprintln!();
+    eprintln!("{} ~{} of {} tokens",
+        "Context:".green().bold(),
+        pack.estimated_tokens.to_string().yellow(),
+        pack.budget
+    );
+
+    for (i, section) in pack.plan.iter().enumerate() {
+        let connector = if i + 1 == pack.plan.len() { "└─" } else { "├─" };
+        let status = match section.status {
+            SectionStatus::Full => format!("{} tokens", section.tokens),
+            SectionStatus::Truncated => format!(
+                "{} of {} tokens ({})",
+                section.tokens,
+                section.full_tokens,
+                section.detail.as_deref().unwrap_or("truncated")
+            ),
+            SectionStatus::Dropped => format!("dropped, needs {} tokens", section.full_tokens).red().to_string(),
+            SectionStatus::Empty => "empty".dimmed().to_string(),
+        };
+        eprintln!("  {} {}: {}", connector.dimmed(), section.section, status);
+    }
+}
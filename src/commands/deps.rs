@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use colored::Colorize;
+use serde_json::Value;
+
+use crate::config::{find_sysmap_root, map_path};
+use crate::deps::path_key;
+use crate::map::{Dependencies, FileNode, SystemMap};
+
+/// Execute the deps command
+pub fn execute(file: Option<PathBuf>, reverse: bool, depth: usize, json: bool) -> Result<()> {
+    let cwd = env::current_dir()?;
+
+    let root = find_sysmap_root(&cwd)
+        .ok_or_else(|| anyhow::anyhow!(
+            "No sysmap found. Run 'sysmap init' first."
+        ))?;
+
+    let map = SystemMap::load(&map_path(&root))?;
+    let target = file
+        .as_ref()
+        .map(|file| resolve_target(&root, &cwd, file, &map.tree))
+        .transpose()?;
+    let depth = depth.max(1);
+
+    if json {
+        let output = build_json(&map.dependencies, target.as_deref(), reverse, depth);
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    // A single file, or every file with dependencies
+    let graph = Graph::new(&map.dependencies, reverse);
+    let files: Vec<String> = match target {
+        Some(target) => vec![target],
+        None => graph.sources(),
+    };
+
+    if files.is_empty() {
+        println!("{}", "No internal dependencies found.".yellow());
+        return Ok(());
+    }
+
+    for (i, file) in files.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        graph.print(file, depth);
+    }
+
+    if file.is_none() {
+        let edges: usize = map.dependencies.internal.values().map(|targets| targets.len()).sum();
+        println!();
+        println!(
+            "{}",
+            format!("{} files, {} internal imports", files.len(), edges).dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// The JSON form of the graph around one file, or of every file with
+/// dependencies when `file` is `None`
+pub fn build_json(deps: &Dependencies, file: Option<&str>, reverse: bool, depth: usize) -> Value {
+    let graph = Graph::new(deps, reverse);
+    let depth = depth.max(1);
+    match file {
+        Some(file) => graph.to_json(file, depth),
+        None => graph.sources().iter().map(|f| graph.to_json(f, depth)).collect(),
+    }
+}
+
+/// The dependency graph in one direction
+struct Graph<'a> {
+    edges: BTreeMap<&'a str, Vec<&'a str>>,
+    external: Option<&'a BTreeMap<String, Vec<String>>>,
+    label: &'static str,
+}
+
+impl<'a> Graph<'a> {
+    fn new(deps: &'a Dependencies, reverse: bool) -> Self {
+        let mut edges: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+        if reverse {
+            for (source, targets) in &deps.internal {
+                for target in targets {
+                    edges.entry(target.as_str()).or_default().push(source.as_str());
+                }
+            }
+        } else {
+            for (source, targets) in &deps.internal {
+                edges.insert(source, targets.iter().map(String::as_str).collect());
+            }
+        }
+
+        Self {
+            edges,
+            // External packages only make sense going forward
+            external: (!reverse).then_some(&deps.external_imports),
+            label: if reverse { "imported by" } else { "imports" },
+        }
+    }
+
+    fn sources(&self) -> Vec<String> {
+        let mut files: Vec<String> = self.edges.keys().map(|f| f.to_string()).collect();
+        if let Some(external) = self.external {
+            files.extend(external.keys().cloned());
+            files.sort();
+            files.dedup();
+        }
+        files
+    }
+
+    fn targets(&self, file: &str) -> &[&'a str] {
+        self.edges.get(file).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    fn external_for(&self, file: &str) -> &[String] {
+        self.external
+            .and_then(|external| external.get(file))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    fn print(&self, file: &str, depth: usize) {
+        println!("{}", file.bold());
+
+        let external = self.external_for(file);
+        if self.targets(file).is_empty() && external.is_empty() {
+            println!("  {} {}", format!("{}:", self.label).dimmed(), "(none)".dimmed());
+            return;
+        }
+
+        println!("  {}", format!("{}:", self.label).dimmed());
+        let mut ancestors = vec![file];
+        self.print_targets(file, "    ", 1, depth, &mut ancestors, external);
+    }
+
+    fn print_targets<'s>(
+        &'s self,
+        file: &str,
+        prefix: &str,
+        level: usize,
+        depth: usize,
+        ancestors: &mut Vec<&'s str>,
+        external: &[String],
+    ) {
+        let targets = self.targets(file);
+        let count = targets.len() + usize::from(!external.is_empty());
+
+        for (i, target) in targets.iter().enumerate() {
+            let is_last = i + 1 == count;
+            let connector = if is_last { "└── " } else { "├── " };
+
+            if ancestors.contains(target) {
+                println!("{}{}{} {}", prefix, connector, target, "(circular)".dimmed());
+                continue;
+            }
+            println!("{}{}{}", prefix, connector, target);
+
+            if level < depth {
+                let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+                ancestors.push(target);
+                self.print_targets(target, &child_prefix, level + 1, depth, ancestors, &[]);
+                ancestors.pop();
+            }
+        }
+
+        if !external.is_empty() {
+            println!(
+                "{}└── {}",
+                prefix,
+                format!("(external) {}", external.join(", ")).dimmed()
+            );
+        }
+    }
+
+    fn to_json(&self, file: &str, depth: usize) -> Value {
+        let mut ancestors = vec![file];
+        let mut value = self.json_node(file, 1, depth, &mut ancestors);
+        if self.external.is_some() {
+            value["external"] = serde_json::json!(self.external_for(file));
+        }
+        value
+    }
+
+    fn json_node<'s>(&'s self, file: &str, level: usize, depth: usize, ancestors: &mut Vec<&'s str>) -> Value {
+        let mut children = Vec::new();
+        for target in self.targets(file) {
+            if ancestors.contains(target) {
+                children.push(serde_json::json!({ "path": target, "circular": true }));
+            } else if level < depth {
+                ancestors.push(target);
+                children.push(self.json_node(target, level + 1, depth, ancestors));
+                ancestors.pop();
+            } else {
+                children.push(serde_json::json!({ "path": target }));
+            }
+        }
+
+        let key = self.label.replace(' ', "_");
+        let mut value = serde_json::json!({ "path": file });
+        value[key] = Value::Array(children);
+        value
+    }
+}
+
+/// Map a file argument (relative to the current directory or the project root)
+/// to its key in the dependency graph
+pub fn resolve_target(root: &Path, cwd: &Path, file: &Path, tree: &FileNode) -> Result<String> {
+    let mut files = HashSet::new();
+    collect_files(tree, &mut files);
+
+    let absolute = cwd.join(file);
+    let absolute = absolute.canonicalize().unwrap_or(absolute);
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+    let candidates = [
+        absolute.strip_prefix(&root).ok().map(path_key),
+        Some(path_key(file)),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|candidate| files.contains(candidate))
+        .ok_or_else(|| anyhow::anyhow!("File not found in map: {}", file.display()))
+}
+
+fn collect_files(node: &FileNode, files: &mut HashSet<String>) {
+    match node {
+        FileNode::File { path, .. } => {
+            files.insert(path_key(path));
+        }
+        FileNode::Directory { children, .. } => {
+            for child in children {
+                collect_files(child, files);
+            }
+        }
+        FileNode::Collapsed { .. } => {}
+    }
+}
@@ -0,0 +1,175 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::{cache_path, find_sysmap_root, history_path, load_config, map_path};
+use crate::map::{find_snapshot, MapDiff, SystemMap};
+use crate::output::{print_structured, OutputFormat};
+use crate::scanner::{scan_incremental, ChecksumCache, PreviousScan, ScannerConfig};
+
+/// Execute the diff command.
+///
+/// With no maps, the saved map is compared against a fresh scan of the
+/// working tree; with one, that map against the saved map; with two, the
+/// first against the second. Maps are files, or snapshot ids.
+pub fn execute(old: Option<PathBuf>, new: Option<PathBuf>, format: OutputFormat) -> Result<()> {
+    let cwd = env::current_dir()?;
+
+    let root = find_sysmap_root(&cwd);
+    let saved = || -> Result<(SystemMap, String)> {
+        let root = root
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No sysmap found. Run 'sysmap init' first."))?;
+        Ok((SystemMap::load(&map_path(root))?, "saved map".to_string()))
+    };
+
+    let load = |arg: &Path| load(root.as_deref(), arg);
+    let ((old_map, old_label), (new_map, new_label)) = match (old, new) {
+        (Some(old), Some(new)) => (load(&old)?, load(&new)?),
+        (Some(old), None) => (load(&old)?, saved()?),
+        _ => {
+            let (map, label) = saved()?;
+            let current = scan_working_tree(&map)?;
+            ((map, label), (current, "working tree".to_string()))
+        }
+    };
+
+    let diff = MapDiff::between(&old_map, &new_map);
+
+    if format != OutputFormat::Text {
+        let output = DiffOutput { old: &old_label, new: &new_label, diff: &diff };
+        return print_structured(&output, format);
+    }
+
+    print_diff(&diff, &old_label, &new_label);
+    Ok(())
+}
+
+/// Structured output of a diff
+#[derive(Serialize)]
+struct DiffOutput<'a> {
+    old: &'a str,
+    new: &'a str,
+    #[serde(flatten)]
+    diff: &'a MapDiff,
+}
+
+/// Load a map file, or a snapshot by id when no such file exists
+fn load(root: Option<&Path>, arg: &Path) -> Result<(SystemMap, String)> {
+    if let (false, Some(root), Some(id)) = (arg.exists(), root, arg.to_str()) {
+        let snapshot = find_snapshot(&history_path(root), id)?;
+        return Ok((snapshot.load()?, format!("snapshot {}", snapshot.id)));
+    }
+    let map = SystemMap::load(arg).with_context(|| format!("Failed to load map: {}", arg.display()))?;
+    Ok((map, arg.display().to_string()))
+}
+
+/// Scan the project again without saving, reusing the saved results for unchanged files
+fn scan_working_tree(saved: &SystemMap) -> Result<SystemMap> {
+    let root = &saved.root;
+    let settings = load_config(root)?;
+    let mut config = ScannerConfig::from_settings(&settings)?;
+    config.show_progress = false;
+    // Commit history plays no part in a structural diff
+    config.read_git = false;
+
+    let cache = ChecksumCache::load(&cache_path(root));
+    let previous = cache.as_ref().map(|cache| PreviousScan::new(saved, cache));
+    Ok(scan_incremental(root, &config, previous.as_ref())?.map)
+}
+
+fn print_diff(diff: &MapDiff, old_label: &str, new_label: &str) {
+    println!("{} {} → {}", "Comparing".bold(), old_label, new_label);
+
+    if diff.is_empty() {
+        println!();
+        println!("{}", "No structural changes.".green());
+        return;
+    }
+
+    let heading = |title: &str, count: usize| {
+        println!();
+        println!("{}", format!("{} ({}):", title, count).bold());
+    };
+
+    if !diff.added.is_empty() {
+        heading("Added", diff.added.len());
+        for file in &diff.added {
+            let lines = file.lines.map(|l| format!("  ({} lines)", l)).unwrap_or_default();
+            println!("  {} {}{}", "+".green(), file.path, lines.dimmed());
+        }
+    }
+
+    if !diff.removed.is_empty() {
+        heading("Removed", diff.removed.len());
+        for file in &diff.removed {
+            let lines = file.lines.map(|l| format!("  ({} lines)", l)).unwrap_or_default();
+            println!("  {} {}{}", "-".red(), file.path, lines.dimmed());
+        }
+    }
+
+    if !diff.renamed.is_empty() {
+        heading("Renamed", diff.renamed.len());
+        for rename in &diff.renamed {
+            println!("  {} → {}", rename.from, rename.to);
+        }
+    }
+
+    if !diff.resized.is_empty() {
+        heading("Lines changed", diff.resized.len());
+        for resize in &diff.resized {
+            let delta = match resize.delta() {
+                d if d > 0 => format!("+{}", d).green(),
+                d => d.to_string().red(),
+            };
+            println!("  {}  {} → {} ({})", resize.path, resize.old_lines, resize.new_lines, delta);
+        }
+    }
+
+    for (title, changes) in [("Language changed", &diff.language_changed), ("Purpose changed", &diff.purpose_changed)] {
+        if changes.is_empty() {
+            continue;
+        }
+        heading(title, changes.len());
+        for change in changes {
+            println!(
+                "  {}  {} → {}",
+                change.path,
+                change.old.as_deref().unwrap_or("none"),
+                change.new.as_deref().unwrap_or("none")
+            );
+        }
+    }
+
+    for (title, sign, changes) in [
+        ("Newly collapsed", "+".green(), &diff.collapsed_added),
+        ("No longer collapsed", "-".red(), &diff.collapsed_removed),
+    ] {
+        if changes.is_empty() {
+            continue;
+        }
+        heading(title, changes.len());
+        for change in changes {
+            println!(
+                "  {} {}/  {}",
+                sign,
+                change.path,
+                format!("{} ({} files)", change.pattern, change.file_count).dimmed()
+            );
+        }
+    }
+
+    let totals = &diff.totals;
+    println!();
+    println!(
+        "{}",
+        format!(
+            "Files: {} → {}, lines: {} → {}",
+            totals.old_files, totals.new_files, totals.old_lines, totals.new_lines
+        )
+        .dimmed()
+    );
+}
@@ -1,19 +1,31 @@
+use std::cmp::Reverse;
 use std::env;
+use std::io::{self, Write};
 use std::time::Instant;
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use colored::Colorize;
+use serde::Serialize;
 
+use crate::cli::LineFilter;
 use crate::colors::{colorize_language, colorize_purpose};
-use crate::config::{find_sysmap_root, map_path};
-use crate::map::{FileNode, SystemMap};
+use crate::commands::update::load_current_map;
+use crate::config::find_sysmap_root;
+use crate::map::FileNode;
+use crate::output::{print_structured, Entry, OutputFormat, SCHEMA_VERSION};
+use crate::scanner::node_churn;
 
 /// Execute the find command
 pub fn execute(
-    query: String, 
-    file_type: Option<String>,
-    language: Option<String>,
-    purpose: Option<String>,
+    query: String,
+    filters: Filters,
+    sort: SortOrder,
+    show_counts: bool,
+    format: OutputFormat,
+    ndjson: bool,
+    auto_update: bool,
 ) -> Result<()> {
     let start = Instant::now();
     let cwd = env::current_dir()?;
@@ -23,34 +35,35 @@ pub fn execute(
             "No sysmap found. Run 'sysmap init' first."
         ))?;
 
-    let map = SystemMap::load(&map_path(&root))?;
+    let map = load_current_map(&root, auto_update)?;
+
+    // Stream one JSON object per line as matches are found (sorting needs them all first)
+    if ndjson && sort == SortOrder::Path {
+        let mut stdout = io::stdout().lock();
+        let mut result = Ok(());
+        find_matches(&map.tree, &filters, &mut |node| {
+            if result.is_ok() {
+                result = write_ndjson(&mut stdout, node);
+            }
+        });
+        return result;
+    }
 
-    let query_lower = query.to_lowercase();
-    
-    // Normalize file type filter (remove leading dot if present)
-    let file_type_normalized = file_type.map(|ft| {
-        ft.strip_prefix('.').unwrap_or(&ft).to_lowercase()
-    });
-    
-    // Normalize language filter
-    let language_normalized = language.map(|l| l.to_lowercase());
-    
-    // Normalize purpose filter
-    let purpose_normalized = purpose.map(|p| p.to_lowercase());
-    
     let mut matches = Vec::new();
+    find_matches(&map.tree, &filters, &mut |node| matches.push(node));
+    sort_matches(&mut matches, sort);
 
-    find_matches(
-        &map.tree, 
-        &query_lower, 
-        &file_type_normalized,
-        &language_normalized,
-        &purpose_normalized,
-        &mut matches
-    );
+    if ndjson {
+        let mut stdout = io::stdout().lock();
+        return matches.iter().try_for_each(|node| write_ndjson(&mut stdout, node));
+    }
 
     let elapsed = start.elapsed();
 
+    if format != OutputFormat::Text {
+        return print_structured(&FindOutput::new(&query, &matches), format);
+    }
+
     if matches.is_empty() {
         println!("{}", "No matches found.".yellow());
         println!("{}", format!("Search completed in {:?}", elapsed).dimmed());
@@ -60,19 +73,46 @@ pub fn execute(
     println!("{} {} matches:", "Found".green().bold(), matches.len());
     println!();
 
-    for (path, name, file_purpose, file_language, lines) in matches {
+    for node in matches {
         let mut info_parts = Vec::new();
-        
-        if let Some(l) = lines {
-            info_parts.push(format!("{} lines", l));
-        }
-        
-        if let Some(p) = &file_purpose {
-            info_parts.push(format!("[{}]", colorize_purpose(p)));
-        }
-        
-        if let Some(l) = &file_language {
-            info_parts.push(colorize_language(l).to_string());
+        let mut name = node.name().to_string();
+
+        if let FileNode::File { lines, counts, generated, vendored, purpose, language, git, .. } = node {
+            if *vendored {
+                info_parts.push("vendored".to_string());
+            } else if *generated {
+                info_parts.push("generated".to_string());
+            }
+
+            match (counts, lines) {
+                (Some(c), _) if show_counts => {
+                    info_parts.push(format!("{} code, {} comment, {} blank", c.code, c.comment, c.blank));
+                }
+                (_, Some(l)) => info_parts.push(format!("{} lines", l)),
+                _ => {}
+            }
+
+            if let Some(p) = purpose {
+                info_parts.push(format!("[{}]", colorize_purpose(p)));
+            }
+
+            if let Some(l) = language {
+                info_parts.push(colorize_language(l).to_string());
+            }
+
+            if let (Some(git), true) = (git, sort != SortOrder::Path) {
+                info_parts.push(format!(
+                    "{}, last {} by {}",
+                    commits(git.commits),
+                    git.last_commit.format("%Y-%m-%d"),
+                    git.author
+                ));
+            }
+        } else {
+            name.push('/');
+            if let (FileNode::Directory { churn: Some(churn), .. }, true) = (node, sort != SortOrder::Path) {
+                info_parts.push(commits(*churn));
+            }
         }
 
         let info = if info_parts.is_empty() {
@@ -83,6 +123,9 @@ pub fn execute(
 
         // Highlight the match in the name
         let highlighted = highlight_match(&name, &query);
+        let path = node.path().parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
         
         println!("  {}{}{}",
             if path.is_empty() { String::new() } else { format!("{}/", path).dimmed().to_string() },
@@ -97,17 +140,134 @@ pub fn execute(
     Ok(())
 }
 
-fn find_matches(
-    node: &FileNode,
-    query: &str,
-    file_type: &Option<String>,
-    language: &Option<String>,
-    purpose: &Option<String>,
-    matches: &mut Vec<(String, String, Option<String>, Option<String>, Option<usize>)>,
-) {
+fn commits(count: usize) -> String {
+    let noun = if count == 1 { "commit" } else { "commits" };
+    format!("{} {}", count, noun)
+}
+
+/// Structured output of a search
+#[derive(Serialize)]
+pub struct FindOutput<'a> {
+    schema_version: u32,
+    query: &'a str,
+    matches: Vec<Entry>,
+}
+
+impl<'a> FindOutput<'a> {
+    pub fn new(query: &'a str, matches: &[&FileNode]) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            query,
+            matches: matches.iter().map(|node| Entry::from_node(node)).collect(),
+        }
+    }
+}
+
+/// An NDJSON record: the entry plus the schema version
+#[derive(Serialize)]
+struct FindRecord {
+    schema_version: u32,
+    #[serde(flatten)]
+    entry: Entry,
+}
+
+fn write_ndjson(out: &mut impl Write, node: &FileNode) -> Result<()> {
+    let record = FindRecord {
+        schema_version: SCHEMA_VERSION,
+        entry: Entry::from_node(node),
+    };
+    serde_json::to_writer(&mut *out, &record)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Order of the search results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    /// Tree order
+    #[default]
+    Path,
+    /// Most recently committed first (modification time outside git)
+    Recent,
+    /// Most commits within the git window first
+    Churn,
+}
+
+/// Sort matches, keeping tree order between equals
+pub fn sort_matches(matches: &mut [&FileNode], sort: SortOrder) {
+    match sort {
+        SortOrder::Path => {}
+        SortOrder::Recent => matches.sort_by_key(|node| Reverse(last_activity(node))),
+        SortOrder::Churn => matches.sort_by_key(|node| Reverse(node_churn(node))),
+    }
+}
+
+/// When a file last changed: its last commit, or its modification time when
+/// untracked (directories have none and sort last)
+fn last_activity(node: &FileNode) -> Option<DateTime<Utc>> {
+    match node {
+        FileNode::File { git: Some(git), .. } => Some(git.last_commit),
+        FileNode::File { modified, .. } => *modified,
+        _ => None,
+    }
+}
+
+/// Normalized search criteria
+pub struct Filters {
+    query: String,
+    file_type: Option<String>,
+    language: Option<String>,
+    purpose: Option<String>,
+    lines: LineFilter,
+    /// Whether generated and vendored files can match
+    include_generated: bool,
+}
+
+impl Filters {
+    pub fn new(
+        query: &str,
+        file_type: Option<String>,
+        language: Option<String>,
+        purpose: Option<String>,
+    ) -> Self {
+        Self {
+            query: query.to_lowercase(),
+            // Remove leading dot if present
+            file_type: file_type.map(|ft| ft.strip_prefix('.').unwrap_or(&ft).to_lowercase()),
+            language: language.map(|l| l.to_lowercase()),
+            purpose: purpose.map(|p| p.to_lowercase()),
+            lines: LineFilter::default(),
+            include_generated: false,
+        }
+    }
+
+    /// Also filter on code and comment line counts
+    pub fn with_lines(self, lines: LineFilter) -> Self {
+        Self { lines, ..self }
+    }
+
+    /// Also match generated and vendored files
+    pub fn with_generated(self, include_generated: bool) -> Self {
+        Self { include_generated, ..self }
+    }
+}
+
+/// Walk the tree, calling `on_match` for every node matching the filters
+pub fn find_matches<'a>(node: &'a FileNode, filters: &Filters, on_match: &mut dyn FnMut(&'a FileNode)) {
+    let query = filters.query.as_str();
+    let (file_type, language, purpose) = (&filters.file_type, &filters.language, &filters.purpose);
+
     match node {
-        FileNode::File { name, path, purpose: file_purpose, language: file_language, lines, .. } => {
+        FileNode::File { name, path, counts, purpose: file_purpose, language: file_language, .. } => {
             let name_lower = name.to_lowercase();
+
+            if !filters.lines.matches(counts.as_ref()) {
+                return;
+            }
+
+            if !filters.include_generated && node.is_generated_or_vendored() {
+                return;
+            }
             
             // Check file type filter
             if let Some(ft) = file_type {
@@ -137,41 +297,21 @@ fn find_matches(
 
             // Check if name matches query
             if name_lower.contains(query) {
-                let parent = path.parent()
-                    .map(|p| p.to_string_lossy().to_string())
-                    .unwrap_or_default();
-                
-                matches.push((
-                    parent,
-                    name.clone(),
-                    file_purpose.clone(),
-                    file_language.clone(),
-                    *lines,
-                ));
+                on_match(node);
             }
         }
-        FileNode::Directory { name, path, children, .. } => {
+        FileNode::Directory { name, children, .. } => {
             // Only match directories if no filters are set
-            if file_type.is_none() && language.is_none() && purpose.is_none() {
+            if file_type.is_none() && language.is_none() && purpose.is_none() && !filters.lines.is_active() {
                 let name_lower = name.to_lowercase();
                 if name_lower.contains(query) {
-                    let parent = path.parent()
-                        .map(|p| p.to_string_lossy().to_string())
-                        .unwrap_or_default();
-                    
-                    matches.push((
-                        parent,
-                        format!("{}/", name),
-                        None,
-                        None,
-                        None,
-                    ));
+                    on_match(node);
                 }
             }
 
             // Search children
             for child in children {
-                find_matches(child, query, file_type, language, purpose, matches);
+                find_matches(child, filters, on_match);
             }
         }
         FileNode::Collapsed { .. } => {
@@ -0,0 +1,127 @@
+use std::env;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::commands::summary::{build_summary, print_human_summary};
+use crate::config::{find_sysmap_root, history_path, map_path};
+use crate::map::{find_snapshot, list_snapshots, FileNode, SystemMap};
+use crate::output::{print_structured, OutputFormat};
+
+/// Execute `history list`: one row per snapshot, oldest first, then the current map
+pub fn list(format: OutputFormat) -> Result<()> {
+    let root = project_root()?;
+
+    let mut rows = Vec::new();
+    for snapshot in list_snapshots(&history_path(&root))? {
+        // An unreadable snapshot shouldn't hide the others
+        match snapshot.load() {
+            Ok(map) => rows.push(HistoryRow::new(&snapshot.id, &map)),
+            Err(e) => eprintln!("{} {:#}", "warning:".yellow().bold(), e),
+        }
+    }
+    if let Ok(map) = SystemMap::load(&map_path(&root)) {
+        rows.push(HistoryRow::new("current", &map));
+    }
+
+    if format != OutputFormat::Text {
+        return print_structured(&rows, format);
+    }
+
+    if rows.len() <= 1 {
+        println!("{}", "No snapshots yet. They are saved when 'sysmap update' replaces a map.".yellow());
+    }
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let id_width = rows.iter().map(|r| r.id.len()).max().unwrap_or(0);
+    println!(
+        "{}",
+        format!("{:<id_width$}  {:<16}  {:>7}  {:>9}  {}", "ID", "Scanned", "Files", "Lines", "Languages").bold()
+    );
+    let mut previous_lines: Option<usize> = None;
+    for row in &rows {
+        let delta = match previous_lines {
+            Some(before) if before != row.lines => {
+                let delta = row.lines as i64 - before as i64;
+                let text = format!(" ({:+})", delta);
+                if delta > 0 { text.green().to_string() } else { text.red().to_string() }
+            }
+            _ => String::new(),
+        };
+        println!(
+            "{:<id_width$}  {:<16}  {:>7}  {:>9}  {}{}",
+            row.id,
+            row.scanned_at.format("%Y-%m-%d %H:%M"),
+            row.files,
+            row.lines,
+            row.languages.join(", "),
+            delta
+        );
+        previous_lines = Some(row.lines);
+    }
+
+    Ok(())
+}
+
+/// Execute `history show`: the summary of one snapshot
+pub fn show(id: &str, format: OutputFormat) -> Result<()> {
+    let root = project_root()?;
+    let snapshot = find_snapshot(&history_path(&root), id)?;
+    let map = snapshot.load()?;
+
+    match format {
+        OutputFormat::Text => {
+            println!(
+                "{}",
+                format!("Snapshot {} (scanned {})", snapshot.id, map.scanned_at.format("%Y-%m-%d %H:%M UTC")).dimmed()
+            );
+            println!();
+            print_human_summary(&map, false);
+        }
+        _ => print_structured(&build_summary(&map, false), format)?,
+    }
+
+    Ok(())
+}
+
+/// One line of `history list`
+#[derive(Serialize)]
+struct HistoryRow {
+    id: String,
+    scanned_at: DateTime<Utc>,
+    files: usize,
+    lines: usize,
+    languages: Vec<String>,
+}
+
+impl HistoryRow {
+    fn new(id: &str, map: &SystemMap) -> Self {
+        Self {
+            id: id.to_string(),
+            scanned_at: map.scanned_at,
+            files: map.meta.indexed_files,
+            lines: total_lines(&map.tree),
+            languages: map.project_type.languages.clone(),
+        }
+    }
+}
+
+fn total_lines(node: &FileNode) -> usize {
+    match node {
+        FileNode::File { lines, .. } => lines.unwrap_or(0),
+        FileNode::Directory { children, .. } => children.iter().map(total_lines).sum(),
+        FileNode::Collapsed { .. } => 0,
+    }
+}
+
+fn project_root() -> Result<PathBuf> {
+    let cwd = env::current_dir()?;
+    find_sysmap_root(&cwd).ok_or_else(|| anyhow::anyhow!(
+        "No sysmap found. Run 'sysmap init' first."
+    ))
+}
@@ -0,0 +1,373 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use chrono::{NaiveDate, Utc};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config::{find_sysmap_root, map_path};
+use crate::deps::path_key;
+use crate::map::{FileNode, SystemMap};
+use crate::output::{print_structured, OutputFormat, SCHEMA_VERSION};
+use crate::scanner::{estimate_complexity, read_git_history};
+
+/// Output format of the hotspot report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Aligned, human-readable tables
+    #[default]
+    Text,
+    /// Pretty-printed JSON
+    Json,
+    /// Comma-separated values, one row per file or directory
+    Csv,
+}
+
+/// Execute the hotspots command
+pub fn execute(since: Option<String>, limit: usize, complexity: bool, format: ReportFormat) -> Result<()> {
+    let cwd = env::current_dir()?;
+
+    let root = find_sysmap_root(&cwd)
+        .ok_or_else(|| anyhow::anyhow!(
+            "No sysmap found. Run 'sysmap init' first."
+        ))?;
+
+    let map = SystemMap::load(&map_path(&root))?;
+
+    // A different window needs the log read again; otherwise the map has it
+    let (window_days, commits) = match since.as_deref().map(parse_since).transpose()? {
+        Some(days) => {
+            let history = read_git_history(&root, days)
+                .ok_or_else(|| anyhow::anyhow!("Can't read git history: not a git repository, or git isn't installed"))?;
+            let files = history.files.into_iter().map(|(path, info)| (path, info.commits)).collect();
+            (days, Commits { files, dirs: history.dirs })
+        }
+        None => {
+            let Some(git) = &map.git else {
+                bail!("The map has no git history. Run 'sysmap update' inside a git repository, or pass --since.");
+            };
+            let mut commits = Commits::default();
+            collect_commits(&map.tree, &mut commits);
+            (git.window_days, commits)
+        }
+    };
+
+    let report = build_report(&root, &map.tree, &commits, window_days, complexity);
+
+    match format {
+        ReportFormat::Text => print_report(&report, limit),
+        ReportFormat::Json => print_structured(&report.limited(limit), OutputFormat::Json)?,
+        ReportFormat::Csv => print!("{}", report.to_csv(limit)),
+    }
+
+    Ok(())
+}
+
+/// Commits within the window, per file and (counting each commit once) per directory
+#[derive(Default)]
+struct Commits {
+    files: HashMap<String, usize>,
+    dirs: HashMap<String, usize>,
+}
+
+/// Files and directories ranked by churn times size (or complexity)
+#[derive(Serialize)]
+pub struct HotspotReport {
+    schema_version: u32,
+    window_days: u32,
+    /// What churn is multiplied by: `code` lines or `complexity`
+    metric: &'static str,
+    files: Vec<Hotspot>,
+    directories: Vec<Hotspot>,
+}
+
+/// One ranked file or directory
+#[derive(Debug, Clone, Serialize)]
+pub struct Hotspot {
+    path: String,
+    /// Distinct commits within the window
+    commits: usize,
+    /// Lines of code
+    code: usize,
+    /// Estimated cyclomatic complexity, when requested
+    complexity: Option<usize>,
+    score: usize,
+}
+
+impl HotspotReport {
+    fn limited(&self, limit: usize) -> Self {
+        Self {
+            schema_version: self.schema_version,
+            window_days: self.window_days,
+            metric: self.metric,
+            files: self.files.iter().take(limit).cloned().collect(),
+            directories: self.directories.iter().take(limit).cloned().collect(),
+        }
+    }
+
+    fn to_csv(&self, limit: usize) -> String {
+        let mut out = String::from("kind,path,commits,code,complexity,score\n");
+        let rows = [("file", &self.files), ("directory", &self.directories)];
+        for (kind, hotspots) in rows {
+            for hotspot in hotspots.iter().take(limit) {
+                let complexity = hotspot.complexity.map(|c| c.to_string()).unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "{},{},{},{},{},{}",
+                    kind,
+                    csv_field(&hotspot.path),
+                    hotspot.commits,
+                    hotspot.code,
+                    complexity,
+                    hotspot.score
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Rank the files with commits in the window, and the directories holding them.
+///
+/// A file scores its commits times its lines of code (or its complexity);
+/// a directory scores the sum of its files, but counts each commit touching
+/// them once. Generated, vendored and binary files are left out.
+fn build_report(
+    root: &Path,
+    tree: &FileNode,
+    commits: &Commits,
+    window_days: u32,
+    complexity: bool,
+) -> HotspotReport {
+    let mut files = Vec::new();
+    collect_files(tree, &mut |node| {
+        let FileNode::File { path, lines, counts, language, is_binary: false, .. } = node else {
+            return;
+        };
+        if node.is_generated_or_vendored() {
+            return;
+        }
+        let key = path_key(path);
+        let churn = commits.files.get(&key).copied().unwrap_or(0);
+        let code = counts.map(|c| c.code).or(*lines).unwrap_or(0);
+        if churn == 0 || code == 0 {
+            return;
+        }
+
+        let estimate = complexity
+            .then(|| fs::read_to_string(root.join(path)).ok())
+            .flatten()
+            .and_then(|text| estimate_complexity(&text, language.as_deref()));
+        let weight = if complexity { estimate.unwrap_or(0) } else { code };
+        files.push(Hotspot {
+            path: key,
+            commits: churn,
+            code,
+            complexity: estimate,
+            score: churn * weight,
+        });
+    });
+    files.retain(|f| f.score > 0);
+
+    // Every directory above a file, the root aside, gets its totals
+    let mut dirs: BTreeMap<String, Hotspot> = BTreeMap::new();
+    for file in &files {
+        let mut dir = Path::new(&file.path).parent();
+        while let Some(path) = dir.filter(|d| !d.as_os_str().is_empty()) {
+            let key = path_key(path);
+            let entry = dirs.entry(key.clone()).or_insert_with(|| Hotspot {
+                commits: commits.dirs.get(&key).copied().unwrap_or(0),
+                path: key,
+                code: 0,
+                complexity: complexity.then_some(0),
+                score: 0,
+            });
+            entry.code += file.code;
+            entry.complexity = entry.complexity.map(|c| c + file.complexity.unwrap_or(0));
+            entry.score += file.score;
+            dir = path.parent();
+        }
+    }
+
+    let rank = |hotspots: &mut Vec<Hotspot>| {
+        hotspots.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    };
+    let mut directories: Vec<Hotspot> = dirs.into_values().collect();
+    rank(&mut files);
+    rank(&mut directories);
+
+    HotspotReport {
+        schema_version: SCHEMA_VERSION,
+        window_days,
+        metric: if complexity { "complexity" } else { "code" },
+        files,
+        directories,
+    }
+}
+
+fn print_report(report: &HotspotReport, limit: usize) {
+    if report.files.is_empty() {
+        println!("{}", format!("No commits to indexed files in the last {} days.", report.window_days).yellow());
+        return;
+    }
+
+    let metric = if report.metric == "complexity" { "complexity" } else { "lines of code" };
+    println!("{}", format!("Hotspots (last {} days, commits × {}):", report.window_days, metric).bold());
+
+    for (title, hotspots, suffix) in [("Files:", &report.files, ""), ("Directories:", &report.directories, "/")] {
+        if hotspots.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{}", title.bold());
+        let complexity = report.metric == "complexity";
+        println!(
+            "  {:>8}  {:>7}  {:>7}{}  {}",
+            "score".dimmed(),
+            "commits".dimmed(),
+            "code".dimmed(),
+            if complexity { format!("  {:>10}", "complexity".dimmed()) } else { String::new() },
+            "path".dimmed()
+        );
+        for hotspot in hotspots.iter().take(limit) {
+            println!(
+                "  {:>8}  {:>7}  {:>7}{}  {}{}",
+                hotspot.score.to_string().yellow(),
+                hotspot.commits,
+                hotspot.code,
+                match hotspot.complexity {
+                    Some(c) if complexity => format!("  {:>10}", c),
+                    _ => String::new(),
+                },
+                hotspot.path,
+                suffix
+            );
+        }
+        if hotspots.len() > limit {
+            println!("  {}", format!("... {} more (--limit to show them)", hotspots.len() - limit).dimmed());
+        }
+    }
+}
+
+/// Commits within the window recorded on each file and directory of the map
+fn collect_commits(node: &FileNode, commits: &mut Commits) {
+    match node {
+        FileNode::File { path, git: Some(git), .. } => {
+            commits.files.insert(path_key(path), git.commits);
+        }
+        FileNode::Directory { path, children, churn, .. } => {
+            if let Some(churn) = churn {
+                commits.dirs.insert(path_key(path), *churn);
+            }
+            for child in children {
+                collect_commits(child, commits);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_files<'a>(node: &'a FileNode, on_file: &mut dyn FnMut(&'a FileNode)) {
+    match node {
+        FileNode::File { .. } => on_file(node),
+        FileNode::Directory { children, .. } => {
+            for child in children {
+                collect_files(child, on_file);
+            }
+        }
+        FileNode::Collapsed { .. } => {}
+    }
+}
+
+/// Parse a `--since` window into days: `30d`, `6w`, `3m` (30-day months),
+/// `1y`, or a `YYYY-MM-DD` date
+fn parse_since(text: &str) -> Result<u32> {
+    let text = text.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        let days = (Utc::now().date_naive() - date).num_days();
+        if days < 0 {
+            bail!("--since date {} is in the future", text);
+        }
+        return Ok(days as u32);
+    }
+
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let Ok(number) = number.parse::<u32>() else {
+        bail!("Invalid --since '{}': expected e.g. 30d, 6w, 3m, 1y or 2025-01-31", text);
+    };
+    let days_per_unit = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "d" | "day" | "days" => 1,
+        "w" | "week" | "weeks" => 7,
+        "m" | "month" | "months" => 30,
+        "y" | "year" | "years" => 365,
+        _ => bail!("Invalid --since unit '{}': use d, w, m or y", unit),
+    };
+    Ok(number.saturating_mul(days_per_unit))
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("30d").unwrap(), 30);
+        assert_eq!(parse_since("6w").unwrap(), 42);
+        assert_eq!(parse_since("2 months").unwrap(), 60);
+        assert_eq!(parse_since("1y").unwrap(), 365);
+        let date = (Utc::now().date_naive() - chrono::Duration::days(10)).format("%Y-%m-%d").to_string();
+        assert_eq!(parse_since(&date).unwrap(), 10);
+        assert!(parse_since("soon").is_err());
+        assert!(parse_since("3 fortnights").is_err());
+    }
+
+    #[test]
+    fn test_csv_output() {
+        let report = HotspotReport {
+            schema_version: SCHEMA_VERSION,
+            window_days: 30,
+            metric: "code",
+            files: vec![Hotspot { path: "src/a,b.rs".to_string(), commits: 3, code: 10, complexity: None, score: 30 }],
+            directories: vec![Hotspot { path: "src".to_string(), commits: 3, code: 10, complexity: None, score: 30 }],
+        };
+        assert_eq!(
+            report.to_csv(10),
+            "kind,path,commits,code,complexity,score\nfile,\"src/a,b.rs\",3,10,,30\ndirectory,src,3,10,,30\n"
+        );
+    }
+
+    #[test]
+    fn test_directories_count_each_commit_once() {
+        let tree = FileNode::Directory {
+            name: "demo".to_string(),
+            path: PathBuf::new(),
+            children: vec![FileNode::test_file("crates/a/lib.rs", 10), FileNode::test_file("crates/b/lib.rs", 20)],
+            churn: None,
+        };
+        // One commit touching both files
+        let commits = Commits {
+            files: [("crates/a/lib.rs", 1), ("crates/b/lib.rs", 1)].map(|(p, c)| (p.to_string(), c)).into(),
+            dirs: [("", 1), ("crates", 1), ("crates/a", 1), ("crates/b", 1)].map(|(p, c)| (p.to_string(), c)).into(),
+        };
+
+        let report = build_report(Path::new("/work/demo"), &tree, &commits, 30, false);
+        let crates = report.directories.iter().find(|d| d.path == "crates").unwrap();
+        assert_eq!((crates.commits, crates.code, crates.score), (1, 30, 30));
+    }
+}
@@ -3,11 +3,25 @@ use std::path::PathBuf;
 use anyhow::{bail, Context, Result};
 use colored::Colorize;
 
-use crate::config::{ensure_sysmap_dir, is_initialized, map_path, sysmap_dir};
-use crate::scanner::{scan_directory, ScannerConfig};
+use crate::config::{
+    cache_path, config_path, ensure_sysmap_dir, history_path, is_initialized, load_config, load_template,
+    map_path, parse_config, render_config, sysmap_dir,
+};
+use crate::map::{record_snapshot, SystemMap};
+use crate::scanner::{scan_incremental, ScannerConfig};
 
 /// Execute the init command
-pub fn execute(path: PathBuf, force: bool, verbosity: u8) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    path: PathBuf,
+    force: bool,
+    respect_gitignore: bool,
+    read_git: bool,
+    write_config: bool,
+    template: Option<String>,
+    threads: usize,
+    verbosity: u8,
+) -> Result<()> {
     // Resolve the path
     let root = path
         .canonicalize()
@@ -21,6 +35,19 @@ pub fn execute(path: PathBuf, force: bool, verbosity: u8) -> Result<()> {
         );
     }
 
+    // A template replaces any existing config, so it also drives this scan
+    let template_contents = template.as_deref().map(load_template).transpose()?;
+    let settings = match (&template, &template_contents) {
+        (Some(name), Some(contents)) => parse_config(contents)
+            .with_context(|| format!("Invalid template: {}", name))?,
+        _ => load_config(&root)?,
+    };
+    let mut config = ScannerConfig::from_settings(&settings)?;
+    config.show_progress = verbosity > 0;
+    config.respect_gitignore &= respect_gitignore;
+    config.read_git &= read_git;
+    config.threads = threads;
+
     if verbosity > 0 {
         println!(
             "{} {}",
@@ -29,17 +56,37 @@ pub fn execute(path: PathBuf, force: bool, verbosity: u8) -> Result<()> {
         );
     }
 
-    // Scan the directory
-    let mut config = ScannerConfig::default();
-    config.show_progress = verbosity > 0;
-    let map = scan_directory(&root, &config)?;
-
-    // Create .sysmap directory
+    // Create .sysmap directory first, so creating it doesn't make the root
+    // look changed since the scan
     ensure_sysmap_dir(&root)?;
 
-    // Save the map
+    // Scan the directory
+    let output = scan_incremental(&root, &config, None)?;
+    let map = output.map;
+
+    // Save the map, keeping any map it replaces
     let map_file = map_path(&root);
+    if let Ok(previous) = SystemMap::load(&map_file) {
+        record_snapshot(&history_path(&root), &previous, &map, &settings.history)?;
+    }
     map.save(&map_file)?;
+    output.cache.save(&cache_path(&root))?;
+
+    // Write config.toml, keeping any existing one unless a template was requested
+    let config_file = config_path(&root);
+    let config_written = if let Some(contents) = &template_contents {
+        std::fs::write(&config_file, contents)
+            .with_context(|| format!("Failed to write {}", config_file.display()))?;
+        true
+    } else if write_config && !config_file.exists() {
+        // --no-gitignore and --no-git apply to this run only, as with update
+        let contents = render_config(&map, &config.patterns, &settings)?;
+        std::fs::write(&config_file, contents)
+            .with_context(|| format!("Failed to write {}", config_file.display()))?;
+        true
+    } else {
+        false
+    };
 
     // Print summary (unless quiet)
     if verbosity > 0 {
@@ -60,6 +107,26 @@ pub fn execute(path: PathBuf, force: bool, verbosity: u8) -> Result<()> {
             map.meta.indexed_files.to_string().green(),
             (map.meta.total_files - map.meta.indexed_files).to_string().dimmed()
         );
+
+        if !map.dependencies.packages.is_empty() {
+            let mut sources: Vec<&str> = map.dependencies.packages
+                .iter()
+                .map(|p| p.source.as_str())
+                .collect();
+            sources.dedup();
+            println!("  {} Dependencies: {} found in {}",
+                "├─".dimmed(),
+                map.dependencies.packages.len().to_string().yellow(),
+                sources.join(", ")
+            );
+        }
+
+        if map.meta.ignored_entries > 0 {
+            println!("  {} Ignored: {} entries (gitignore rules)",
+                "├─".dimmed(),
+                map.meta.ignored_entries.to_string().dimmed()
+            );
+        }
         
         if !map.patterns_matched.is_empty() {
             let pattern_summary: Vec<String> = map.patterns_matched
@@ -83,6 +150,13 @@ pub fn execute(path: PathBuf, force: bool, verbosity: u8) -> Result<()> {
             "Map saved to".green(),
             map_file.display().to_string().dimmed()
         );
+        if config_written {
+            println!(
+                "{} {}",
+                "Config saved to".green(),
+                config_file.display().to_string().dimmed()
+            );
+        }
         println!(
             "Run '{}' to view project overview.",
             "sysmap summary".cyan()
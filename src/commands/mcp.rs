@@ -0,0 +1,25 @@
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::config::find_sysmap_root;
+use crate::mcp::Server;
+
+/// Execute the mcp command
+pub fn execute(path: Option<PathBuf>) -> Result<()> {
+    let start = match path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let root = find_sysmap_root(&start)
+        .ok_or_else(|| anyhow::anyhow!(
+            "No sysmap found. Run 'sysmap init' first."
+        ))?;
+
+    // Stdout carries the protocol; nothing else may be printed there
+    let mut server = Server::new(root);
+    server.serve(io::stdin().lock(), io::stdout().lock())
+}
@@ -3,3 +3,10 @@ pub mod summary;
 pub mod tree;
 pub mod update;
 pub mod find;
+pub mod deps;
+pub mod watch;
+pub mod mcp;
+pub mod context;
+pub mod hotspots;
+pub mod diff;
+pub mod history;
@@ -1,14 +1,20 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::env;
+use std::path::Path;
 
 use anyhow::Result;
 use colored::Colorize;
 
-use crate::config::{find_sysmap_root, map_path};
-use crate::map::{FileNode, SystemMap};
+use crate::commands::update::load_current_map;
+use crate::config::find_sysmap_root;
+use crate::deps::path_key;
+use crate::map::{DependencyScope, FileNode, LineCounts, Package, SubProject, SystemMap};
+use crate::output::{print_structured, OutputFormat};
+use crate::scanner::node_churn;
 
 /// Execute the summary command
-pub fn execute(json: bool, yaml: bool) -> Result<()> {
+pub fn execute(format: OutputFormat, include_generated: bool, auto_update: bool) -> Result<()> {
     let cwd = env::current_dir()?;
     
     let root = find_sysmap_root(&cwd)
@@ -16,20 +22,17 @@ pub fn execute(json: bool, yaml: bool) -> Result<()> {
             "No sysmap found. Run 'sysmap init' first."
         ))?;
 
-    let map = SystemMap::load(&map_path(&root))?;
+    let map = load_current_map(&root, auto_update)?;
 
-    if json {
-        print_json_summary(&map)?;
-    } else if yaml {
-        print_yaml_summary(&map)?;
-    } else {
-        print_human_summary(&map);
+    match format {
+        OutputFormat::Text => print_human_summary(&map, include_generated),
+        _ => print_structured(&build_summary(&map, include_generated), format)?,
     }
 
     Ok(())
 }
 
-fn print_human_summary(map: &SystemMap) {
+pub fn print_human_summary(map: &SystemMap, include_generated: bool) {
     // Project header
     let project_name = map.root
         .file_name()
@@ -61,7 +64,8 @@ fn print_human_summary(map: &SystemMap) {
     println!();
 
     // Analyze structure
-    let analysis = analyze_tree(&map.tree);
+    let (tree, excluded) = stats_tree(&map.tree, include_generated);
+    let analysis = analyze_tree(&tree);
 
     // Structure section
     println!("{}", "Structure:".bold());
@@ -72,7 +76,11 @@ fn print_human_summary(map: &SystemMap) {
             format!("{}/", dir_name),
             stats.file_count.to_string().yellow(),
             stats.primary_language.as_deref().unwrap_or(""),
-            stats.lines.map(|l| format!(" ({} lines)", l)).unwrap_or_default()
+            match stats.lines {
+                Some(l) if stats.counts.code > 0 => format!(" ({} lines, {} code)", l, stats.counts.code),
+                Some(l) => format!(" ({} lines)", l),
+                None => String::new(),
+            }
         );
     }
 
@@ -92,6 +100,64 @@ fn print_human_summary(map: &SystemMap) {
         );
     }
 
+    // One line per package of a monorepo or workspace
+    let packages = package_stats(&tree, &map.projects);
+    if packages.len() > 1 || packages.iter().any(|p| !p.project.path.as_os_str().is_empty()) {
+        println!();
+        println!("{}", "Packages:".bold());
+        let path_width = packages.iter().map(|p| display_dir(&p.project.path).len()).max().unwrap_or(0);
+        let name_width = packages.iter().map(|p| p.project.name.as_deref().unwrap_or("-").len()).max().unwrap_or(0);
+        for package in &packages {
+            let project = package.project;
+            let mut kind = project.project_type.languages.join(", ");
+            if let Some(framework) = &project.project_type.framework {
+                kind = format!("{} ({})", kind, framework);
+            }
+            let role = if !project.members.is_empty() {
+                format!("  workspace, {} members", project.members.len())
+            } else if let Some(workspace) = &project.workspace {
+                format!("  member of {}", display_dir(workspace))
+            } else {
+                String::new()
+            };
+            println!("  {:<path_width$}  {:<name_width$}  {} files, {} code  {}{}",
+                display_dir(&project.path),
+                project.name.as_deref().unwrap_or("-"),
+                package.files.to_string().yellow(),
+                package.counts.code,
+                kind,
+                role.dimmed()
+            );
+        }
+    }
+
+    // Directories with the most commits in the git window
+    let hot = hot_areas(&tree, HOT_AREAS);
+    if let Some(git) = map.git.as_ref().filter(|_| !hot.is_empty()) {
+        println!();
+        println!("{}", format!("Hot areas (last {} days):", git.window_days).bold());
+        let width = hot.iter().map(|(path, _)| display_dir(path).len()).max().unwrap_or(0);
+        for (path, churn) in &hot {
+            let noun = if *churn == 1 { "commit" } else { "commits" };
+            println!("  {:<width$}  {} {}", display_dir(path), churn.to_string().yellow(), noun);
+        }
+    }
+
+    // Code, comment and blank lines per language
+    if !analysis.lines_by_language.is_empty() {
+        println!();
+        println!("{}", "Lines by language:".bold());
+        for (language, files, counts) in &analysis.lines_by_language {
+            println!("  {:<14} {:>8} code {:>8} comment {:>8} blank  {}",
+                language,
+                counts.code.to_string().yellow(),
+                counts.comment,
+                counts.blank,
+                format!("({} files)", files).dimmed()
+            );
+        }
+    }
+
     // Entry points
     if !analysis.entry_points.is_empty() {
         println!();
@@ -113,11 +179,20 @@ fn print_human_summary(map: &SystemMap) {
         }
     }
 
-    // Dependencies
-    if !analysis.dependencies.is_empty() {
+    // Dependencies, grouped by the manifest that declares them
+    for (source, packages) in group_by_source(&map.dependencies.packages) {
         println!();
-        println!("{}", "Dependencies:".bold());
-        println!("  {}", analysis.dependencies.join(", "));
+        println!("{}", format!("Dependencies (from {}):", source).bold());
+        for (label, scope) in [("", DependencyScope::Runtime), ("dev: ", DependencyScope::Dev), ("build: ", DependencyScope::Build)] {
+            let names: Vec<&str> = packages
+                .iter()
+                .filter(|p| p.scope == scope)
+                .map(|p| p.name.as_str())
+                .collect();
+            if !names.is_empty() {
+                println!("  {}{}", label.dimmed(), names.join(", "));
+            }
+        }
     }
     
     // File types and purposes (metadata)
@@ -138,6 +213,16 @@ fn print_human_summary(map: &SystemMap) {
         }
     }
 
+    // Files left out of the statistics above
+    if excluded.generated + excluded.vendored > 0 {
+        println!();
+        println!("{}", "Excluded from stats:".bold().dimmed());
+        println!("  {}",
+            format!("{} generated, {} vendored files (--include-generated to count them)",
+                excluded.generated, excluded.vendored).dimmed()
+        );
+    }
+
     // Collapsed directories
     if !map.patterns_matched.is_empty() {
         println!();
@@ -152,10 +237,14 @@ fn print_human_summary(map: &SystemMap) {
     }
 }
 
-fn print_json_summary(map: &SystemMap) -> Result<()> {
-    let analysis = analyze_tree(&map.tree);
+/// The summary structure shared by the JSON and YAML output
+pub fn build_summary(map: &SystemMap, include_generated: bool) -> serde_json::Value {
+    let (tree, excluded) = stats_tree(&map.tree, include_generated);
+    let analysis = analyze_tree(&tree);
+    let packages = package_stats(&tree, &map.projects);
+    let hot = hot_areas(&tree, HOT_AREAS);
     
-    let summary = serde_json::json!({
+    serde_json::json!({
         "name": map.root.file_name().map(|n| n.to_string_lossy().to_string()),
         "languages": map.project_type.languages,
         "framework": map.project_type.framework,
@@ -165,6 +254,9 @@ fn print_json_summary(map: &SystemMap) -> Result<()> {
                     "path": name,
                     "files": stats.file_count,
                     "lines": stats.lines,
+                    "code": stats.counts.code,
+                    "comment": stats.counts.comment,
+                    "blank": stats.counts.blank,
                     "language": stats.primary_language
                 })
             }).collect::<Vec<_>>(),
@@ -176,6 +268,39 @@ fn print_json_summary(map: &SystemMap) -> Result<()> {
             }).collect::<Vec<_>>(),
             "config_files": analysis.config_files
         },
+        "lines_by_language": analysis.lines_by_language.iter().map(|(language, files, counts)| {
+            serde_json::json!({
+                "language": language,
+                "files": files,
+                "code": counts.code,
+                "comment": counts.comment,
+                "blank": counts.blank
+            })
+        }).collect::<Vec<_>>(),
+        "packages": packages.iter().map(|package| {
+            let project = package.project;
+            serde_json::json!({
+                "path": path_key(&project.path),
+                "name": project.name,
+                "languages": project.project_type.languages,
+                "framework": project.project_type.framework,
+                "workspace": project.workspace.as_deref().map(path_key),
+                "members": project.members.iter().map(|m| path_key(m)).collect::<Vec<_>>(),
+                "files": package.files,
+                "code": package.counts.code,
+                "comment": package.counts.comment,
+                "blank": package.counts.blank
+            })
+        }).collect::<Vec<_>>(),
+        "hot_areas": map.git.as_ref().map(|git| serde_json::json!({
+            "window_days": git.window_days,
+            "directories": hot.iter().map(|(path, churn)| {
+                serde_json::json!({
+                    "path": path_key(path),
+                    "commits": churn
+                })
+            }).collect::<Vec<_>>()
+        })),
         "entry_points": analysis.entry_points,
         "key_directories": analysis.key_dirs.iter().map(|(path, contents)| {
             serde_json::json!({
@@ -184,7 +309,11 @@ fn print_json_summary(map: &SystemMap) -> Result<()> {
             })
         }).collect::<Vec<_>>(),
         "dependencies": {
-            "packages": analysis.dependencies
+            "sources": group_by_source(&map.dependencies.packages)
+                .iter()
+                .map(|(source, _)| *source)
+                .collect::<Vec<_>>(),
+            "packages": map.dependencies.packages
         },
         "collapsed": map.patterns_matched.iter().map(|p| {
             serde_json::json!({
@@ -198,26 +327,63 @@ fn print_json_summary(map: &SystemMap) -> Result<()> {
             "total_files": map.meta.total_files,
             "last_updated": map.scanned_at,
             "purposes_found": analysis.purposes_found,
-            "file_languages": analysis.languages_found
+            "file_languages": analysis.languages_found,
+            "excluded": {
+                "generated": excluded.generated,
+                "vendored": excluded.vendored
+            }
         }
-    });
-
-    println!("{}", serde_json::to_string_pretty(&summary)?);
-    Ok(())
+    })
 }
 
-fn print_yaml_summary(map: &SystemMap) -> Result<()> {
-    // For now, just output JSON - YAML support can be added later
-    // This keeps dependencies minimal for MVP
-    eprintln!("{}", "YAML output not yet implemented, showing JSON:".yellow());
-    print_json_summary(map)
+// ============ Analysis helpers ============
+
+/// Generated and vendored files left out of the statistics
+#[derive(Default)]
+struct Excluded {
+    generated: usize,
+    vendored: usize,
 }
 
-// ============ Analysis helpers ============
+/// The tree the statistics are computed from: without generated and
+/// vendored files, unless they are included
+fn stats_tree(tree: &FileNode, include_generated: bool) -> (Cow<'_, FileNode>, Excluded) {
+    fn prune(node: &FileNode, excluded: &mut Excluded) -> Option<FileNode> {
+        match node {
+            FileNode::File { vendored: true, .. } => {
+                excluded.vendored += 1;
+                None
+            }
+            FileNode::File { generated: true, .. } => {
+                excluded.generated += 1;
+                None
+            }
+            FileNode::Directory { name, path, children, churn } => {
+                let children: Vec<FileNode> = children.iter().filter_map(|child| prune(child, excluded)).collect();
+                // Churn counts distinct commits, so it stays as recorded
+                Some(FileNode::Directory {
+                    name: name.clone(),
+                    path: path.clone(),
+                    churn: *churn,
+                    children,
+                })
+            }
+            _ => Some(node.clone()),
+        }
+    }
+
+    let mut excluded = Excluded::default();
+    if include_generated {
+        return (Cow::Borrowed(tree), excluded);
+    }
+    let pruned = prune(tree, &mut excluded).unwrap_or_else(|| tree.clone());
+    (Cow::Owned(pruned), excluded)
+}
 
 struct DirStats {
     file_count: usize,
     lines: Option<usize>,
+    counts: LineCounts,
     primary_language: Option<String>,
 }
 
@@ -227,9 +393,10 @@ struct TreeAnalysis {
     config_files: Vec<String>,
     entry_points: Vec<String>,
     key_dirs: Vec<(String, Vec<String>)>,
-    dependencies: Vec<String>,
     purposes_found: Vec<String>,
     languages_found: Vec<String>,
+    /// Language, file count and line counts, most code first
+    lines_by_language: Vec<(String, usize, LineCounts)>,
 }
 
 fn analyze_tree(tree: &FileNode) -> TreeAnalysis {
@@ -239,9 +406,9 @@ fn analyze_tree(tree: &FileNode) -> TreeAnalysis {
         config_files: Vec::new(),
         entry_points: Vec::new(),
         key_dirs: Vec::new(),
-        dependencies: Vec::new(),
         purposes_found: Vec::new(),
         languages_found: Vec::new(),
+        lines_by_language: Vec::new(),
     };
 
     // Known source directory names
@@ -252,6 +419,8 @@ fn analyze_tree(tree: &FileNode) -> TreeAnalysis {
     
     // Collect all purposes and languages
     collect_metadata(tree, &mut analysis.purposes_found, &mut analysis.languages_found);
+    analysis.lines_by_language = count_by_language(tree);
+    find_entry_points(tree, &mut analysis.entry_points);
 
     if let FileNode::Directory { children, .. } = tree {
         for child in children {
@@ -276,30 +445,16 @@ fn analyze_tree(tree: &FileNode) -> TreeAnalysis {
                         analysis.test_dirs.push((name.clone(), stats));
                     }
                 }
-                FileNode::File { name, purpose, .. } => {
+                FileNode::File { name, .. } => {
                     // Check for config files
                     let is_config = config_names.iter().any(|c| name.contains(c))
-                        || name.split('.').last()
+                        || name.split('.').next_back()
                             .map(|ext| config_extensions.contains(&ext))
                             .unwrap_or(false);
                     
                     if is_config && !name.starts_with('.') {
                         analysis.config_files.push(name.clone());
                     }
-
-                    // Check for entry points
-                    if purpose.as_deref() == Some("entry") {
-                        analysis.entry_points.push(name.clone());
-                    }
-
-                    // Parse dependency files
-                    if name == "pyproject.toml" || name == "requirements.txt" {
-                        // Would parse dependencies here in a full implementation
-                    } else if name == "package.json" {
-                        // Would parse dependencies here
-                    } else if name == "Cargo.toml" {
-                        // Would parse dependencies here
-                    }
                 }
                 _ => {}
             }
@@ -312,12 +467,120 @@ fn analyze_tree(tree: &FileNode) -> TreeAnalysis {
     analysis
 }
 
+/// A project of the map with the files that belong to it
+struct PackageStats<'a> {
+    project: &'a SubProject,
+    files: usize,
+    counts: LineCounts,
+}
+
+/// Files and line counts per project; each file counts towards the innermost
+/// project containing it, so a workspace root doesn't repeat its members
+fn package_stats<'a>(tree: &FileNode, projects: &'a [SubProject]) -> Vec<PackageStats<'a>> {
+    fn visit(node: &FileNode, projects: &[SubProject], stats: &mut [PackageStats]) {
+        match node {
+            FileNode::File { path, counts, .. } => {
+                let owner = projects
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| path.starts_with(&p.path))
+                    .max_by_key(|(_, p)| p.path.components().count());
+                if let Some((i, _)) = owner {
+                    stats[i].files += 1;
+                    if let Some(c) = counts {
+                        stats[i].counts += *c;
+                    }
+                }
+            }
+            FileNode::Directory { children, .. } => {
+                for child in children {
+                    visit(child, projects, stats);
+                }
+            }
+            FileNode::Collapsed { .. } => {}
+        }
+    }
+
+    let mut stats: Vec<PackageStats> = projects
+        .iter()
+        .map(|project| PackageStats { project, files: 0, counts: LineCounts::default() })
+        .collect();
+    visit(tree, projects, &mut stats);
+    stats
+}
+
+/// Hot areas listed by the summary
+const HOT_AREAS: usize = 5;
+
+/// The directories with the most churn, busiest first.
+///
+/// The root is skipped, and so is any directory whose churn all comes from
+/// one subdirectory, which would repeat that subdirectory's entry.
+fn hot_areas(tree: &FileNode, limit: usize) -> Vec<(&Path, usize)> {
+    fn visit<'a>(node: &'a FileNode, depth: usize, hot: &mut Vec<(&'a Path, usize)>) {
+        let FileNode::Directory { path, children, churn: Some(churn), .. } = node else {
+            return;
+        };
+        let passthrough = children
+            .iter()
+            .any(|c| matches!(c, FileNode::Directory { .. }) && node_churn(c) == *churn);
+        if depth > 0 && *churn > 0 && !passthrough {
+            hot.push((path, *churn));
+        }
+        for child in children {
+            visit(child, depth + 1, hot);
+        }
+    }
+
+    let mut hot = Vec::new();
+    visit(tree, 0, &mut hot);
+    hot.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    hot.truncate(limit);
+    hot
+}
+
+/// A directory for display, `.` for the project root
+fn display_dir(path: &Path) -> String {
+    match path_key(path) {
+        key if key.is_empty() => ".".to_string(),
+        key => format!("{}/", key),
+    }
+}
+
+/// Paths of every entry point in the tree, in tree order
+fn find_entry_points(node: &FileNode, entries: &mut Vec<String>) {
+    match node {
+        FileNode::File { path, purpose, .. } if purpose.as_deref() == Some("entry") => {
+            entries.push(path_key(path));
+        }
+        FileNode::Directory { children, .. } => {
+            for child in children {
+                find_entry_points(child, entries);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Packages grouped by manifest, in the order the manifests were found
+fn group_by_source(packages: &[Package]) -> Vec<(&str, Vec<&Package>)> {
+    let mut groups: Vec<(&str, Vec<&Package>)> = Vec::new();
+    for package in packages {
+        match groups.iter_mut().find(|(source, _)| *source == package.source) {
+            Some((_, list)) => list.push(package),
+            None => groups.push((&package.source, vec![package])),
+        }
+    }
+    groups
+}
+
 fn compute_dir_stats(children: &[FileNode]) -> DirStats {
     let mut file_count = 0;
     let mut total_lines = 0;
+    let mut counts = LineCounts::default();
     let mut lang_counts: HashMap<String, usize> = HashMap::new();
 
-    count_recursive(children, &mut file_count, &mut total_lines, &mut lang_counts);
+    count_recursive(children, &mut file_count, &mut total_lines, &mut counts, &mut lang_counts);
 
     let primary_language = lang_counts
         .into_iter()
@@ -327,6 +590,7 @@ fn compute_dir_stats(children: &[FileNode]) -> DirStats {
     DirStats {
         file_count,
         lines: if total_lines > 0 { Some(total_lines) } else { None },
+        counts,
         primary_language,
     }
 }
@@ -335,21 +599,25 @@ fn count_recursive(
     nodes: &[FileNode],
     file_count: &mut usize,
     total_lines: &mut usize,
+    line_counts: &mut LineCounts,
     lang_counts: &mut HashMap<String, usize>,
 ) {
     for node in nodes {
         match node {
-            FileNode::File { lines, language, .. } => {
+            FileNode::File { lines, counts, language, .. } => {
                 *file_count += 1;
                 if let Some(l) = lines {
                     *total_lines += l;
                 }
+                if let Some(c) = counts {
+                    *line_counts += *c;
+                }
                 if let Some(lang) = language {
                     *lang_counts.entry(lang.clone()).or_insert(0) += 1;
                 }
             }
             FileNode::Directory { children, .. } => {
-                count_recursive(children, file_count, total_lines, lang_counts);
+                count_recursive(children, file_count, total_lines, line_counts, lang_counts);
             }
             FileNode::Collapsed { file_count: fc, .. } => {
                 // Don't count collapsed files in detail
@@ -359,6 +627,35 @@ fn count_recursive(
     }
 }
 
+/// Files and line counts per language, most code first
+fn count_by_language(tree: &FileNode) -> Vec<(String, usize, LineCounts)> {
+    fn visit(node: &FileNode, totals: &mut HashMap<String, (usize, LineCounts)>) {
+        match node {
+            FileNode::File { counts: Some(counts), language: Some(language), .. } => {
+                let entry = totals.entry(language.clone()).or_default();
+                entry.0 += 1;
+                entry.1 += *counts;
+            }
+            FileNode::Directory { children, .. } => {
+                for child in children {
+                    visit(child, totals);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut totals = HashMap::new();
+    visit(tree, &mut totals);
+
+    let mut result: Vec<(String, usize, LineCounts)> = totals
+        .into_iter()
+        .map(|(language, (files, counts))| (language, files, counts))
+        .collect();
+    result.sort_by(|a, b| b.2.code.cmp(&a.2.code).then_with(|| a.0.cmp(&b.0)));
+    result
+}
+
 fn find_key_subdirs(children: &[FileNode]) -> Vec<(String, Vec<String>)> {
     let mut result = Vec::new();
     
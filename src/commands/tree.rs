@@ -1,15 +1,27 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use colored::Colorize;
+use serde::Serialize;
 
+use crate::cli::LineFilter;
 use crate::colors::{colorize_language, colorize_purpose};
-use crate::config::{find_sysmap_root, map_path};
-use crate::map::{FileNode, SystemMap};
+use crate::commands::update::load_current_map;
+use crate::config::find_sysmap_root;
+use crate::map::FileNode;
+use crate::output::{print_structured, Entry, OutputFormat, SCHEMA_VERSION};
 
 /// Execute the tree command
-pub fn execute(path: Option<PathBuf>, depth: usize, show_all: bool) -> Result<()> {
+pub fn execute(
+    path: Option<PathBuf>,
+    depth: usize,
+    show_all: bool,
+    show_counts: bool,
+    lines: &LineFilter,
+    format: OutputFormat,
+    auto_update: bool,
+) -> Result<()> {
     let cwd = env::current_dir()?;
     
     let root = find_sysmap_root(&cwd)
@@ -17,7 +29,7 @@ pub fn execute(path: Option<PathBuf>, depth: usize, show_all: bool) -> Result<()
             "No sysmap found. Run 'sysmap init' first."
         ))?;
 
-    let map = SystemMap::load(&map_path(&root))?;
+    let map = load_current_map(&root, auto_update)?;
 
     // Find the starting node
     let start_node = if let Some(ref subpath) = path {
@@ -27,16 +39,110 @@ pub fn execute(path: Option<PathBuf>, depth: usize, show_all: bool) -> Result<()
         &map.tree
     };
 
-    print_tree(start_node, "", true, 0, depth, show_all);
+    // Hide files outside the line filter, and directories left empty
+    let filtered;
+    let start_node = if lines.is_active() {
+        filtered = filter_tree(start_node, lines)
+            .ok_or_else(|| anyhow::anyhow!("No files match the line count filters"))?;
+        &filtered
+    } else {
+        start_node
+    };
+
+    match format {
+        OutputFormat::Text => print_tree(start_node, "", true, 0, depth, show_all, show_counts),
+        _ => print_structured(&TreeOutput::new(start_node, depth), format)?,
+    }
 
     Ok(())
 }
 
-fn find_node<'a>(node: &'a FileNode, target: &PathBuf) -> Option<&'a FileNode> {
+/// Structured output of the tree command
+#[derive(Serialize)]
+pub struct TreeOutput {
+    schema_version: u32,
+    tree: TreeEntry,
+}
+
+impl TreeOutput {
+    /// The subtree under `node`, cut off past `depth`
+    pub fn new(node: &FileNode, depth: usize) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            tree: TreeEntry::prune(node, 0, depth),
+        }
+    }
+}
+
+/// A node of the pruned subtree
+#[derive(Serialize)]
+struct TreeEntry {
+    #[serde(flatten)]
+    entry: Entry,
+
+    /// Collapse reason and counts, for collapsed directories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dir_count: Option<usize>,
+
+    /// Children of directories within the requested depth
+    #[serde(skip_serializing_if = "Option::is_none")]
+    children: Option<Vec<TreeEntry>>,
+
+    /// Set on directories past the requested depth, with their item count
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<usize>,
+}
+
+impl TreeEntry {
+    /// Build the subtree, cutting off directories past `max_depth`
+    fn prune(node: &FileNode, current_depth: usize, max_depth: usize) -> Self {
+        let mut tree_entry = TreeEntry {
+            entry: Entry::from_node(node),
+            reason: None,
+            file_count: None,
+            dir_count: None,
+            children: None,
+            truncated: false,
+            items: None,
+        };
+
+        match node {
+            FileNode::Directory { children, .. } if current_depth >= max_depth => {
+                tree_entry.truncated = true;
+                tree_entry.items = Some(count_files(children));
+            }
+            FileNode::Directory { children, .. } => {
+                tree_entry.children = Some(
+                    children
+                        .iter()
+                        .map(|child| TreeEntry::prune(child, current_depth + 1, max_depth))
+                        .collect(),
+                );
+            }
+            FileNode::Collapsed { reason, file_count, dir_count, .. } => {
+                tree_entry.reason = Some(reason.clone());
+                tree_entry.file_count = Some(*file_count);
+                tree_entry.dir_count = Some(*dir_count);
+            }
+            FileNode::File { .. } => {}
+        }
+
+        tree_entry
+    }
+}
+
+/// Look up a node by its path relative to the root
+pub fn find_node<'a>(node: &'a FileNode, target: &Path) -> Option<&'a FileNode> {
     // Normalize the target path
     let target_str = target.to_string_lossy();
     let target_parts: Vec<&str> = target_str
-        .split(|c| c == '/' || c == '\\')
+        .split(['/', '\\'])
         .filter(|s| !s.is_empty())
         .collect();
 
@@ -68,15 +174,56 @@ fn find_node_recursive<'a>(node: &'a FileNode, parts: &[&str], index: usize) ->
     None
 }
 
-fn print_tree(node: &FileNode, prefix: &str, is_last: bool, current_depth: usize, max_depth: usize, show_all: bool) {
+/// Copy of a subtree keeping only files that pass the filter
+fn filter_tree(node: &FileNode, lines: &LineFilter) -> Option<FileNode> {
+    match node {
+        FileNode::File { counts, .. } => lines.matches(counts.as_ref()).then(|| node.clone()),
+        FileNode::Directory { name, path, children, churn } => {
+            let children: Vec<FileNode> = children
+                .iter()
+                .filter_map(|child| filter_tree(child, lines))
+                .collect();
+            (!children.is_empty()).then(|| FileNode::Directory {
+                name: name.clone(),
+                path: path.clone(),
+                children,
+                churn: *churn,
+            })
+        }
+        FileNode::Collapsed { .. } => None,
+    }
+}
+
+fn print_tree(
+    node: &FileNode,
+    prefix: &str,
+    is_last: bool,
+    current_depth: usize,
+    max_depth: usize,
+    show_all: bool,
+    show_counts: bool,
+) {
     let connector = if is_last { "└── " } else { "├── " };
     
     match node {
-        FileNode::File { name, lines, purpose, language, .. } => {
+        FileNode::File { name, lines, counts, is_binary, generated, vendored, purpose, language, .. } => {
             let mut info_parts = Vec::new();
-            
-            if let Some(l) = lines {
-                info_parts.push(format!("{} lines", l));
+
+            if *is_binary {
+                info_parts.push("binary".to_string());
+            }
+            if *vendored {
+                info_parts.push("vendored".to_string());
+            } else if *generated {
+                info_parts.push("generated".to_string());
+            }
+
+            match (counts, lines) {
+                (Some(c), _) if show_counts => {
+                    info_parts.push(format!("{} code, {} comment, {} blank", c.code, c.comment, c.blank));
+                }
+                (_, Some(l)) => info_parts.push(format!("{} lines", l)),
+                _ => {}
             }
             
             if let Some(p) = purpose {
@@ -142,7 +289,7 @@ fn print_tree(node: &FileNode, prefix: &str, is_last: bool, current_depth: usize
 
             for (i, child) in sorted_children.iter().enumerate() {
                 let child_is_last = i == sorted_children.len() - 1;
-                print_tree(child, &child_prefix, child_is_last, current_depth + 1, max_depth, show_all);
+                print_tree(child, &child_prefix, child_is_last, current_depth + 1, max_depth, show_all, show_counts);
             }
         }
         
@@ -1,13 +1,17 @@
 use std::env;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use colored::Colorize;
 
-use crate::config::{find_sysmap_root, map_path};
-use crate::scanner::{scan_directory, ScannerConfig};
+use crate::config::{cache_path, find_sysmap_root, history_path, load_config, map_path, SysmapConfig};
+use crate::map::{record_snapshot, SystemMap};
+use crate::scanner::{
+    check_staleness, scan_incremental, ChangeSummary, ChecksumCache, PreviousScan, ScanOutput, ScannerConfig,
+};
 
 /// Execute the update command
-pub fn execute(full: bool, verbosity: u8) -> Result<()> {
+pub fn execute(full: bool, respect_gitignore: bool, read_git: bool, threads: usize, verbosity: u8) -> Result<()> {
     let cwd = env::current_dir()?;
     
     let root = find_sysmap_root(&cwd)
@@ -15,29 +19,63 @@ pub fn execute(full: bool, verbosity: u8) -> Result<()> {
             "No sysmap found. Run 'sysmap init' first."
         ))?;
 
+    let previous_map = SystemMap::load(&map_path(&root)).ok();
+    let previous_cache = ChecksumCache::load(&cache_path(&root));
+
     if verbosity > 0 {
         if full {
             println!("{} full rebuild...", "Starting".green().bold());
+        } else if previous_map.is_none() || previous_cache.is_none() {
+            println!("{} map (no cache found, rebuilding)...", "Updating".green().bold());
         } else {
             println!("{} map...", "Updating".green().bold());
         }
     }
 
-    let mut config = ScannerConfig::default();
+    let settings = load_config(&root)?;
+    let mut config = ScannerConfig::from_settings(&settings)?;
     config.show_progress = verbosity > 0;
-    let map = scan_directory(&root, &config)?;
-
-    // Save updated map
-    let map_file = map_path(&root);
-    map.save(&map_file)?;
+    config.respect_gitignore &= respect_gitignore;
+    config.read_git &= read_git;
+    config.threads = threads;
+    // Reuse the previous scan unless a full rebuild was requested
+    let reuse = if full { None } else { previous_cache.as_ref() };
+    let (output, snapshot) = rescan(&root, &settings, &config, previous_map.as_ref(), reuse)?;
+    let map = &output.map;
 
     if verbosity > 0 {
         println!();
-        println!("  {} Files: {} scanned, {} indexed",
-            "├─".dimmed(),
-            map.meta.total_files.to_string().yellow(),
-            map.meta.indexed_files.to_string().green()
-        );
+        match &previous_cache {
+            Some(old) => {
+                let changes = ChangeSummary::between(old, &output.cache);
+                if changes.is_empty() {
+                    println!("  {} No changes", "├─".dimmed());
+                } else {
+                    print_changes("Added", &changes.added, verbosity);
+                    print_changes("Removed", &changes.removed, verbosity);
+                    print_changes("Modified", &changes.modified, verbosity);
+                }
+                if verbosity > 1 {
+                    println!("  {} Reused: {} unchanged files",
+                        "├─".dimmed(),
+                        output.reused.to_string().dimmed()
+                    );
+                }
+            }
+            None => {
+                println!("  {} Files: {} scanned, {} indexed",
+                    "├─".dimmed(),
+                    map.meta.total_files.to_string().yellow(),
+                    map.meta.indexed_files.to_string().green()
+                );
+            }
+        }
+        if let Some(id) = &snapshot {
+            println!("  {} Previous map saved as snapshot {}",
+                "├─".dimmed(),
+                id.dimmed()
+            );
+        }
         println!("  {} Updated in {}ms",
             "└─".dimmed(),
             map.meta.scan_time_ms
@@ -46,3 +84,75 @@ pub fn execute(full: bool, verbosity: u8) -> Result<()> {
 
     Ok(())
 }
+
+/// Load the saved map, first checking that it still matches the project.
+///
+/// A stale map is brought up to date with an incremental update when
+/// `auto_update` is set; otherwise it is used as it is, with a warning.
+pub fn load_current_map(root: &Path, auto_update: bool) -> Result<SystemMap> {
+    let map = SystemMap::load(&map_path(root))?;
+    let cache = ChecksumCache::load(&cache_path(root));
+    let Some(staleness) = check_staleness(root, &map, cache.as_ref()) else {
+        return Ok(map);
+    };
+
+    if !auto_update {
+        eprintln!(
+            "{} The map may be out of date: {}. Run 'sysmap update', or pass --auto-update.",
+            "warning:".yellow().bold(),
+            staleness
+        );
+        return Ok(map);
+    }
+
+    let settings = load_config(root)?;
+    let mut config = ScannerConfig::from_settings(&settings)?;
+    config.show_progress = false;
+    let (output, _) = rescan(root, &settings, &config, Some(&map), cache.as_ref())?;
+    Ok(output.map)
+}
+
+/// Scan the project, reusing the previous map and cache (when both exist)
+/// for unchanged files, then snapshot the map being replaced and save the
+/// new map and cache. Returns the scan and the id of any snapshot taken.
+fn rescan(
+    root: &Path,
+    settings: &SysmapConfig,
+    config: &ScannerConfig,
+    previous_map: Option<&SystemMap>,
+    previous_cache: Option<&ChecksumCache>,
+) -> Result<(ScanOutput, Option<String>)> {
+    let previous = match (previous_map, previous_cache) {
+        (Some(map), Some(cache)) => Some(PreviousScan::new(map, cache)),
+        _ => None,
+    };
+    let output = scan_incremental(root, config, previous.as_ref())?;
+
+    let snapshot = match previous_map {
+        Some(previous) => record_snapshot(&history_path(root), previous, &output.map, &settings.history)?,
+        None => None,
+    };
+    output.map.save(&map_path(root))?;
+    output.cache.save(&cache_path(root))?;
+
+    Ok((output, snapshot))
+}
+
+/// Print a change count, listing the paths when there are only a few (or in verbose mode)
+fn print_changes(label: &str, paths: &[PathBuf], verbosity: u8) {
+    let noun = if paths.len() == 1 { "file" } else { "files" };
+    let listed = if !paths.is_empty() && (paths.len() <= 3 || verbosity > 1) {
+        let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+        format!(" ({})", names.join(", "))
+    } else {
+        String::new()
+    };
+
+    println!("  {} {}: {} {}{}",
+        "├─".dimmed(),
+        label,
+        paths.len().to_string().yellow(),
+        noun,
+        listed.dimmed()
+    );
+}
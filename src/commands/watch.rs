@@ -0,0 +1,242 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use colored::Colorize;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::config::{cache_path, find_sysmap_root, load_config, map_path};
+use crate::map::SystemMap;
+use crate::scanner::{scan_incremental, ChecksumCache, LiveMap, PatchSummary, PreviousScan, ScannerConfig};
+
+/// A burst of events is applied at the latest after this many debounce periods
+const MAX_DEBOUNCE_PERIODS: u32 = 10;
+
+enum Message {
+    Fs(notify::Result<Event>),
+    Stop,
+}
+
+/// Execute the watch command
+pub fn execute(debounce_ms: u64, interval_secs: u64, respect_gitignore: bool, verbosity: u8) -> Result<()> {
+    let cwd = env::current_dir()?;
+
+    let root = find_sysmap_root(&cwd)
+        .ok_or_else(|| anyhow::anyhow!(
+            "No sysmap found. Run 'sysmap init' first."
+        ))?;
+
+    let map_file = map_path(&root);
+    let cache_file = cache_path(&root);
+
+    let settings = load_config(&root)?;
+    let mut config = ScannerConfig::from_settings(&settings)?;
+    config.show_progress = false;
+    config.respect_gitignore &= respect_gitignore;
+
+    // Catch up with anything that changed while nobody was watching
+    let previous_map = SystemMap::load(&map_file).ok();
+    let previous_cache = ChecksumCache::load(&cache_file);
+    let previous = match (&previous_map, &previous_cache) {
+        (Some(map), Some(cache)) => Some(PreviousScan::new(map, cache)),
+        _ => None,
+    };
+    let output = scan_incremental(&root, &config, previous.as_ref())?;
+    output.map.save(&map_file)?;
+    output.cache.save(&cache_file)?;
+
+    let mut live = LiveMap::new(output.map, output.cache, config)?;
+
+    let (tx, rx) = mpsc::channel();
+    let stop = tx.clone();
+    ctrlc::set_handler(move || {
+        let _ = stop.send(Message::Stop);
+    })
+    .context("Failed to install Ctrl-C handler")?;
+
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(Message::Fs(event));
+    })?;
+    watcher.watch(live.root(), RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", live.root().display()))?;
+    let mut watched = BTreeSet::from([live.root().to_path_buf()]);
+    sync_watches(&mut watcher, &live, &mut watched);
+
+    if verbosity > 0 {
+        println!("{} {} (Ctrl-C to stop)", "Watching".green().bold(), live.root().display());
+        println!("  {} {} files indexed",
+            "├─".dimmed(),
+            live.map.meta.indexed_files.to_string().green()
+        );
+        println!("  {} Saving every {}s",
+            "└─".dimmed(),
+            interval_secs
+        );
+    }
+
+    let debounce = Duration::from_millis(debounce_ms);
+    let interval = Duration::from_secs(interval_secs);
+
+    let mut pending: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut first_event = Instant::now();
+    let mut last_event = Instant::now();
+    let mut last_save = Instant::now();
+    let mut dirty = false;
+
+    loop {
+        let timeout = if pending.is_empty() { interval } else { debounce };
+
+        match rx.recv_timeout(timeout) {
+            Ok(Message::Fs(Ok(event))) => {
+                if !event.kind.is_access() && !event.paths.is_empty() {
+                    if pending.is_empty() {
+                        first_event = Instant::now();
+                    }
+                    last_event = Instant::now();
+                    pending.extend(event.paths);
+                }
+            }
+            Ok(Message::Fs(Err(e))) => {
+                eprintln!("{} {}", "warning:".yellow().bold(), e);
+            }
+            Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        // Wait for a burst of events to settle, but not forever
+        let settled = last_event.elapsed() >= debounce
+            || first_event.elapsed() >= debounce * MAX_DEBOUNCE_PERIODS;
+        if !pending.is_empty() && settled && apply_batch(&mut live, &mut pending, verbosity) {
+            dirty = true;
+            // Anything created in a new directory before its watch was added
+            // is picked up by scanning it once more
+            pending.extend(sync_watches(&mut watcher, &live, &mut watched));
+        }
+
+        if dirty && last_save.elapsed() >= interval {
+            save(&live, &map_file, &cache_file)?;
+            dirty = false;
+            last_save = Instant::now();
+            if verbosity > 1 {
+                println!("{} {}", timestamp(), "Saved map".dimmed());
+            }
+        }
+    }
+
+    if !pending.is_empty() {
+        dirty |= apply_batch(&mut live, &mut pending, verbosity);
+    }
+    if dirty {
+        save(&live, &map_file, &cache_file)?;
+    }
+    if verbosity > 0 {
+        println!();
+        println!("{} watching, map saved", "Stopped".green().bold());
+    }
+
+    Ok(())
+}
+
+/// Watch each directory the map descends into, without recursing into
+/// collapsed, ignored or hidden trees. Returns the newly watched directories.
+fn sync_watches(watcher: &mut RecommendedWatcher, live: &LiveMap, watched: &mut BTreeSet<PathBuf>) -> Vec<PathBuf> {
+    let wanted = live.mapped_dirs();
+
+    // Watches on deleted directories are already gone
+    for dir in watched.difference(&wanted) {
+        let _ = watcher.unwatch(dir);
+    }
+    watched.retain(|dir| wanted.contains(dir));
+
+    let mut added = Vec::new();
+    for dir in wanted {
+        if watched.contains(&dir) {
+            continue;
+        }
+        match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                watched.insert(dir.clone());
+                added.push(dir);
+            }
+            // The directory may have been removed again in the meantime
+            Err(e) if dir.is_dir() => {
+                eprintln!("{} failed to watch {}: {}", "warning:".yellow().bold(), dir.display(), e);
+            }
+            Err(_) => {}
+        }
+    }
+    added
+}
+
+/// Patch the map with a batch of changed paths, returning whether it changed
+fn apply_batch(live: &mut LiveMap, pending: &mut BTreeSet<PathBuf>, verbosity: u8) -> bool {
+    let paths: Vec<PathBuf> = std::mem::take(pending).into_iter().collect();
+
+    match live.apply(&paths) {
+        Ok(Some(summary)) => {
+            if verbosity > 0 && (!summary.changes.is_empty() || verbosity > 1) {
+                print_patch(&summary, verbosity);
+            }
+            true
+        }
+        Ok(None) => false,
+        Err(e) => {
+            eprintln!("{} {}", "warning:".yellow().bold(), e);
+            false
+        }
+    }
+}
+
+/// One line per batch: counts, then the rescanned directories
+fn print_patch(summary: &PatchSummary, verbosity: u8) {
+    let changes = &summary.changes;
+    let mut parts = Vec::new();
+    if !changes.added.is_empty() {
+        parts.push(format!("+{}", changes.added.len()).green().to_string());
+    }
+    if !changes.removed.is_empty() {
+        parts.push(format!("-{}", changes.removed.len()).red().to_string());
+    }
+    if !changes.modified.is_empty() {
+        parts.push(format!("~{}", changes.modified.len()).yellow().to_string());
+    }
+    if parts.is_empty() {
+        parts.push("no changes".dimmed().to_string());
+    }
+
+    let dirs: Vec<String> = summary.rescanned.iter().map(|d| display_dir(d)).collect();
+    println!("{} {} {}",
+        timestamp(),
+        parts.join(" "),
+        format!("in {}", dirs.join(", ")).dimmed()
+    );
+
+    if verbosity > 1 {
+        for (label, paths) in [("+", &changes.added), ("-", &changes.removed), ("~", &changes.modified)] {
+            for path in paths {
+                println!("  {} {} {}", "├─".dimmed(), label, path.display());
+            }
+        }
+    }
+}
+
+fn display_dir(dir: &Path) -> String {
+    if dir.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        format!("{}/", dir.display())
+    }
+}
+
+fn timestamp() -> String {
+    Local::now().format("[%H:%M:%S]").to_string().dimmed().to_string()
+}
+
+fn save(live: &LiveMap, map_file: &Path, cache_file: &Path) -> Result<()> {
+    live.map.save(map_file)?;
+    live.cache.save(cache_file)
+}
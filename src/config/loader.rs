@@ -0,0 +1,405 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::patterns::{
+    parse_size, CollapsePattern, CollapseThresholds, PatternMatcher, PatternSet, ProjectPattern, PurposePattern,
+};
+
+use super::config_path;
+
+/// User configuration loaded from `.sysmap/config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SysmapConfig {
+    /// General scan settings
+    pub scan: ScanSettings,
+
+    /// Git history enrichment
+    pub git: GitSettings,
+
+    /// Snapshots of previous maps
+    pub history: HistorySettings,
+
+    /// Collapse directories past size and file-count thresholds
+    pub auto_collapse: AutoCollapseSettings,
+
+    /// Directories to collapse, keyed by pattern name
+    pub collapse: toml::Table,
+
+    /// File patterns to ignore entirely
+    pub ignore: toml::Table,
+
+    /// File patterns of generated code
+    pub generated: toml::Table,
+
+    /// Directory patterns of vendored third-party code
+    pub vendored: toml::Table,
+
+    /// Project type detection, keyed by language
+    pub project_types: toml::Table,
+
+    /// File purpose patterns (`"pattern" = "purpose"`)
+    pub purposes: toml::Table,
+}
+
+/// The `[scan]` section
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanSettings {
+    /// Whether to respect .gitignore, .ignore and git exclude files
+    pub respect_gitignore: bool,
+    /// Maximum depth to scan
+    pub max_depth: usize,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self {
+            respect_gitignore: true,
+            max_depth: 20,
+        }
+    }
+}
+
+/// The `[git]` section
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitSettings {
+    /// Whether to read commit history when the project is a git repository
+    pub enabled: bool,
+    /// Days of history counted as recent churn
+    pub window_days: u32,
+}
+
+impl Default for GitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_days: 90,
+        }
+    }
+}
+
+/// The `[history]` section
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistorySettings {
+    /// Whether `init` and `update` snapshot the map they replace
+    pub enabled: bool,
+    /// Most snapshots to keep
+    pub keep: usize,
+    /// Delete snapshots older than this many days
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            keep: 20,
+            max_age_days: None,
+        }
+    }
+}
+
+/// The `[auto_collapse]` section; every threshold is off unless set
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoCollapseSettings {
+    /// Collapse directories holding more files than this
+    pub max_files: Option<usize>,
+    /// Collapse directories larger than this, in bytes or with a unit (`"200MB"`)
+    pub max_bytes: Option<Size>,
+    /// Collapse directories where at least this percentage of files are not code
+    pub non_code_percent: Option<u8>,
+    /// Fewest files a directory needs before `non_code_percent` applies
+    pub min_files: Option<usize>,
+}
+
+/// A size in bytes, or a string with a unit
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Size {
+    Bytes(u64),
+    Text(String),
+}
+
+/// Files a directory needs before `non_code_percent` applies, unless configured
+const DEFAULT_MIN_FILES: usize = 20;
+
+/// A config entry: `false` disables a default, a table adds or overrides it
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Rule<T> {
+    Toggle(bool),
+    Define(T),
+}
+
+/// A `[collapse]` entry
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CollapseRule {
+    #[serde(rename = "match")]
+    match_name: Option<String>,
+    reason: Option<String>,
+    indicator: Option<String>,
+}
+
+/// An `[ignore]`, `[generated]` or `[vendored]` entry (currently carries no options)
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IgnoreRule {}
+
+/// A `[project_types.<language>]` entry
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProjectRule {
+    markers: Option<Vec<String>>,
+    framework_hints: Option<toml::Table>,
+}
+
+/// Load `.sysmap/config.toml`, falling back to defaults when it doesn't exist
+pub fn load_config(root: &Path) -> Result<SysmapConfig> {
+    let path = config_path(root);
+    if !path.exists() {
+        return Ok(SysmapConfig::default());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_config(&contents).with_context(|| format!("Invalid config: {}", path.display()))
+}
+
+/// Parse config.toml contents
+pub fn parse_config(contents: &str) -> Result<SysmapConfig> {
+    Ok(toml::from_str(contents)?)
+}
+
+impl SysmapConfig {
+    /// Merge the user rules over the built-in default patterns
+    pub fn patterns(&self) -> Result<PatternSet> {
+        let mut set = PatternSet::default();
+
+        for (key, value) in &self.collapse {
+            match parse_rule::<CollapseRule>("collapse", key, value)? {
+                Rule::Toggle(true) => {}
+                Rule::Toggle(false) => set.collapse.retain(|p| p.name != *key),
+                Rule::Define(rule) => {
+                    let existing = set.collapse.iter().position(|p| p.name == *key);
+                    let base = existing.map(|i| set.collapse.remove(i));
+                    let pattern = CollapsePattern {
+                        name: rule.match_name.unwrap_or_else(|| key.clone()),
+                        reason: rule
+                            .reason
+                            .or_else(|| base.as_ref().map(|b| b.reason.clone()))
+                            .unwrap_or_else(|| "Collapsed by config".to_string()),
+                        indicator: rule.indicator.or_else(|| base.and_then(|b| b.indicator)),
+                    };
+                    match existing {
+                        Some(i) => set.collapse.insert(i, pattern),
+                        None => set.collapse.push(pattern),
+                    }
+                }
+            }
+        }
+
+        merge_list("ignore", &self.ignore, &mut set.ignore)?;
+        merge_list("generated", &self.generated, &mut set.generated)?;
+        merge_list("vendored", &self.vendored, &mut set.vendored)?;
+
+        for (key, value) in &self.project_types {
+            match parse_rule::<ProjectRule>("project_types", key, value)? {
+                Rule::Toggle(true) => {}
+                Rule::Toggle(false) => set.project.retain(|p| p.language != *key),
+                Rule::Define(rule) => {
+                    let frameworks = rule
+                        .framework_hints
+                        .map(|hints| {
+                            hints
+                                .iter()
+                                .map(|(name, hint)| match hint.as_str() {
+                                    Some(hint) => Ok((name.clone(), hint.to_string())),
+                                    None => anyhow::bail!(
+                                        "[project_types.{}] framework hint '{}' must be a string",
+                                        key,
+                                        name
+                                    ),
+                                })
+                                .collect::<Result<Vec<_>>>()
+                        })
+                        .transpose()?;
+
+                    if let Some(existing) = set.project.iter_mut().find(|p| p.language == *key) {
+                        if let Some(markers) = rule.markers {
+                            existing.markers = markers;
+                        }
+                        if let Some(frameworks) = frameworks {
+                            existing.frameworks = frameworks;
+                        }
+                    } else {
+                        set.project.push(ProjectPattern {
+                            language: key.clone(),
+                            markers: rule.markers.unwrap_or_default(),
+                            frameworks: frameworks.unwrap_or_default(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // User purposes take priority over the defaults, in file order
+        let mut user_purposes = Vec::new();
+        for (key, value) in &self.purposes {
+            match parse_rule::<String>("purposes", key, value)? {
+                Rule::Toggle(true) => {}
+                Rule::Toggle(false) => set.purpose.retain(|p| p.pattern != *key),
+                Rule::Define(purpose) => {
+                    set.purpose.retain(|p| p.pattern != *key);
+                    user_purposes.push(PurposePattern {
+                        pattern: key.clone(),
+                        purpose,
+                    });
+                }
+            }
+        }
+        user_purposes.append(&mut set.purpose);
+        set.purpose = user_purposes;
+
+        set.thresholds = self.auto_collapse.thresholds()?;
+
+        // Report malformed globs now rather than when scanning
+        PatternMatcher::new(&set)?;
+        Ok(set)
+    }
+}
+
+impl AutoCollapseSettings {
+    fn thresholds(&self) -> Result<CollapseThresholds> {
+        let max_bytes = match &self.max_bytes {
+            Some(Size::Bytes(bytes)) => Some(*bytes),
+            Some(Size::Text(text)) => Some(parse_size(text).context("Invalid [auto_collapse] max_bytes")?),
+            None => None,
+        };
+        if let Some(percent) = self.non_code_percent.filter(|p| *p > 100) {
+            anyhow::bail!("Invalid [auto_collapse] non_code_percent {}: expected 0 to 100", percent);
+        }
+
+        Ok(CollapseThresholds {
+            max_files: self.max_files,
+            max_bytes,
+            non_code_percent: self.non_code_percent,
+            min_files: self.min_files.unwrap_or(DEFAULT_MIN_FILES),
+        })
+    }
+}
+
+/// Merge the entries of a section that is a plain list of patterns
+fn merge_list(section: &str, table: &toml::Table, patterns: &mut Vec<String>) -> Result<()> {
+    for (key, value) in table {
+        match parse_rule::<IgnoreRule>(section, key, value)? {
+            Rule::Toggle(false) => patterns.retain(|p| p != key),
+            Rule::Toggle(true) | Rule::Define(_) => {
+                if !patterns.contains(key) {
+                    patterns.push(key.clone());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_rule<T: DeserializeOwned>(section: &str, key: &str, value: &toml::Value) -> Result<Rule<T>> {
+    let expected = match section {
+        "purposes" => "a purpose string or `false`",
+        _ => "a table of options or `false`",
+    };
+    value.clone().try_into().map_err(|_| {
+        anyhow::anyhow!("Invalid entry '{}' in [{}]: expected {}", key, section, expected)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_over_defaults() {
+        let config = parse_config(
+            r#"
+[collapse]
+dist = false
+target = { indicator = "../Cargo.toml" }
+fixtures = { reason = "Test fixtures" }
+
+[ignore]
+"*.log" = {}
+"*.swp" = false
+
+[generated]
+"*.gen.ts" = {}
+"Cargo.lock" = false
+
+[vendored]
+"external" = {}
+
+[project_types.rust]
+markers = ["Cargo.toml", "rust-toolchain.toml"]
+
+[project_types.elixir]
+markers = ["mix.exs"]
+framework_hints = { phoenix = "phoenix" }
+
+[purposes]
+"cli.py" = "entry"
+"config.py" = false
+
+[auto_collapse]
+max_files = 5000
+max_bytes = "200MB"
+"#,
+        )
+        .unwrap();
+
+        let set = config.patterns().unwrap();
+
+        assert!(!set.collapse.iter().any(|p| p.name == "dist"));
+        let target = set.collapse.iter().find(|p| p.name == "target").unwrap();
+        assert_eq!(target.reason, "Rust build output");
+        assert_eq!(target.indicator.as_deref(), Some("../Cargo.toml"));
+        assert!(set.collapse.iter().any(|p| p.name == "fixtures" && p.reason == "Test fixtures"));
+
+        assert!(set.ignore.contains(&"*.log".to_string()));
+        assert!(!set.ignore.contains(&"*.swp".to_string()));
+        assert!(set.generated.contains(&"*.gen.ts".to_string()));
+        assert!(!set.generated.contains(&"Cargo.lock".to_string()));
+        assert!(set.vendored.contains(&"external".to_string()));
+
+        let rust = set.project.iter().find(|p| p.language == "rust").unwrap();
+        assert_eq!(rust.markers.len(), 2);
+        assert!(!rust.frameworks.is_empty());
+        let elixir = set.project.iter().find(|p| p.language == "elixir").unwrap();
+        assert_eq!(elixir.frameworks, vec![("phoenix".to_string(), "phoenix".to_string())]);
+
+        assert_eq!(set.purpose[0].pattern, "cli.py");
+        assert!(!set.purpose.iter().any(|p| p.pattern == "config.py"));
+
+        assert_eq!(set.thresholds.max_files, Some(5000));
+        assert_eq!(set.thresholds.max_bytes, Some(200 << 20));
+        assert_eq!(set.thresholds.non_code_percent, None);
+    }
+
+    #[test]
+    fn test_invalid_entry() {
+        let config = parse_config("[collapse]\ndist = { reasn = \"typo\" }\n").unwrap();
+        assert!(config.patterns().is_err());
+
+        let config = parse_config("[ignore]\n\"src/[a\" = {}\n").unwrap();
+        assert!(config.patterns().is_err());
+
+        let config = parse_config("[auto_collapse]\nmax_bytes = \"12 parsecs\"\n").unwrap();
+        assert!(config.patterns().is_err());
+    }
+}
@@ -1,3 +1,7 @@
+mod loader;
+mod template;
 mod types;
 
+pub use loader::*;
+pub use template::*;
 pub use types::*;
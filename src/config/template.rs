@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::map::{FileNode, SystemMap};
+use crate::deps::path_key;
+use crate::patterns::{PatternMatcher, PatternSet};
+
+use super::SysmapConfig;
+
+/// Environment variable pointing at a directory of config templates
+pub const TEMPLATE_DIR_ENV: &str = "SYSMAP_TEMPLATE_DIR";
+
+/// Render a commented config.toml listing the active patterns.
+///
+/// Entries this project actually uses (detected markers, matched collapse
+/// patterns, purposes seen) are written out; the remaining defaults are
+/// included as comments for reference.
+pub fn render_config(map: &SystemMap, patterns: &PatternSet, settings: &SysmapConfig) -> Result<String> {
+    let mut out = String::new();
+    let project_name = map
+        .root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string());
+
+    let _ = writeln!(out, "# sysmap configuration for {}", project_name);
+    let _ = writeln!(out, "# Generated by `sysmap init` on {}", map.scanned_at.format("%Y-%m-%d"));
+    let _ = writeln!(out, "#");
+    let _ = writeln!(out, "# Entries are merged over sysmap's built-in defaults: a table adds or");
+    let _ = writeln!(out, "# overrides an entry, and `false` disables one. Commented-out entries");
+    let _ = writeln!(out, "# are defaults that did not match anything in this project.");
+    out.push('\n');
+
+    let (scan, git, history) = (&settings.scan, &settings.git, &settings.history);
+    out.push_str("[scan]\n");
+    let _ = writeln!(out, "respect_gitignore = {}", scan.respect_gitignore);
+    let _ = writeln!(out, "max_depth = {}", scan.max_depth);
+    out.push('\n');
+
+    out.push_str("[git]\n");
+    let _ = writeln!(out, "enabled = {}", git.enabled);
+    let _ = writeln!(out, "window_days = {}", git.window_days);
+    out.push('\n');
+
+    out.push_str("[history]\n");
+    let _ = writeln!(out, "enabled = {}", history.enabled);
+    let _ = writeln!(out, "keep = {}", history.keep);
+    let max_age = format!("max_age_days = {}", history.max_age_days.unwrap_or(365));
+    push_entry(&mut out, &max_age, history.max_age_days.is_some());
+    out.push('\n');
+
+    // Thresholds are off by default, so suggest values when none are set
+    let thresholds = &patterns.thresholds;
+    out.push_str("[auto_collapse]\n");
+    let entries = [
+        ("max_files", thresholds.max_files.map(|n| n.to_string()), "5000"),
+        ("max_bytes", thresholds.max_bytes.map(|n| n.to_string()), "\"200MB\""),
+        ("non_code_percent", thresholds.non_code_percent.map(|n| n.to_string()), "90"),
+    ];
+    for (name, value, example) in entries {
+        let active = value.is_some();
+        let line = format!("{} = {}", name, value.as_deref().unwrap_or(example));
+        push_entry(&mut out, &line, active);
+    }
+    let line = format!("min_files = {}", thresholds.min_files);
+    push_entry(&mut out, &line, thresholds.non_code_percent.is_some());
+    out.push('\n');
+
+    // Collapse patterns
+    let matched: HashSet<&str> = map.patterns_matched.iter().map(|p| p.pattern.as_str()).collect();
+    out.push_str("[collapse]\n");
+    for pattern in &patterns.collapse {
+        let mut fields = vec![format!("reason = {}", quote(&pattern.reason))];
+        if let Some(indicator) = &pattern.indicator {
+            fields.push(format!("indicator = {}", quote(indicator)));
+        }
+        let line = format!("{} = {{ {} }}", key(&pattern.name), fields.join(", "));
+        push_entry(&mut out, &line, matched.contains(pattern.name.as_str()));
+    }
+    out.push('\n');
+
+    // Ignore patterns always apply, so list them all
+    out.push_str("[ignore]\n");
+    for pattern in &patterns.ignore {
+        let _ = writeln!(out, "{} = {{}}", key(pattern));
+    }
+    out.push('\n');
+
+    // Generated and vendored patterns only flag files, so list them all too
+    for (section, list) in [("generated", &patterns.generated), ("vendored", &patterns.vendored)] {
+        let _ = writeln!(out, "[{}]", section);
+        for pattern in list {
+            let _ = writeln!(out, "{} = {{}}", key(pattern));
+        }
+        out.push('\n');
+    }
+
+    // Project types
+    for pattern in &patterns.project {
+        let detected: Vec<&String> = pattern
+            .markers
+            .iter()
+            .filter(|m| map.project_type.detected_from.contains(m))
+            .collect();
+        let active = !detected.is_empty();
+
+        let mut lines = vec![format!("[project_types.{}]", key(&pattern.language))];
+        let markers: Vec<String> = pattern.markers.iter().map(|m| quote(m)).collect();
+        lines.push(format!("markers = [{}]", markers.join(", ")));
+        if !pattern.frameworks.is_empty() {
+            let hints: Vec<String> = pattern
+                .frameworks
+                .iter()
+                .map(|(name, hint)| format!("{} = {}", key(name), quote(hint)))
+                .collect();
+            lines.push(format!("framework_hints = {{ {} }}", hints.join(", ")));
+        }
+
+        if active {
+            let names: Vec<&str> = detected.iter().map(|m| m.as_str()).collect();
+            let _ = writeln!(out, "# Detected from: {}", names.join(", "));
+        }
+        for line in &lines {
+            push_entry(&mut out, line, active);
+        }
+        out.push('\n');
+    }
+
+    // Purposes
+    let mut seen = HashSet::new();
+    collect_purpose_patterns(&map.tree, &PatternMatcher::new(patterns)?, &mut seen);
+    out.push_str("[purposes]\n");
+    for pattern in &patterns.purpose {
+        let line = format!("{} = {}", key(&pattern.pattern), quote(&pattern.purpose));
+        push_entry(&mut out, &line, seen.contains(&pattern.pattern));
+    }
+
+    Ok(out)
+}
+
+/// Load a starter config by template name or path.
+///
+/// Names are looked up as `<name>.toml` in `$SYSMAP_TEMPLATE_DIR`, then in
+/// `$XDG_CONFIG_HOME/sysmap/templates` (or `~/.config/sysmap/templates`).
+pub fn load_template(name: &str) -> Result<String> {
+    let direct = Path::new(name);
+    if direct.is_file() {
+        return std::fs::read_to_string(direct)
+            .with_context(|| format!("Failed to read template: {}", direct.display()));
+    }
+
+    let candidates: Vec<PathBuf> = template_dirs()
+        .into_iter()
+        .map(|dir| dir.join(format!("{}.toml", name)))
+        .collect();
+
+    for path in &candidates {
+        if path.is_file() {
+            return std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read template: {}", path.display()));
+        }
+    }
+
+    let searched: Vec<String> = candidates.iter().map(|p| p.display().to_string()).collect();
+    bail!(
+        "Template '{}' not found (searched: {})",
+        name,
+        if searched.is_empty() { "no template directories".to_string() } else { searched.join(", ") }
+    )
+}
+
+fn template_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os(TEMPLATE_DIR_ENV) {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        dirs.push(PathBuf::from(dir).join("sysmap").join("templates"));
+    } else if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        dirs.push(PathBuf::from(home).join(".config").join("sysmap").join("templates"));
+    }
+    dirs
+}
+
+fn collect_purpose_patterns(node: &FileNode, patterns: &PatternMatcher, seen: &mut HashSet<String>) {
+    match node {
+        FileNode::File { name, path, purpose: Some(_), .. } => {
+            if let Some(pattern) = patterns.match_purpose(name, &path_key(path)) {
+                seen.insert(pattern.pattern.clone());
+            }
+        }
+        FileNode::Directory { children, .. } => {
+            for child in children {
+                collect_purpose_patterns(child, patterns, seen);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn push_entry(out: &mut String, line: &str, active: bool) {
+    if !active {
+        out.push_str("# ");
+    }
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// Quote a TOML string value
+fn quote(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// Format a TOML key, quoting it unless it's a valid bare key
+fn key(name: &str) -> String {
+    let bare = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        name.to_string()
+    } else {
+        quote(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_config;
+    use crate::map::MatchedPattern;
+
+    #[test]
+    fn test_rendered_config_round_trips() {
+        let mut map = SystemMap::new(PathBuf::from("/tmp/demo"));
+        map.project_type.detected_from.push("Cargo.toml".to_string());
+        map.patterns_matched.push(MatchedPattern {
+            pattern: "target".to_string(),
+            path: PathBuf::from("target"),
+            files_collapsed: 10,
+            dirs_collapsed: 2,
+        });
+
+        let patterns = PatternSet::default();
+        let rendered = render_config(&map, &patterns, &SysmapConfig::default()).unwrap();
+
+        assert!(rendered.contains("\ntarget = { reason = \"Rust build output\" }\n"));
+        assert!(rendered.contains("\n# node_modules = "));
+        assert!(rendered.contains("\n[project_types.rust]\n"));
+        assert!(rendered.contains("\n# [project_types.python]\n"));
+        assert!(rendered.contains("\n# max_files = 5000\n"));
+
+        let parsed = parse_config(&rendered).unwrap();
+        let merged = parsed.patterns().unwrap();
+        assert_eq!(merged.collapse.len(), patterns.collapse.len());
+        assert_eq!(merged.ignore, patterns.ignore);
+    }
+}
@@ -7,10 +7,18 @@ pub const SYSMAP_DIR: &str = ".sysmap";
 /// Name of the map file
 pub const MAP_FILE: &str = "map.json";
 
-/// Name of the config file (for future use)
-#[allow(dead_code)]
+/// Name of the config file
 pub const CONFIG_FILE: &str = "config.toml";
 
+/// Name of the incremental update cache directory
+pub const CACHE_DIR: &str = "cache";
+
+/// Name of the file stamp cache inside the cache directory
+pub const CHECKSUMS_FILE: &str = "checksums";
+
+/// Name of the map snapshot directory
+pub const HISTORY_DIR: &str = "history";
+
 /// Find the sysmap root directory by looking for .sysmap folder
 pub fn find_sysmap_root(start: &Path) -> Option<PathBuf> {
     let mut current = start.to_path_buf();
@@ -37,12 +45,21 @@ pub fn map_path(root: &Path) -> PathBuf {
     sysmap_dir(root).join(MAP_FILE)
 }
 
-/// Get the path to the config.toml file (for future use)
-#[allow(dead_code)]
+/// Get the path to the config.toml file
 pub fn config_path(root: &Path) -> PathBuf {
     sysmap_dir(root).join(CONFIG_FILE)
 }
 
+/// Get the path to the incremental update checksums file
+pub fn cache_path(root: &Path) -> PathBuf {
+    sysmap_dir(root).join(CACHE_DIR).join(CHECKSUMS_FILE)
+}
+
+/// Get the path to the map snapshot directory
+pub fn history_path(root: &Path) -> PathBuf {
+    sysmap_dir(root).join(HISTORY_DIR)
+}
+
 /// Ensure the .sysmap directory exists
 pub fn ensure_sysmap_dir(root: &Path) -> Result<PathBuf> {
     let dir = sysmap_dir(root);
@@ -51,6 +68,20 @@ pub fn ensure_sysmap_dir(root: &Path) -> Result<PathBuf> {
     Ok(dir)
 }
 
+/// Write a file by renaming a temporary sibling over it, so readers never
+/// see a partially written file
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    std::fs::write(&tmp, contents)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
 /// Check if a sysmap has been initialized in the given directory
 pub fn is_initialized(root: &Path) -> bool {
     map_path(root).exists()
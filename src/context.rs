@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::commands::summary::build_summary;
+use crate::deps::path_key;
+use crate::map::{DependencyScope, FileNode, SystemMap};
+
+/// Deepest tree rendering tried
+const MAX_TREE_DEPTH: usize = 6;
+
+/// Most files considered for the key files section
+const MAX_KEY_FILES: usize = 25;
+
+/// Most imports named per key file
+const MAX_LISTED_IMPORTS: usize = 4;
+
+/// Approximate token count of a piece of text (about four characters per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// A prompt-ready document built to fit a token budget, with the plan used
+#[derive(Debug, Serialize)]
+pub struct ContextPack {
+    pub budget: usize,
+    pub estimated_tokens: usize,
+    pub plan: Vec<SectionPlan>,
+    pub document: String,
+}
+
+/// How one section was fitted into the budget
+#[derive(Debug, Serialize)]
+pub struct SectionPlan {
+    pub section: &'static str,
+    pub status: SectionStatus,
+    /// Tokens spent on the section as included
+    pub tokens: usize,
+    /// Tokens the complete section would take
+    pub full_tokens: usize,
+    /// What was kept, e.g. "depth 2" or "10 of 25 files"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Share of the budget the section may use before leftovers are handed out
+    pub share_percent: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionStatus {
+    Full,
+    Truncated,
+    Dropped,
+    /// Nothing to show (e.g. no packages declared)
+    Empty,
+}
+
+/// One way of rendering a section; the first variant is the complete one
+struct Variant {
+    text: String,
+    detail: Option<String>,
+}
+
+impl Variant {
+    /// Tokens including the blank line separating sections
+    fn cost(&self) -> usize {
+        estimate_tokens(&self.text) + 1
+    }
+}
+
+struct Section {
+    name: &'static str,
+    share_percent: usize,
+    variants: Vec<Variant>,
+}
+
+/// Assemble the context document for a map, dropping or truncating sections
+/// (lowest priority last) to stay under `budget` tokens.
+///
+/// Sections first get their share of the budget, in priority order; whatever
+/// is left over then goes to truncated sections, again in priority order.
+pub fn build_context(map: &SystemMap, budget: usize) -> ContextPack {
+    let summary = build_summary(map, false);
+    let name = summary["name"].as_str().unwrap_or("Project");
+    let title = format!("# {}\n", name);
+
+    let sections = [
+        Section { name: "overview", share_percent: 25, variants: overview_variants(&summary) },
+        Section { name: "entry_points", share_percent: 10, variants: entry_point_variants(&map.tree) },
+        Section { name: "tree", share_percent: 35, variants: tree_variants(&map.tree) },
+        Section { name: "key_files", share_percent: 20, variants: key_file_variants(map) },
+        Section { name: "dependencies", share_percent: 10, variants: dependency_variants(map) },
+    ];
+
+    let available = budget.saturating_sub(estimate_tokens(&title));
+    let mut remaining = available;
+    let mut chosen: Vec<Option<usize>> = vec![None; sections.len()];
+
+    // First pass: each section within its share
+    for (i, section) in sections.iter().enumerate() {
+        let cap = remaining.min(available * section.share_percent / 100);
+        if let Some(v) = section.variants.iter().position(|v| v.cost() <= cap) {
+            remaining -= section.variants[v].cost();
+            chosen[i] = Some(v);
+        }
+    }
+
+    // Second pass: upgrade truncated or dropped sections with what's left
+    for (i, section) in sections.iter().enumerate() {
+        let current_cost = chosen[i].map_or(0, |v| section.variants[v].cost());
+        let current = chosen[i].unwrap_or(section.variants.len());
+        if let Some(v) = section.variants[..current]
+            .iter()
+            .position(|v| v.cost() <= remaining + current_cost)
+        {
+            remaining = remaining + current_cost - section.variants[v].cost();
+            chosen[i] = Some(v);
+        }
+    }
+
+    let mut document = title;
+    let mut plan = Vec::new();
+    for (section, choice) in sections.iter().zip(&chosen) {
+        let full_tokens = section.variants.first().map_or(0, Variant::cost);
+        let (status, tokens, detail) = match choice {
+            _ if section.variants.is_empty() => (SectionStatus::Empty, 0, None),
+            None => (SectionStatus::Dropped, 0, None),
+            Some(v) => {
+                let variant = &section.variants[*v];
+                document.push('\n');
+                document.push_str(&variant.text);
+                let status = if *v == 0 { SectionStatus::Full } else { SectionStatus::Truncated };
+                (status, variant.cost(), variant.detail.clone())
+            }
+        };
+        plan.push(SectionPlan {
+            section: section.name,
+            status,
+            tokens,
+            full_tokens,
+            detail,
+            share_percent: section.share_percent,
+        });
+    }
+
+    ContextPack {
+        budget,
+        estimated_tokens: estimate_tokens(&document),
+        plan,
+        document,
+    }
+}
+
+// ============ Sections ============
+
+/// Full overview, then without directory details, then the bare facts
+fn overview_variants(summary: &Value) -> Vec<Variant> {
+    let mut facts = Vec::new();
+    let languages = join_strings(&summary["languages"]);
+    if !languages.is_empty() {
+        facts.push(format!("- Languages: {}", languages));
+    }
+    if let Some(framework) = summary["framework"].as_str() {
+        facts.push(format!("- Framework: {}", framework));
+    }
+    facts.push(format!(
+        "- Files: {} indexed, {} total",
+        summary["meta"]["indexed_files"], summary["meta"]["total_files"]
+    ));
+
+    let mut layout = Vec::new();
+    let sources: Vec<String> = array(&summary["structure"]["source_dirs"])
+        .map(|dir| {
+            let mut info = vec![format!("{} files", dir["files"])];
+            if let Some(lines) = dir["lines"].as_u64() {
+                info.push(format!("{} lines", lines));
+            }
+            if let Some(language) = dir["language"].as_str() {
+                info.push(language.to_string());
+            }
+            format!("{}/ ({})", str_field(dir, "path"), info.join(", "))
+        })
+        .collect();
+    if !sources.is_empty() {
+        layout.push(format!("- Source: {}", sources.join("; ")));
+    }
+    let tests: Vec<String> = array(&summary["structure"]["test_dirs"])
+        .map(|dir| format!("{}/ ({} files)", str_field(dir, "path"), dir["files"]))
+        .collect();
+    if !tests.is_empty() {
+        layout.push(format!("- Tests: {}", tests.join("; ")));
+    }
+    let config = join_strings(&summary["structure"]["config_files"]);
+    if !config.is_empty() {
+        layout.push(format!("- Config: {}", config));
+    }
+
+    let mut details = Vec::new();
+    let key_dirs: Vec<String> = array(&summary["key_directories"])
+        .map(|dir| format!("  - {}/: {}", str_field(dir, "path"), join_strings(&dir["contents"])))
+        .collect();
+    if !key_dirs.is_empty() {
+        details.push("- Key directories:".to_string());
+        details.extend(key_dirs);
+    }
+    let collapsed: Vec<String> = array(&summary["collapsed"])
+        .map(|c| format!("{}/ ({} files)", str_field(c, "path"), c["file_count"]))
+        .collect();
+    if !collapsed.is_empty() {
+        details.push(format!("- Collapsed: {}", collapsed.join(", ")));
+    }
+
+    let render = |parts: &[&[String]]| {
+        let mut text = "## Overview\n".to_string();
+        for line in parts.iter().flat_map(|p| p.iter()) {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text
+    };
+
+    let mut variants = vec![Variant { text: render(&[&facts, &layout, &details]), detail: None }];
+    if !details.is_empty() {
+        variants.push(Variant {
+            text: render(&[&facts, &layout]),
+            detail: Some("without key directories".to_string()),
+        });
+    }
+    if !layout.is_empty() {
+        variants.push(Variant { text: render(&[&facts]), detail: Some("languages and counts only".to_string()) });
+    }
+    variants
+}
+
+fn entry_point_variants(tree: &FileNode) -> Vec<Variant> {
+    let mut lines = Vec::new();
+    visit_files(tree, &mut |node| {
+        if let FileNode::File { path, purpose, language, .. } = node {
+            if purpose.as_deref() == Some("entry") {
+                let language = language.as_deref().map(|l| format!(" ({})", l)).unwrap_or_default();
+                lines.push(format!("- {}{}", path_key(path), language));
+            }
+        }
+    });
+    list_variants("Entry points", &lines, "entry points")
+}
+
+/// The tree at decreasing depths, from the deepest that still adds detail
+fn tree_variants(tree: &FileNode) -> Vec<Variant> {
+    let mut variants = Vec::new();
+    for depth in 1..=MAX_TREE_DEPTH {
+        let mut text = "## Tree\n```\n".to_string();
+        let complete = render_tree(tree, 0, depth, "", &mut text);
+        text.push_str("```\n");
+        variants.push(Variant { text, detail: Some(format!("depth {}", depth)) });
+        if complete {
+            break;
+        }
+    }
+    variants.reverse();
+    if let Some(full) = variants.first_mut() {
+        full.detail = None;
+    }
+    variants
+}
+
+/// The files most other files depend on, plus entry points
+fn key_file_variants(map: &SystemMap) -> Vec<Variant> {
+    let deps = &map.dependencies;
+    let mut importers: HashMap<&str, usize> = HashMap::new();
+    for targets in deps.internal.values() {
+        for target in targets {
+            *importers.entry(target.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut candidates = Vec::new();
+    visit_files(&map.tree, &mut |node| {
+        if let FileNode::File { path, lines, language, purpose, .. } = node {
+            if purpose.as_deref() == Some("test") {
+                return;
+            }
+            let key = path_key(path);
+            let imported_by = importers.get(key.as_str()).copied().unwrap_or(0);
+            let imports = deps.internal.get(&key).map_or(&[][..], Vec::as_slice);
+            let score = imported_by * 3 + imports.len() + if purpose.as_deref() == Some("entry") { 5 } else { 0 };
+
+            let mut info = Vec::new();
+            if let Some(lines) = lines {
+                info.push(format!("{} lines", lines));
+            }
+            info.extend(language.clone());
+            info.extend(purpose.clone());
+            let mut line = format!("- {} ({})", key, info.join(", "));
+            if imported_by > 0 {
+                line.push_str(&format!(", imported by {}", imported_by));
+            }
+            if !imports.is_empty() {
+                let mut listed: Vec<&str> = imports.iter().take(MAX_LISTED_IMPORTS).map(String::as_str).collect();
+                let more = imports.len().saturating_sub(MAX_LISTED_IMPORTS);
+                let more = format!("+{} more", more);
+                if imports.len() > MAX_LISTED_IMPORTS {
+                    listed.push(&more);
+                }
+                line.push_str(&format!(", imports {}", listed.join(", ")));
+            }
+            candidates.push((score, lines.unwrap_or(0), line));
+        }
+    });
+
+    // Most depended-on first, larger files breaking ties
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+    let lines: Vec<String> = candidates.into_iter().take(MAX_KEY_FILES).map(|(_, _, line)| line).collect();
+    list_variants("Key files", &lines, "files")
+}
+
+fn dependency_variants(map: &SystemMap) -> Vec<Variant> {
+    let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+    for package in &map.dependencies.packages {
+        let mut label = match &package.version {
+            Some(version) => format!("{} {}", package.name, version),
+            None => package.name.clone(),
+        };
+        match package.scope {
+            DependencyScope::Runtime => {}
+            DependencyScope::Dev => label.push_str(" (dev)"),
+            DependencyScope::Build => label.push_str(" (build)"),
+        }
+        match groups.iter_mut().find(|(source, _)| *source == package.source) {
+            Some((_, labels)) => labels.push(label),
+            None => groups.push((&package.source, vec![label])),
+        }
+    }
+
+    let lines: Vec<String> = groups
+        .into_iter()
+        .map(|(source, labels)| format!("- {}: {}", source, labels.join(", ")))
+        .collect();
+    list_variants("Dependencies", &lines, "manifests")
+}
+
+// ============ Helpers ============
+
+/// A list section with every prefix of its lines, longest first
+fn list_variants(title: &str, lines: &[String], noun: &str) -> Vec<Variant> {
+    (1..=lines.len())
+        .rev()
+        .map(|kept| {
+            let mut text = format!("## {}\n", title);
+            for line in &lines[..kept] {
+                text.push_str(line);
+                text.push('\n');
+            }
+            let detail = (kept < lines.len()).then(|| {
+                text.push_str(&format!("- ... {} more\n", lines.len() - kept));
+                format!("{} of {} {}", kept, lines.len(), noun)
+            });
+            Variant { text, detail }
+        })
+        .collect()
+}
+
+/// Render a plain tree, returning whether nothing was cut off by `max_depth`
+fn render_tree(node: &FileNode, depth: usize, max_depth: usize, prefix: &str, out: &mut String) -> bool {
+    let FileNode::Directory { name, children, .. } = node else {
+        return true;
+    };
+    if depth == 0 {
+        out.push_str(&format!("{}/\n", name));
+    }
+
+    // Directories first, like `sysmap tree`
+    let mut sorted: Vec<&FileNode> = children.iter().collect();
+    sorted.sort_by_key(|c| (!(c.is_directory() || c.is_collapsed()), c.name().to_string()));
+
+    let mut complete = true;
+    for (i, child) in sorted.iter().enumerate() {
+        let is_last = i + 1 == sorted.len();
+        let connector = if is_last { "└── " } else { "├── " };
+        match child {
+            FileNode::File { name, .. } => out.push_str(&format!("{}{}{}\n", prefix, connector, name)),
+            FileNode::Collapsed { name, reason, file_count, .. } => {
+                out.push_str(&format!("{}{}{}/ [{}: {} files]\n", prefix, connector, name, reason, file_count));
+            }
+            FileNode::Directory { name, children, .. } if depth + 1 >= max_depth && !children.is_empty() => {
+                out.push_str(&format!("{}{}{}/ ({} items)\n", prefix, connector, name, children.len()));
+                complete = false;
+            }
+            FileNode::Directory { name, .. } => {
+                out.push_str(&format!("{}{}{}/\n", prefix, connector, name));
+                let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+                complete &= render_tree(child, depth + 1, max_depth, &child_prefix, out);
+            }
+        }
+    }
+    complete
+}
+
+fn visit_files(node: &FileNode, visit: &mut dyn FnMut(&FileNode)) {
+    match node {
+        FileNode::File { .. } => visit(node),
+        FileNode::Directory { children, .. } => {
+            for child in children {
+                visit_files(child, visit);
+            }
+        }
+        FileNode::Collapsed { .. } => {}
+    }
+}
+
+fn array(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value[key].as_str().unwrap_or_default()
+}
+
+fn join_strings(value: &Value) -> String {
+    array(value).filter_map(Value::as_str).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sample_map() -> SystemMap {
+        let mut map = SystemMap::new(PathBuf::from("/work/shop"));
+        let modules: Vec<FileNode> = (0..40)
+            .map(|i| FileNode::test_file(&format!("src/models/model_{i}.py"), 50 + i))
+            .collect();
+        map.tree = FileNode::Directory {
+            name: "shop".to_string(),
+            path: PathBuf::new(),
+            children: vec![FileNode::Directory {
+                name: "src".to_string(),
+                path: PathBuf::from("src"),
+                children: vec![FileNode::Directory {
+                    name: "models".to_string(),
+                    path: PathBuf::from("src/models"),
+                    children: modules,
+                    churn: None,
+                }],
+                churn: None,
+            }],
+            churn: None,
+        };
+        map.dependencies
+            .internal
+            .insert("src/models/model_1.py".to_string(), vec!["src/models/model_0.py".to_string()]);
+        map
+    }
+
+    #[test]
+    fn test_context_fits_budget() {
+        let map = sample_map();
+
+        let roomy = build_context(&map, 10_000);
+        assert!(roomy.plan.iter().all(|s| matches!(s.status, SectionStatus::Full | SectionStatus::Empty)));
+        assert!(roomy.document.contains("model_39.py"));
+
+        let tight = build_context(&map, 300);
+        assert!(tight.estimated_tokens <= 300);
+        let tree = tight.plan.iter().find(|s| s.section == "tree").unwrap();
+        assert_ne!(tree.status, SectionStatus::Full);
+        let key_files = tight.plan.iter().find(|s| s.section == "key_files").unwrap();
+        assert!(tight.document.contains("src/models/model_0.py") || key_files.status == SectionStatus::Dropped);
+    }
+}
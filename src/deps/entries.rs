@@ -0,0 +1,152 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::map::FileNode;
+
+use super::manifests::collect_manifests;
+use super::resolve::{collect_files, join, normalize, split_parent};
+use super::path_key;
+
+/// Files declared as entry points by the manifests in the tree, as path keys:
+/// `Cargo.toml` binaries, `package.json` `main`/`bin` and Python console scripts.
+///
+/// Declared files that aren't in the map are left out, as are manifests that
+/// can't be read or parsed.
+pub fn collect_entry_points(root: &Path, tree: &FileNode) -> BTreeSet<String> {
+    let mut manifests = Vec::new();
+    collect_manifests(tree, &mut manifests);
+    let mut files = HashSet::new();
+    collect_files(tree, &mut files);
+
+    let mut entries = BTreeSet::new();
+    for (name, path) in manifests {
+        let Ok(contents) = std::fs::read_to_string(root.join(path)) else {
+            continue;
+        };
+        let key = path_key(path);
+        let (dir, _) = split_parent(&key);
+        let Ok(declared) = parse_entry_points(name, &contents) else {
+            continue;
+        };
+        for candidates in declared {
+            let found = candidates
+                .iter()
+                .filter_map(|p| normalize(&join(dir, p)))
+                .find(|p| files.contains(p));
+            entries.extend(found);
+        }
+    }
+
+    entries
+}
+
+/// Give every file in `entries` the `entry` purpose
+pub fn mark_entry_points(node: &mut FileNode, entries: &BTreeSet<String>) {
+    match node {
+        FileNode::File { path, purpose, .. } => {
+            if entries.contains(&path_key(path)) {
+                *purpose = Some("entry".to_string());
+            }
+        }
+        FileNode::Directory { children, .. } => {
+            for child in children {
+                mark_entry_points(child, entries);
+            }
+        }
+        FileNode::Collapsed { .. } => {}
+    }
+}
+
+/// The entry points a manifest declares, relative to its directory; each is a
+/// list of candidate paths, the first existing one being the entry point
+fn parse_entry_points(name: &str, contents: &str) -> Result<Vec<Vec<String>>> {
+    let mut entries = Vec::new();
+
+    match name {
+        "Cargo.toml" => {
+            let doc: toml::Value = toml::from_str(contents)?;
+            for bin in doc.get("bin").and_then(|b| b.as_array()).into_iter().flatten() {
+                let path = bin.get("path").and_then(|p| p.as_str());
+                let name = bin.get("name").and_then(|n| n.as_str());
+                match (path, name) {
+                    (Some(path), _) => entries.push(vec![path.to_string()]),
+                    (None, Some(name)) => entries.push(vec![
+                        format!("src/bin/{}.rs", name),
+                        format!("src/bin/{}/main.rs", name),
+                        "src/main.rs".to_string(),
+                    ]),
+                    _ => {}
+                }
+            }
+        }
+        "package.json" => {
+            let doc: serde_json::Value = serde_json::from_str(contents)?;
+            let main = doc.get("main").and_then(|m| m.as_str());
+            let bins: Vec<&str> = match doc.get("bin") {
+                Some(serde_json::Value::String(bin)) => vec![bin],
+                Some(serde_json::Value::Object(bins)) => bins.values().filter_map(|b| b.as_str()).collect(),
+                _ => Vec::new(),
+            };
+            // `main` may leave out the extension
+            for path in main.into_iter().chain(bins) {
+                entries.push(vec![path.to_string(), format!("{}.js", path), format!("{}/index.js", path)]);
+            }
+        }
+        "pyproject.toml" => {
+            let doc: toml::Value = toml::from_str(contents)?;
+            let tables = [
+                doc.get("project").and_then(|p| p.get("scripts")),
+                doc.get("project").and_then(|p| p.get("gui-scripts")),
+                doc.get("tool").and_then(|t| t.get("poetry")).and_then(|p| p.get("scripts")),
+            ];
+            for table in tables.into_iter().flatten().filter_map(|t| t.as_table()) {
+                for target in table.values().filter_map(|t| t.as_str()) {
+                    entries.push(python_module_files(target));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(entries)
+}
+
+/// Candidate files of the module in a `package.module:function` script target
+fn python_module_files(target: &str) -> Vec<String> {
+    let module = target.split(':').next().unwrap_or(target).trim().replace('.', "/");
+    ["", "src/"]
+        .iter()
+        .flat_map(|prefix| {
+            [
+                format!("{}{}.py", prefix, module),
+                format!("{}{}/__init__.py", prefix, module),
+                format!("{}{}/__main__.py", prefix, module),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entry_points() {
+        let cargo = "[package]\nname = \"tool\"\n\n[[bin]]\nname = \"migrate\"\n\n[[bin]]\nname = \"x\"\npath = \"tools/x.rs\"\n";
+        let entries = parse_entry_points("Cargo.toml", cargo).unwrap();
+        assert_eq!(entries[0][0], "src/bin/migrate.rs");
+        assert_eq!(entries[1], ["tools/x.rs"]);
+
+        let package = r#"{"main": "lib/index", "bin": {"tool": "./bin/tool.js"}}"#;
+        let entries = parse_entry_points("package.json", package).unwrap();
+        assert_eq!(entries[0][1], "lib/index.js");
+        assert_eq!(entries[1][0], "./bin/tool.js");
+
+        let pyproject = "[project.scripts]\ntool = \"tool.cli:main\"\n";
+        let entries = parse_entry_points("pyproject.toml", pyproject).unwrap();
+        assert_eq!(entries[0][0], "tool/cli.py");
+        assert!(entries[0].contains(&"src/tool/cli.py".to_string()));
+    }
+}
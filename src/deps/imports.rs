@@ -0,0 +1,470 @@
+use serde::{Deserialize, Serialize};
+
+/// An import statement as written in the source, before resolution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RawImport {
+    /// `import a.b` or `from ..a import b, c` (level counts the leading dots)
+    Python {
+        module: String,
+        level: usize,
+        names: Vec<String>,
+    },
+    /// `mod foo;`
+    RustMod { name: String },
+    /// `use crate::a::b;` expanded to one path per leaf
+    RustUse { path: String },
+    /// ES module or CommonJS specifier
+    Js { specifier: String },
+    /// Go import path
+    Go { path: String },
+}
+
+/// Whether imports can be parsed for a language
+pub fn supports_language(language: &str) -> bool {
+    matches!(language, "python" | "rust" | "javascript" | "typescript" | "go")
+}
+
+/// Parse the import statements of a source file
+pub fn parse_imports(language: &str, source: &str) -> Vec<RawImport> {
+    match language {
+        "python" => parse_python(source),
+        "rust" => parse_rust(source),
+        "javascript" | "typescript" => parse_js(source),
+        "go" => parse_go(source),
+        _ => Vec::new(),
+    }
+}
+
+// ============ Python ============
+
+fn parse_python(source: &str) -> Vec<RawImport> {
+    let mut imports = Vec::new();
+    let mut lines = source.lines();
+
+    while let Some(line) = lines.next() {
+        let line = strip_comment(line, "#");
+        let trimmed = line.trim();
+
+        if let Some(rest) = trimmed.strip_prefix("import ") {
+            for part in rest.split(',') {
+                let module = part.split_whitespace().next().unwrap_or("");
+                if is_dotted_name(module) {
+                    imports.push(RawImport::Python {
+                        module: module.to_string(),
+                        level: 0,
+                        names: Vec::new(),
+                    });
+                }
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("from ") {
+            let Some((module, names)) = rest.split_once(" import ") else {
+                continue;
+            };
+            let module = module.trim();
+            let level = module.chars().take_while(|c| *c == '.').count();
+            let module = &module[level..];
+            if !module.is_empty() && !is_dotted_name(module) {
+                continue;
+            }
+
+            // Parenthesized or backslash-continued name lists
+            let mut names = names.trim().to_string();
+            if names.starts_with('(') {
+                while !names.contains(')') {
+                    match lines.next() {
+                        Some(next) => {
+                            names.push(' ');
+                            names.push_str(strip_comment(next, "#").trim());
+                        }
+                        None => break,
+                    }
+                }
+            }
+            while names.ends_with('\\') {
+                names.pop();
+                match lines.next() {
+                    Some(next) => names.push_str(strip_comment(next, "#").trim()),
+                    None => break,
+                }
+            }
+
+            let names = names
+                .trim_matches(|c| c == '(' || c == ')')
+                .split(',')
+                .filter_map(|n| n.split_whitespace().next())
+                .filter(|n| *n != "*" && is_dotted_name(n))
+                .map(|n| n.trim_end_matches(')').to_string())
+                .collect();
+
+            imports.push(RawImport::Python {
+                module: module.to_string(),
+                level,
+                names,
+            });
+        }
+    }
+
+    imports
+}
+
+fn is_dotted_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
+// ============ Rust ============
+
+fn parse_rust(source: &str) -> Vec<RawImport> {
+    let mut imports = Vec::new();
+    let mut statement = String::new();
+
+    for line in source.lines() {
+        let line = strip_comment(line, "//");
+        let trimmed = line.trim();
+
+        if !statement.is_empty() {
+            statement.push(' ');
+            statement.push_str(trimmed);
+            if trimmed.contains(';') {
+                push_rust_use(&statement, &mut imports);
+                statement.clear();
+            }
+            continue;
+        }
+
+        let item = strip_visibility(trimmed);
+        if let Some(rest) = item.strip_prefix("mod ") {
+            // `mod foo;` declares a file module; `mod foo {` is inline
+            if let Some(name) = rest.trim().strip_suffix(';') {
+                let name = name.trim();
+                if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    imports.push(RawImport::RustMod {
+                        name: name.to_string(),
+                    });
+                }
+            }
+        } else if item.starts_with("use ") {
+            if item.contains(';') {
+                push_rust_use(item, &mut imports);
+            } else {
+                statement.push_str(item);
+            }
+        }
+    }
+
+    imports
+}
+
+fn strip_visibility(item: &str) -> &str {
+    if let Some(rest) = item.strip_prefix("pub") {
+        if let Some(rest) = rest.strip_prefix(' ') {
+            return rest.trim_start();
+        }
+        if rest.starts_with('(') {
+            if let Some(end) = rest.find(')') {
+                return rest[end + 1..].trim_start();
+            }
+        }
+    }
+    item
+}
+
+fn push_rust_use(statement: &str, imports: &mut Vec<RawImport>) {
+    let body = statement
+        .trim()
+        .trim_start_matches("use ")
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim();
+
+    for path in expand_use_tree(body) {
+        imports.push(RawImport::RustUse { path });
+    }
+}
+
+/// Expand a use tree like `a::{b, c::{d, e}}` into `a::b`, `a::c::d`, `a::c::e`
+fn expand_use_tree(tree: &str) -> Vec<String> {
+    let tree = strip_renames(tree);
+    let mut paths = Vec::new();
+    expand_into("", &tree, &mut paths);
+    paths
+}
+
+fn expand_into(prefix: &str, tree: &str, paths: &mut Vec<String>) {
+    match tree.find('{') {
+        Some(open) if tree.ends_with('}') => {
+            let base = format!("{}{}", prefix, &tree[..open]);
+            let inner = &tree[open + 1..tree.len() - 1];
+            for part in split_top_level(inner) {
+                if part == "self" {
+                    paths.push(base.trim_end_matches("::").to_string());
+                } else if !part.is_empty() {
+                    expand_into(&base, part, paths);
+                }
+            }
+        }
+        _ => {
+            let full = format!("{}{}", prefix, tree);
+            if !full.is_empty() {
+                paths.push(full);
+            }
+        }
+    }
+}
+
+/// Drop `as` renames (`a::b as c` becomes `a::b`) along with all whitespace
+fn strip_renames(tree: &str) -> String {
+    let mut out = String::new();
+    let mut tokens = tree.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token == "as" {
+            // Skip the alias, keeping any `,` or `}` that follows it
+            if let Some(alias) = tokens.next() {
+                out.push_str(alias.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_'));
+            }
+        } else {
+            out.push_str(token);
+        }
+    }
+    out
+}
+
+fn split_top_level(list: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&list[start..]);
+    parts
+}
+
+// ============ JavaScript / TypeScript ============
+
+fn parse_js(source: &str) -> Vec<RawImport> {
+    let mut imports = Vec::new();
+    let mut in_import = false;
+
+    for line in source.lines() {
+        let line = strip_comment(line, "//");
+        let trimmed = line.trim();
+
+        // `import x from "y"`, `export { x } from "y"`, `import "y"`
+        let starts_statement = trimmed.starts_with("import ")
+            || trimmed.starts_with("import{")
+            || trimmed.starts_with("import\"")
+            || trimmed.starts_with("import'")
+            || trimmed.starts_with("export ");
+
+        if starts_statement || in_import {
+            if let Some(spec) = spec_after(trimmed, "from") {
+                imports.push(RawImport::Js { specifier: spec });
+                in_import = false;
+            } else if let Some(spec) = trimmed.strip_prefix("import").and_then(leading_string) {
+                imports.push(RawImport::Js { specifier: spec });
+                in_import = false;
+            } else if trimmed.starts_with("import") && !trimmed.starts_with("import(") {
+                // Multi-line import list; the specifier follows on a later line
+                in_import = !trimmed.ends_with(';');
+            }
+        }
+
+        // `require("y")` and dynamic `import("y")` can appear anywhere
+        for call in ["require(", "import("] {
+            let mut rest = trimmed;
+            while let Some(pos) = rest.find(call) {
+                rest = &rest[pos + call.len()..];
+                if let Some(spec) = leading_string(rest) {
+                    imports.push(RawImport::Js { specifier: spec });
+                }
+            }
+        }
+    }
+
+    imports
+}
+
+/// Find `keyword "string"` in a line and return the string
+fn spec_after(line: &str, keyword: &str) -> Option<String> {
+    let mut rest = line;
+    while let Some(pos) = rest.find(keyword) {
+        let before_ok = pos == 0 || !rest[..pos].ends_with(|c: char| c.is_alphanumeric() || c == '_');
+        rest = &rest[pos + keyword.len()..];
+        if before_ok {
+            if let Some(spec) = leading_string(rest) {
+                return Some(spec);
+            }
+        }
+    }
+    None
+}
+
+/// Parse a quoted string at the start of the input (after whitespace)
+fn leading_string(text: &str) -> Option<String> {
+    let text = text.trim_start();
+    let quote = text.chars().next().filter(|c| matches!(c, '"' | '\'' | '`'))?;
+    let rest = &text[1..];
+    let end = rest.find(quote)?;
+    let value = &rest[..end];
+    (!value.is_empty() && !value.contains("${")).then(|| value.to_string())
+}
+
+// ============ Go ============
+
+fn parse_go(source: &str) -> Vec<RawImport> {
+    let mut imports = Vec::new();
+    let mut in_block = false;
+
+    for line in source.lines() {
+        let line = strip_comment(line, "//");
+        let trimmed = line.trim();
+
+        if in_block {
+            if trimmed.starts_with(')') {
+                in_block = false;
+            } else if let Some(path) = go_import_path(trimmed) {
+                imports.push(RawImport::Go { path });
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("import") {
+            let rest = rest.trim();
+            if rest.starts_with('(') {
+                in_block = true;
+                if let Some(path) = go_import_path(rest.trim_start_matches('(')) {
+                    imports.push(RawImport::Go { path });
+                }
+            } else if let Some(path) = go_import_path(rest) {
+                imports.push(RawImport::Go { path });
+            }
+        } else if trimmed.starts_with("func ") || trimmed.starts_with("type ") {
+            // Imports must precede declarations
+            break;
+        }
+    }
+
+    imports
+}
+
+/// Parse `"path"`, `alias "path"` or `_ "path"`
+fn go_import_path(spec: &str) -> Option<String> {
+    let start = spec.find('"')?;
+    leading_string(&spec[start..])
+}
+
+// ============ Helpers ============
+
+/// Remove a trailing line comment, ignoring markers inside string literals
+fn strip_comment<'a>(line: &'a str, marker: &str) -> &'a str {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match quote {
+            Some(q) => {
+                if c == '\\' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => {
+                if matches!(c, '"' | '\'' | '`') {
+                    quote = Some(c);
+                } else if line[i..].starts_with(marker) {
+                    return &line[..i];
+                }
+            }
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn python(module: &str, level: usize, names: &[&str]) -> RawImport {
+        RawImport::Python {
+            module: module.to_string(),
+            level,
+            names: names.iter().map(|n| n.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_python() {
+        let source = "import os, app.models as m\nfrom ..services import (\n    db,  # comment\n    email,\n)\nfrom . import utils\n";
+        assert_eq!(
+            parse_imports("python", source),
+            vec![
+                python("os", 0, &[]),
+                python("app.models", 0, &[]),
+                python("services", 2, &["db", "email"]),
+                python("", 1, &["utils"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rust() {
+        let source = "mod cli;\npub mod map;\nmod tests {\n}\nuse crate::map::{FileNode, types::SystemMap};\nuse super::{\n    walker,\n    stats::count,\n};\n";
+        let paths: Vec<String> = parse_imports("rust", source)
+            .into_iter()
+            .map(|i| match i {
+                RawImport::RustMod { name } => format!("mod {}", name),
+                RawImport::RustUse { path } => path,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "mod cli",
+                "mod map",
+                "crate::map::FileNode",
+                "crate::map::types::SystemMap",
+                "super::walker",
+                "super::stats::count",
+            ]
+        );
+
+        // Path segments containing the letters "as" aren't renames
+        assert_eq!(expand_use_tree("crate::database::Pool"), ["crate::database::Pool"]);
+        assert_eq!(expand_use_tree("a::ast as b"), ["a::ast"]);
+        assert_eq!(expand_use_tree("a::{base as b, class::{x as y, z}}"), ["a::base", "a::class::x", "a::class::z"]);
+    }
+
+    #[test]
+    fn test_parse_js_and_go() {
+        let js = "import React from 'react';\nimport {\n  a,\n} from \"./a\";\nexport * from './b';\nconst c = require('../c');\nimport './styles.css';\n";
+        let specs: Vec<RawImport> = ["react", "./a", "./b", "../c", "./styles.css"]
+            .iter()
+            .map(|s| RawImport::Js { specifier: s.to_string() })
+            .collect();
+        assert_eq!(parse_imports("typescript", js), specs);
+
+        let go = "package main\n\nimport (\n\t\"fmt\"\n\tm \"example.com/app/models\"\n)\n\nfunc main() {}\n";
+        assert_eq!(
+            parse_imports("go", go),
+            vec![
+                RawImport::Go { path: "fmt".to_string() },
+                RawImport::Go { path: "example.com/app/models".to_string() },
+            ]
+        );
+    }
+}
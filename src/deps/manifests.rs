@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::map::{DependencyScope, FileNode, Package};
+
+use super::path_key;
+
+/// Whether a file name is a manifest sysmap can read packages from
+pub fn is_manifest(name: &str) -> bool {
+    matches!(
+        name,
+        "pyproject.toml" | "Pipfile" | "package.json" | "Cargo.toml" | "go.mod" | "Gemfile" | "pom.xml"
+    ) || (name.starts_with("requirements") && name.ends_with(".txt"))
+}
+
+/// Read the packages declared by every manifest in the tree.
+///
+/// Manifests that can't be read or parsed are skipped rather than failing the scan.
+pub fn collect_packages(root: &Path, tree: &FileNode) -> Vec<Package> {
+    let mut packages = Vec::new();
+    let mut manifests = Vec::new();
+    collect_manifests(tree, &mut manifests);
+
+    for (name, path) in manifests {
+        let source = path_key(path);
+        let Ok(contents) = std::fs::read_to_string(root.join(path)) else {
+            continue;
+        };
+        if let Ok(found) = parse_manifest(name, &contents, &source) {
+            packages.extend(found);
+        }
+    }
+
+    packages
+}
+
+/// Parse the packages declared in a manifest
+pub fn parse_manifest(name: &str, contents: &str, source: &str) -> Result<Vec<Package>> {
+    let mut packages = Packages::new(source);
+
+    match name {
+        "pyproject.toml" => parse_pyproject(contents, &mut packages)?,
+        "Pipfile" => parse_pipfile(contents, &mut packages)?,
+        "package.json" => parse_package_json(contents, &mut packages)?,
+        "Cargo.toml" => parse_cargo(contents, &mut packages)?,
+        "go.mod" => parse_go_mod(contents, &mut packages),
+        "Gemfile" => parse_gemfile(contents, &mut packages),
+        "pom.xml" => parse_pom(contents, &mut packages)?,
+        _ if is_manifest(name) => {
+            let scope = if name.contains("dev") || name.contains("test") {
+                DependencyScope::Dev
+            } else {
+                DependencyScope::Runtime
+            };
+            parse_requirements(contents, scope, &mut packages);
+        }
+        _ => {}
+    }
+
+    Ok(packages.list)
+}
+
+pub(super) fn collect_manifests<'a>(node: &'a FileNode, manifests: &mut Vec<(&'a str, &'a Path)>) {
+    match node {
+        FileNode::File { name, path, .. } if is_manifest(name) => {
+            manifests.push((name, path));
+        }
+        FileNode::Directory { children, .. } => {
+            for child in children {
+                collect_manifests(child, manifests);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Packages from one manifest
+struct Packages<'a> {
+    source: &'a str,
+    list: Vec<Package>,
+}
+
+impl<'a> Packages<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            list: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, name: &str, version: Option<&str>, scope: DependencyScope) {
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        let version = version
+            .map(str::trim)
+            .filter(|v| !v.is_empty() && *v != "*");
+
+        self.list.push(Package {
+            name: name.to_string(),
+            version: version.map(str::to_string),
+            scope,
+            source: self.source.to_string(),
+        });
+    }
+
+    /// Add each entry of a `name = "version"` or `name = { version = ... }` table
+    fn push_table(&mut self, table: Option<&toml::Value>, scope: DependencyScope) {
+        let Some(table) = table.and_then(|t| t.as_table()) else {
+            return;
+        };
+        for (name, spec) in table {
+            let version = match spec {
+                toml::Value::String(version) => Some(version.as_str()),
+                toml::Value::Table(fields) => fields.get("version").and_then(|v| v.as_str()),
+                _ => None,
+            };
+            self.push(name, version, scope);
+        }
+    }
+
+    /// Add each PEP 508 requirement string in an array
+    fn push_requirements(&mut self, list: Option<&toml::Value>, scope: DependencyScope) {
+        let Some(list) = list.and_then(|l| l.as_array()) else {
+            return;
+        };
+        for requirement in list.iter().filter_map(|r| r.as_str()) {
+            let (name, version) = split_requirement(requirement);
+            self.push(name, version, scope);
+        }
+    }
+}
+
+// ============ Python ============
+
+fn parse_pyproject(contents: &str, packages: &mut Packages) -> Result<()> {
+    let doc: toml::Value = toml::from_str(contents)?;
+
+    // PEP 621
+    let project = doc.get("project");
+    packages.push_requirements(project.and_then(|p| p.get("dependencies")), DependencyScope::Runtime);
+    if let Some(extras) = project
+        .and_then(|p| p.get("optional-dependencies"))
+        .and_then(|e| e.as_table())
+    {
+        for (group, list) in extras {
+            packages.push_requirements(Some(list), python_group_scope(group));
+        }
+    }
+
+    // PEP 735 dependency groups
+    if let Some(groups) = doc.get("dependency-groups").and_then(|g| g.as_table()) {
+        for list in groups.values() {
+            packages.push_requirements(Some(list), DependencyScope::Dev);
+        }
+    }
+
+    // Poetry
+    if let Some(poetry) = doc.get("tool").and_then(|t| t.get("poetry")) {
+        let mut runtime = poetry.get("dependencies").cloned();
+        if let Some(table) = runtime.as_mut().and_then(|t| t.as_table_mut()) {
+            table.remove("python");
+        }
+        packages.push_table(runtime.as_ref(), DependencyScope::Runtime);
+        packages.push_table(poetry.get("dev-dependencies"), DependencyScope::Dev);
+        if let Some(groups) = poetry.get("group").and_then(|g| g.as_table()) {
+            for (name, group) in groups {
+                packages.push_table(group.get("dependencies"), python_group_scope(name));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_pipfile(contents: &str, packages: &mut Packages) -> Result<()> {
+    let doc: toml::Value = toml::from_str(contents)?;
+    packages.push_table(doc.get("packages"), DependencyScope::Runtime);
+    packages.push_table(doc.get("dev-packages"), DependencyScope::Dev);
+    Ok(())
+}
+
+fn parse_requirements(contents: &str, scope: DependencyScope, packages: &mut Packages) {
+    for line in contents.lines() {
+        let line = line.split(" #").next().unwrap_or(line).trim();
+        // Skip comments, options (-r, -e, --index-url) and direct URLs
+        if line.is_empty() || line.starts_with('#') || line.starts_with('-') || line.contains("://") {
+            continue;
+        }
+        let (name, version) = split_requirement(line);
+        packages.push(name, version, scope);
+    }
+}
+
+fn python_group_scope(group: &str) -> DependencyScope {
+    if matches!(group, "dev" | "test" | "tests" | "testing" | "lint" | "docs" | "typing") {
+        DependencyScope::Dev
+    } else {
+        DependencyScope::Runtime
+    }
+}
+
+/// Split a PEP 508 requirement like `requests[socks]>=2.0; python_version<"3.8"`
+/// into its name and version constraint
+fn split_requirement(requirement: &str) -> (&str, Option<&str>) {
+    let requirement = requirement.split(';').next().unwrap_or(requirement).trim();
+    let name_end = requirement
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(requirement.len());
+    let (name, rest) = requirement.split_at(name_end);
+
+    // Drop extras
+    let rest = match rest.trim_start().strip_prefix('[') {
+        Some(extras) => extras.split_once(']').map(|(_, r)| r).unwrap_or(""),
+        None => rest,
+    };
+    let version = rest.trim().trim_start_matches('(').trim_end_matches(')').trim();
+
+    (name, (!version.is_empty() && !version.starts_with('@')).then_some(version))
+}
+
+// ============ JavaScript ============
+
+fn parse_package_json(contents: &str, packages: &mut Packages) -> Result<()> {
+    let doc: serde_json::Value = serde_json::from_str(contents)?;
+
+    let sections = [
+        ("dependencies", DependencyScope::Runtime),
+        ("peerDependencies", DependencyScope::Runtime),
+        ("optionalDependencies", DependencyScope::Runtime),
+        ("devDependencies", DependencyScope::Dev),
+    ];
+    for (key, scope) in sections {
+        if let Some(deps) = doc.get(key).and_then(|d| d.as_object()) {
+            for (name, version) in deps {
+                packages.push(name, version.as_str(), scope);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ============ Rust ============
+
+fn parse_cargo(contents: &str, packages: &mut Packages) -> Result<()> {
+    let doc: toml::Value = toml::from_str(contents)?;
+
+    let mut push_sections = |table: &toml::Value| {
+        packages.push_table(table.get("dependencies"), DependencyScope::Runtime);
+        packages.push_table(table.get("dev-dependencies"), DependencyScope::Dev);
+        packages.push_table(table.get("build-dependencies"), DependencyScope::Build);
+    };
+
+    push_sections(&doc);
+    if let Some(targets) = doc.get("target").and_then(|t| t.as_table()) {
+        for target in targets.values() {
+            push_sections(target);
+        }
+    }
+    // Versions shared by workspace members
+    if let Some(workspace) = doc.get("workspace") {
+        packages.push_table(workspace.get("dependencies"), DependencyScope::Runtime);
+    }
+
+    Ok(())
+}
+
+// ============ Go ============
+
+fn parse_go_mod(contents: &str, packages: &mut Packages) {
+    let mut in_block = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        let requirement = if in_block {
+            if line.starts_with(')') {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if let Some(rest) = line.strip_prefix("require") {
+            let rest = rest.trim();
+            if rest.starts_with('(') {
+                in_block = true;
+                continue;
+            }
+            rest
+        } else {
+            continue;
+        };
+
+        // Indirect requirements are transitive dependencies
+        if requirement.is_empty() || requirement.starts_with("//") || requirement.ends_with("// indirect") {
+            continue;
+        }
+        let mut parts = requirement.split_whitespace();
+        if let Some(module) = parts.next() {
+            packages.push(module, parts.next(), DependencyScope::Runtime);
+        }
+    }
+}
+
+// ============ Ruby ============
+
+fn parse_gemfile(contents: &str, packages: &mut Packages) {
+    let mut group_depth = 0;
+    let mut dev_group_depth = None;
+
+    for line in contents.lines() {
+        let line = line.split(" #").next().unwrap_or(line).trim();
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with("group ") && line.ends_with(" do") {
+            group_depth += 1;
+            if dev_group_depth.is_none() && (line.contains(":development") || line.contains(":test")) {
+                dev_group_depth = Some(group_depth);
+            }
+            continue;
+        }
+        if line == "end" && group_depth > 0 {
+            if dev_group_depth == Some(group_depth) {
+                dev_group_depth = None;
+            }
+            group_depth -= 1;
+            continue;
+        }
+
+        let Some(args) = line.strip_prefix("gem ") else {
+            continue;
+        };
+        let strings = quoted_strings(args);
+        let Some((name, versions)) = strings.split_first() else {
+            continue;
+        };
+
+        let inline_dev = args.contains("group: :development")
+            || args.contains("group: :test")
+            || args.contains("groups: [:development")
+            || args.contains("groups: [:test");
+        let scope = if dev_group_depth.is_some() || inline_dev {
+            DependencyScope::Dev
+        } else {
+            DependencyScope::Runtime
+        };
+
+        // Version constraints are the leading string arguments
+        let version = versions.join(", ");
+        packages.push(name, Some(&version), scope);
+    }
+}
+
+/// Leading quoted string arguments, stopping at the first keyword argument
+fn quoted_strings(args: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    for arg in args.split(',') {
+        let arg = arg.trim();
+        let Some(quote) = arg.chars().next().filter(|c| matches!(c, '"' | '\'')) else {
+            break;
+        };
+        strings.push(arg.trim_matches(quote).to_string());
+    }
+    strings
+}
+
+// ============ Java ============
+
+fn parse_pom(contents: &str, packages: &mut Packages) -> Result<()> {
+    let doc = roxmltree::Document::parse(contents)?;
+    let project = doc.root_element();
+
+    let child = |node: roxmltree::Node<'_, '_>, name: &str| {
+        node.children()
+            .find(|c| c.has_tag_name(name))
+            .and_then(|c| c.text())
+            .map(str::trim)
+            .map(str::to_string)
+    };
+
+    // `${property}` references in versions
+    let properties: HashMap<String, String> = project
+        .children()
+        .find(|c| c.has_tag_name("properties"))
+        .map(|props| {
+            props
+                .children()
+                .filter(|p| p.is_element())
+                .filter_map(|p| Some((p.tag_name().name().to_string(), p.text()?.trim().to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let Some(dependencies) = project.children().find(|c| c.has_tag_name("dependencies")) else {
+        return Ok(());
+    };
+
+    for dependency in dependencies.children().filter(|c| c.has_tag_name("dependency")) {
+        let (Some(group), Some(artifact)) = (child(dependency, "groupId"), child(dependency, "artifactId")) else {
+            continue;
+        };
+        let version = child(dependency, "version").map(|v| {
+            v.strip_prefix("${")
+                .and_then(|v| v.strip_suffix('}'))
+                .and_then(|key| properties.get(key).cloned())
+                .unwrap_or(v)
+        });
+        let scope = match child(dependency, "scope").as_deref() {
+            Some("test") => DependencyScope::Dev,
+            Some("provided") => DependencyScope::Build,
+            _ => DependencyScope::Runtime,
+        };
+        packages.push(&format!("{}:{}", group, artifact), version.as_deref(), scope);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summarize(name: &str, contents: &str) -> Vec<(String, Option<String>, DependencyScope)> {
+        parse_manifest(name, contents, name)
+            .unwrap()
+            .into_iter()
+            .map(|p| (p.name, p.version, p.scope))
+            .collect()
+    }
+
+    fn entry(name: &str, version: Option<&str>, scope: DependencyScope) -> (String, Option<String>, DependencyScope) {
+        (name.to_string(), version.map(str::to_string), scope)
+    }
+
+    #[test]
+    fn test_parse_python_manifests() {
+        let pyproject = r#"
+[project]
+dependencies = ["flask>=2.0", "requests[socks] (>=2.28); python_version>'3.7'"]
+
+[project.optional-dependencies]
+test = ["pytest"]
+
+[tool.poetry.dependencies]
+python = "^3.11"
+sqlalchemy = { version = "^2.0", extras = ["asyncio"] }
+"#;
+        assert_eq!(
+            summarize("pyproject.toml", pyproject),
+            vec![
+                entry("flask", Some(">=2.0"), DependencyScope::Runtime),
+                entry("requests", Some(">=2.28"), DependencyScope::Runtime),
+                entry("pytest", None, DependencyScope::Dev),
+                entry("sqlalchemy", Some("^2.0"), DependencyScope::Runtime),
+            ]
+        );
+
+        let requirements = "# pinned\ngunicorn==21.2.0  # server\n-r base.txt\npython-dotenv\n";
+        assert_eq!(
+            summarize("requirements-dev.txt", requirements),
+            vec![
+                entry("gunicorn", Some("==21.2.0"), DependencyScope::Dev),
+                entry("python-dotenv", None, DependencyScope::Dev),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_other_manifests() {
+        let cargo = "[dependencies]\nserde = { version = \"1\", features = [\"derive\"] }\nlocal = { path = \"../local\" }\n\n[dev-dependencies]\ntempfile = \"3\"\n";
+        assert_eq!(
+            summarize("Cargo.toml", cargo),
+            vec![
+                entry("serde", Some("1"), DependencyScope::Runtime),
+                entry("local", None, DependencyScope::Runtime),
+                entry("tempfile", Some("3"), DependencyScope::Dev),
+            ]
+        );
+
+        let go_mod = "module example.com/app\n\nrequire github.com/spf13/cobra v1.8.0\n\nrequire (\n\tgolang.org/x/sync v0.6.0\n\tgithub.com/inconshreveable/mousetrap v1.1.0 // indirect\n)\n";
+        assert_eq!(
+            summarize("go.mod", go_mod),
+            vec![
+                entry("github.com/spf13/cobra", Some("v1.8.0"), DependencyScope::Runtime),
+                entry("golang.org/x/sync", Some("v0.6.0"), DependencyScope::Runtime),
+            ]
+        );
+
+        let gemfile = "source 'https://rubygems.org'\ngem 'rails', '~> 7.1'\ngem 'pg'\n\ngroup :development, :test do\n  gem 'rspec-rails', '>= 6', '< 8'\nend\n";
+        assert_eq!(
+            summarize("Gemfile", gemfile),
+            vec![
+                entry("rails", Some("~> 7.1"), DependencyScope::Runtime),
+                entry("pg", None, DependencyScope::Runtime),
+                entry("rspec-rails", Some(">= 6, < 8"), DependencyScope::Dev),
+            ]
+        );
+
+        let pom = r#"<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <properties><junit.version>5.10.0</junit.version></properties>
+  <dependencies>
+    <dependency><groupId>org.slf4j</groupId><artifactId>slf4j-api</artifactId><version>2.0.9</version></dependency>
+    <dependency><groupId>org.junit.jupiter</groupId><artifactId>junit-jupiter</artifactId><version>${junit.version}</version><scope>test</scope></dependency>
+  </dependencies>
+</project>"#;
+        assert_eq!(
+            summarize("pom.xml", pom),
+            vec![
+                entry("org.slf4j:slf4j-api", Some("2.0.9"), DependencyScope::Runtime),
+                entry("org.junit.jupiter:junit-jupiter", Some("5.10.0"), DependencyScope::Dev),
+            ]
+        );
+    }
+}
@@ -0,0 +1,9 @@
+mod entries;
+mod imports;
+mod manifests;
+mod resolve;
+
+pub use entries::*;
+pub use imports::*;
+pub use manifests::*;
+pub use resolve::*;
@@ -0,0 +1,510 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Component, Path};
+
+use crate::map::{Dependencies, FileNode};
+
+use super::RawImport;
+
+/// Where an import points
+#[derive(Debug, PartialEq, Eq)]
+enum Resolved {
+    /// Files inside the project
+    Internal(Vec<String>),
+    /// A third-party module or package
+    External(String),
+    /// Standard library or an import that couldn't be resolved
+    Unresolved,
+}
+
+/// Resolve each file's imports against the files in the map
+pub fn build_dependencies<'a>(
+    root: &Path,
+    tree: &FileNode,
+    imports: impl IntoIterator<Item = (&'a Path, &'a [RawImport])>,
+) -> Dependencies {
+    let resolver = Resolver::new(root, tree);
+    let mut deps = Dependencies::default();
+
+    for (path, file_imports) in imports {
+        let file = path_key(path);
+        let mut internal = BTreeSet::new();
+        let mut external = BTreeSet::new();
+
+        for import in file_imports {
+            match resolver.resolve(&file, import) {
+                Resolved::Internal(targets) => {
+                    internal.extend(targets.into_iter().filter(|t| *t != file));
+                }
+                Resolved::External(name) => {
+                    external.insert(name);
+                }
+                Resolved::Unresolved => {}
+            }
+        }
+
+        if !internal.is_empty() {
+            deps.internal.insert(file.clone(), internal.into_iter().collect());
+        }
+        if !external.is_empty() {
+            deps.external_imports.insert(file, external.into_iter().collect());
+        }
+    }
+
+    deps
+}
+
+/// Format a relative path the way the dependency graph stores it
+pub fn path_key(path: &Path) -> String {
+    path.components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+struct Resolver {
+    files: HashSet<String>,
+    /// Go module paths and the directories holding their go.mod
+    go_modules: Vec<(String, String)>,
+    /// Non-test .go files per directory
+    go_packages: HashMap<String, Vec<String>>,
+    /// Directories containing a Cargo.toml
+    crate_roots: Vec<String>,
+}
+
+impl Resolver {
+    fn new(root: &Path, tree: &FileNode) -> Self {
+        let mut files = HashSet::new();
+        collect_files(tree, &mut files);
+
+        let mut go_modules = Vec::new();
+        let mut go_packages: HashMap<String, Vec<String>> = HashMap::new();
+        let mut crate_roots = Vec::new();
+
+        for file in &files {
+            let (dir, name) = split_parent(file);
+            if name == "go.mod" {
+                if let Some(module) = read_go_module(&root.join(file)) {
+                    go_modules.push((module, dir.to_string()));
+                }
+            } else if name == "Cargo.toml" {
+                crate_roots.push(dir.to_string());
+            } else if name.ends_with(".go") && !name.ends_with("_test.go") {
+                go_packages.entry(dir.to_string()).or_default().push(file.clone());
+            }
+        }
+
+        // Prefer the most specific module or crate
+        go_modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        crate_roots.sort_by_key(|dir| std::cmp::Reverse(dir.len()));
+        for package in go_packages.values_mut() {
+            package.sort();
+        }
+
+        Self {
+            files,
+            go_modules,
+            go_packages,
+            crate_roots,
+        }
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.contains(path)
+    }
+
+    fn resolve(&self, file: &str, import: &RawImport) -> Resolved {
+        match import {
+            RawImport::Python { module, level, names } => self.resolve_python(file, module, *level, names),
+            RawImport::RustMod { name } => self.resolve_rust_mod(file, name),
+            RawImport::RustUse { path } => self.resolve_rust_use(file, path),
+            RawImport::Js { specifier } => self.resolve_js(file, specifier),
+            RawImport::Go { path } => self.resolve_go(path),
+        }
+    }
+
+    // ============ Python ============
+
+    fn resolve_python(&self, file: &str, module: &str, level: usize, names: &[String]) -> Resolved {
+        let bases = if level > 0 {
+            let mut dir = split_parent(file).0;
+            for _ in 1..level {
+                if dir.is_empty() {
+                    return Resolved::Unresolved;
+                }
+                dir = split_parent(dir).0;
+            }
+            vec![dir.to_string()]
+        } else {
+            self.python_roots(file)
+        };
+
+        for base in &bases {
+            let module_path = join(base, &module.replace('.', "/"));
+
+            // `from pkg import submodule` depends on the submodule itself
+            let submodules: Vec<String> = names
+                .iter()
+                .filter_map(|name| self.python_module(&join(&module_path, name)))
+                .collect();
+            if !submodules.is_empty() {
+                return Resolved::Internal(submodules);
+            }
+
+            if let Some(target) = self.python_module(&module_path) {
+                return Resolved::Internal(vec![target]);
+            }
+        }
+
+        if level > 0 {
+            return Resolved::Unresolved;
+        }
+        let top = module.split('.').next().unwrap_or(module);
+        if PYTHON_STDLIB.contains(&top) {
+            Resolved::Unresolved
+        } else {
+            Resolved::External(top.to_string())
+        }
+    }
+
+    fn python_module(&self, path: &str) -> Option<String> {
+        let candidates = [format!("{}.py", path), join(path, "__init__.py")];
+        candidates.into_iter().find(|c| !path.is_empty() && self.exists(c))
+    }
+
+    /// Directories absolute imports are resolved from: the project root,
+    /// `src/`, and the directory above the file's top-level package
+    fn python_roots(&self, file: &str) -> Vec<String> {
+        let mut dir = split_parent(file).0;
+        while !dir.is_empty() && self.exists(&join(dir, "__init__.py")) {
+            dir = split_parent(dir).0;
+        }
+
+        let mut roots = vec![String::new(), "src".to_string()];
+        if !roots.iter().any(|r| r == dir) {
+            roots.push(dir.to_string());
+        }
+        roots
+    }
+
+    // ============ Rust ============
+
+    /// The directory module paths are relative to, and the file's module path
+    fn rust_module(&self, file: &str) -> Option<(String, Vec<String>)> {
+        let crate_root = self
+            .crate_roots
+            .iter()
+            .find(|dir| dir.is_empty() || file.starts_with(&format!("{}/", dir)))?;
+        let src = join(crate_root, "src");
+        let relative = file.strip_prefix(&format!("{}/", src))?;
+        let relative = relative.strip_suffix(".rs")?;
+
+        // Binaries under src/bin are crate roots of their own
+        if let Some(bin) = relative.strip_prefix("bin/") {
+            let Some((name, rest)) = bin.split_once('/') else {
+                return Some((join(&src, "bin"), Vec::new()));
+            };
+            let mut segments: Vec<String> = rest.split('/').map(str::to_string).collect();
+            let last = segments.last().map(String::as_str);
+            if last == Some("mod") || (segments.len() == 1 && last == Some("main")) {
+                segments.pop();
+            }
+            return Some((join(&src, &format!("bin/{}", name)), segments));
+        }
+
+        let mut segments: Vec<String> = relative.split('/').map(str::to_string).collect();
+        let last = segments.last().map(String::as_str);
+        if last == Some("mod") || (segments.len() == 1 && matches!(last, Some("main") | Some("lib"))) {
+            segments.pop();
+        }
+        Some((src, segments))
+    }
+
+    fn rust_file(&self, base: &str, segments: &[String]) -> Option<String> {
+        let candidates = if segments.is_empty() {
+            vec![join(base, "lib.rs"), join(base, "main.rs")]
+        } else {
+            let path = join(base, &segments.join("/"));
+            vec![format!("{}.rs", path), join(&path, "mod.rs")]
+        };
+        candidates.into_iter().find(|c| self.exists(c))
+    }
+
+    fn resolve_rust_mod(&self, file: &str, name: &str) -> Resolved {
+        let target = match self.rust_module(file) {
+            Some((base, mut segments)) => {
+                segments.push(name.to_string());
+                self.rust_file(&base, &segments)
+            }
+            // Outside src/ (tests, examples, build scripts) modules sit next to the file
+            None => {
+                let path = join(split_parent(file).0, name);
+                [format!("{}.rs", path), join(&path, "mod.rs")]
+                    .into_iter()
+                    .find(|c| self.exists(c))
+            }
+        };
+
+        target.map_or(Resolved::Unresolved, |t| Resolved::Internal(vec![t]))
+    }
+
+    fn resolve_rust_use(&self, file: &str, path: &str) -> Resolved {
+        let mut parts = path.trim_start_matches("::").split("::").map(str::to_string);
+        let Some(first) = parts.next() else {
+            return Resolved::Unresolved;
+        };
+        let rest: Vec<String> = parts.collect();
+        let module = self.rust_module(file);
+
+        let segments = match (first.as_str(), &module) {
+            ("crate", Some(_)) => rest,
+            ("self", Some((_, current))) => [current.clone(), rest].concat(),
+            ("super", Some((_, current))) => {
+                let mut segments = current.clone();
+                segments.pop();
+                let mut rest = rest.into_iter().peekable();
+                while rest.peek().map(String::as_str) == Some("super") {
+                    rest.next();
+                    segments.pop();
+                }
+                segments.extend(rest);
+                segments
+            }
+            ("crate" | "self" | "super", None) => return Resolved::Unresolved,
+            _ => {
+                // A child module used without a prefix (2018 edition paths)
+                let child = module.as_ref().and_then(|(base, current)| {
+                    let mut segments = current.clone();
+                    segments.push(first.clone());
+                    self.rust_file(base, &segments).map(|_| [segments, rest.clone()].concat())
+                });
+                match child {
+                    Some(segments) => segments,
+                    None if matches!(first.as_str(), "std" | "core" | "alloc") => return Resolved::Unresolved,
+                    None => return Resolved::External(first),
+                }
+            }
+        };
+
+        let Some((base, _)) = module else {
+            return Resolved::Unresolved;
+        };
+
+        // The longest prefix that names a module file; the rest are items in it
+        (0..=segments.len())
+            .rev()
+            .find_map(|len| self.rust_file(&base, &segments[..len]))
+            .map_or(Resolved::Unresolved, |t| Resolved::Internal(vec![t]))
+    }
+
+    // ============ JavaScript / TypeScript ============
+
+    fn resolve_js(&self, file: &str, specifier: &str) -> Resolved {
+        let specifier = specifier.split(['?', '#']).next().unwrap_or(specifier);
+
+        if specifier == "." || specifier == ".." || specifier.starts_with("./") || specifier.starts_with("../") {
+            let Some(base) = normalize(&join(split_parent(file).0, specifier)) else {
+                return Resolved::Unresolved;
+            };
+            return self
+                .js_candidates(&base)
+                .into_iter()
+                .find(|c| self.exists(c))
+                .map_or(Resolved::Unresolved, |t| Resolved::Internal(vec![t]));
+        }
+
+        if specifier.starts_with('/') || specifier.starts_with("node:") {
+            return Resolved::Unresolved;
+        }
+
+        // Package name: `@scope/name` or the first path segment
+        let mut segments = specifier.split('/');
+        let first = segments.next().unwrap_or("");
+        let package = if let Some(scope) = first.strip_prefix('@') {
+            match segments.next() {
+                Some(name) if !scope.is_empty() => format!("{}/{}", first, name),
+                _ => return Resolved::Unresolved,
+            }
+        } else {
+            first.to_string()
+        };
+
+        if package.is_empty() || NODE_BUILTINS.contains(&package.as_str()) {
+            Resolved::Unresolved
+        } else {
+            Resolved::External(package)
+        }
+    }
+
+    fn js_candidates(&self, base: &str) -> Vec<String> {
+        let mut candidates = vec![base.to_string()];
+        for ext in JS_EXTENSIONS {
+            candidates.push(format!("{}.{}", base, ext));
+        }
+        for ext in JS_EXTENSIONS {
+            candidates.push(join(base, &format!("index.{}", ext)));
+        }
+        // TypeScript sources are imported by their compiled `.js` name
+        for (js, ts) in [(".js", ".ts"), (".jsx", ".tsx"), (".mjs", ".mts"), (".cjs", ".cts")] {
+            if let Some(stem) = base.strip_suffix(js) {
+                candidates.push(format!("{}{}", stem, ts));
+            }
+        }
+        candidates
+    }
+
+    // ============ Go ============
+
+    fn resolve_go(&self, path: &str) -> Resolved {
+        for (module, dir) in &self.go_modules {
+            let relative = if path == module {
+                Some("")
+            } else {
+                path.strip_prefix(module.as_str()).and_then(|r| r.strip_prefix('/'))
+            };
+            if let Some(relative) = relative {
+                return self
+                    .go_packages
+                    .get(&join(dir, relative))
+                    .map_or(Resolved::Unresolved, |files| Resolved::Internal(files.clone()));
+            }
+        }
+
+        // Standard library paths have no domain in their first segment
+        let first = path.split('/').next().unwrap_or("");
+        if !first.contains('.') {
+            return Resolved::Unresolved;
+        }
+        let module: Vec<&str> = path.split('/').take(3).collect();
+        Resolved::External(module.join("/"))
+    }
+}
+
+const JS_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts", "vue", "svelte", "json"];
+
+const NODE_BUILTINS: &[&str] = &[
+    "assert", "buffer", "child_process", "cluster", "crypto", "dgram", "dns", "events", "fs",
+    "http", "http2", "https", "module", "net", "os", "path", "perf_hooks", "process",
+    "querystring", "readline", "stream", "string_decoder", "timers", "tls", "tty", "url",
+    "util", "v8", "vm", "worker_threads", "zlib",
+];
+
+const PYTHON_STDLIB: &[&str] = &[
+    "__future__", "abc", "argparse", "array", "ast", "asyncio", "base64", "bisect", "builtins",
+    "calendar", "collections", "concurrent", "configparser", "contextlib", "copy", "csv",
+    "ctypes", "dataclasses", "datetime", "decimal", "difflib", "email", "enum", "errno",
+    "fnmatch", "fractions", "functools", "gc", "getpass", "glob", "gzip", "hashlib", "heapq",
+    "hmac", "html", "http", "importlib", "inspect", "io", "ipaddress", "itertools", "json",
+    "logging", "math", "mimetypes", "multiprocessing", "operator", "os", "pathlib", "pickle",
+    "platform", "pprint", "queue", "random", "re", "secrets", "select", "shlex", "shutil",
+    "signal", "socket", "sqlite3", "ssl", "stat", "statistics", "string", "struct",
+    "subprocess", "sys", "tarfile", "tempfile", "textwrap", "threading", "time", "timeit",
+    "tomllib", "traceback", "types", "typing", "unicodedata", "unittest", "urllib", "uuid",
+    "warnings", "weakref", "xml", "zipfile", "zlib",
+];
+
+pub(super) fn collect_files(node: &FileNode, files: &mut HashSet<String>) {
+    match node {
+        FileNode::File { path, .. } => {
+            files.insert(path_key(path));
+        }
+        FileNode::Directory { children, .. } => {
+            for child in children {
+                collect_files(child, files);
+            }
+        }
+        FileNode::Collapsed { .. } => {}
+    }
+}
+
+fn read_go_module(go_mod: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(go_mod).ok()?;
+    contents
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("module "))
+        .map(|module| module.trim().trim_matches('"').to_string())
+}
+
+/// Split `a/b/c` into (`a/b`, `c`)
+pub(super) fn split_parent(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+pub(super) fn join(base: &str, path: &str) -> String {
+    match (base.is_empty(), path.is_empty()) {
+        (true, _) => path.to_string(),
+        (_, true) => base.to_string(),
+        _ => format!("{}/{}", base, path),
+    }
+}
+
+/// Resolve `.` and `..` segments, returning None if the path leaves the root
+pub(super) fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            _ => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deps::parse_imports;
+    use std::path::PathBuf;
+
+    fn tree(paths: &[&str]) -> FileNode {
+        FileNode::Directory {
+            name: "root".to_string(),
+            path: PathBuf::new(),
+            children: paths.iter().map(|p| FileNode::test_file(p, 0)).collect(),
+            churn: None,
+        }
+    }
+
+    fn deps_for(paths: &[&str], sources: &[(&str, &str, &str)]) -> Dependencies {
+        let imports: Vec<(PathBuf, Vec<RawImport>)> = sources
+            .iter()
+            .map(|(file, language, source)| (PathBuf::from(file), parse_imports(language, source)))
+            .collect();
+        let imports = imports.iter().map(|(path, list)| (path.as_path(), list.as_slice()));
+        build_dependencies(Path::new("/nonexistent"), &tree(paths), imports)
+    }
+
+    #[test]
+    fn test_resolve_python_and_js() {
+        let deps = deps_for(
+            &["app/__init__.py", "app/routes.py", "app/models/__init__.py", "app/models/user.py", "web/index.ts", "web/lib/api.ts"],
+            &[
+                ("app/routes.py", "python", "import os\nimport flask\nfrom app.models import user\nfrom . import models\n"),
+                ("web/index.ts", "typescript", "import api from './lib/api.js';\nimport React from 'react';\nimport fs from 'fs';\n"),
+            ],
+        );
+
+        assert_eq!(deps.internal["app/routes.py"], vec!["app/models/__init__.py", "app/models/user.py"]);
+        assert_eq!(deps.external_imports["app/routes.py"], vec!["flask"]);
+        assert_eq!(deps.internal["web/index.ts"], vec!["web/lib/api.ts"]);
+        assert_eq!(deps.external_imports["web/index.ts"], vec!["react"]);
+    }
+
+    #[test]
+    fn test_resolve_rust() {
+        let deps = deps_for(
+            &["Cargo.toml", "src/main.rs", "src/cli.rs", "src/map/mod.rs", "src/map/types.rs", "src/commands/tree.rs"],
+            &[
+                ("src/main.rs", "rust", "mod cli;\nmod map;\nuse cli::Cli;\nuse anyhow::Result;\nuse std::env;\n"),
+                ("src/commands/tree.rs", "rust", "use crate::map::{FileNode, SystemMap};\nuse super::super::cli::Cli;\n"),
+            ],
+        );
+
+        assert_eq!(deps.internal["src/main.rs"], vec!["src/cli.rs", "src/map/mod.rs"]);
+        assert_eq!(deps.external_imports["src/main.rs"], vec!["anyhow"]);
+        assert_eq!(deps.internal["src/commands/tree.rs"], vec!["src/cli.rs", "src/map/mod.rs"]);
+    }
+}
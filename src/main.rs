@@ -6,11 +6,17 @@ mod cli;
 mod colors;
 mod commands;
 mod config;
+mod context;
+mod deps;
 mod map;
+mod mcp;
+mod output;
 mod patterns;
 mod scanner;
 
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, HistoryCommand};
+use commands::hotspots::ReportFormat;
+use output::OutputFormat;
 
 fn main() {
     if let Err(e) = run() {
@@ -30,20 +36,55 @@ fn run() -> Result<()> {
     let verbosity = if cli.quiet { 0 } else if cli.verbose { 2 } else { 1 };
 
     match cli.command {
-        Commands::Init { path, force } => {
-            commands::init::execute(path, force, verbosity)?;
+        Commands::Init { path, force, no_gitignore, no_git, no_config, template, threads } => {
+            commands::init::execute(path, force, !no_gitignore, !no_git, !no_config, template, threads, verbosity)?;
         }
-        Commands::Summary { json, yaml } => {
-            commands::summary::execute(json, yaml)?;
+        Commands::Summary { json, yaml, include_generated, auto_update, format } => {
+            let format = OutputFormat::from_flags(format, json, yaml);
+            commands::summary::execute(format, include_generated, auto_update)?;
         }
-        Commands::Tree { path, depth, all } => {
-            commands::tree::execute(path, depth, all)?;
+        Commands::Tree { path, depth, all, counts, lines, auto_update, json, format } => {
+            let format = OutputFormat::from_flags(format, json, false);
+            commands::tree::execute(path, depth, all, counts, &lines, format, auto_update)?;
         }
-        Commands::Update { full } => {
-            commands::update::execute(full, verbosity)?;
+        Commands::Update { full, no_gitignore, no_git, threads } => {
+            commands::update::execute(full, !no_gitignore, !no_git, threads, verbosity)?;
         }
-        Commands::Find { query, file_type, language, purpose } => {
-            commands::find::execute(query, file_type, language, purpose)?;
+        Commands::Find {
+            query, file_type, language, purpose, lines, counts, include_generated, sort, auto_update, json, ndjson, format,
+        } => {
+            let format = OutputFormat::from_flags(format, json, false);
+            let filters = commands::find::Filters::new(&query, file_type, language, purpose)
+                .with_lines(lines)
+                .with_generated(include_generated);
+            commands::find::execute(query, filters, sort, counts, format, ndjson, auto_update)?;
+        }
+        Commands::Deps { file, reverse, depth, json } => {
+            commands::deps::execute(file, reverse, depth, json)?;
+        }
+        Commands::Watch { debounce, interval, no_gitignore } => {
+            commands::watch::execute(debounce, interval, !no_gitignore, verbosity)?;
+        }
+        Commands::Context { budget, format } => {
+            commands::context::execute(budget, format, verbosity)?;
+        }
+        Commands::Diff { old, new, json, format } => {
+            commands::diff::execute(old, new, OutputFormat::from_flags(format, json, false))?;
+        }
+        Commands::History { command } => match command {
+            HistoryCommand::List { json, format } => {
+                commands::history::list(OutputFormat::from_flags(format, json, false))?;
+            }
+            HistoryCommand::Show { id, json, format } => {
+                commands::history::show(&id, OutputFormat::from_flags(format, json, false))?;
+            }
+        },
+        Commands::Hotspots { since, limit, complexity, json, format } => {
+            let format = format.unwrap_or(if json { ReportFormat::Json } else { ReportFormat::Text });
+            commands::hotspots::execute(since, limit, complexity, format)?;
+        }
+        Commands::Mcp { path } => {
+            commands::mcp::execute(path)?;
         }
     }
 
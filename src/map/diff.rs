@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::deps::path_key;
+
+use super::{FileNode, SystemMap};
+
+/// Structural differences between two maps
+#[derive(Debug, Default, Serialize)]
+pub struct MapDiff {
+    pub added: Vec<FileChange>,
+    pub removed: Vec<FileChange>,
+    /// Files that moved with their contents unchanged
+    pub renamed: Vec<Rename>,
+    /// Files whose line count changed
+    pub resized: Vec<Resize>,
+    pub language_changed: Vec<AttributeChange>,
+    pub purpose_changed: Vec<AttributeChange>,
+    pub collapsed_added: Vec<CollapsedChange>,
+    pub collapsed_removed: Vec<CollapsedChange>,
+    pub totals: Totals,
+}
+
+/// A file present in only one of the maps
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChange {
+    pub path: String,
+    pub lines: Option<usize>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Resize {
+    pub path: String,
+    pub old_lines: usize,
+    pub new_lines: usize,
+}
+
+impl Resize {
+    pub fn delta(&self) -> i64 {
+        self.new_lines as i64 - self.old_lines as i64
+    }
+}
+
+/// A language or purpose that differs between the maps
+#[derive(Debug, Serialize)]
+pub struct AttributeChange {
+    pub path: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A directory collapsed in only one of the maps
+#[derive(Debug, Serialize)]
+pub struct CollapsedChange {
+    pub path: String,
+    pub pattern: String,
+    pub file_count: usize,
+}
+
+/// Indexed files and lines in each map
+#[derive(Debug, Default, Serialize)]
+pub struct Totals {
+    pub old_files: usize,
+    pub new_files: usize,
+    pub old_lines: usize,
+    pub new_lines: usize,
+}
+
+/// The attributes of a file that are compared
+struct FileFacts<'a> {
+    lines: Option<usize>,
+    language: Option<&'a str>,
+    purpose: Option<&'a str>,
+    hash: Option<&'a str>,
+}
+
+impl<'a> FileFacts<'a> {
+    fn change(&self, path: &str) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            lines: self.lines,
+            language: self.language.map(str::to_string),
+        }
+    }
+}
+
+impl MapDiff {
+    /// Compare two maps, matching files by path, and removed files to added
+    /// ones by content hash to find renames
+    pub fn between(old: &SystemMap, new: &SystemMap) -> Self {
+        let old_files = file_facts(&old.tree);
+        let new_files = file_facts(&new.tree);
+        let mut diff = MapDiff::default();
+
+        for (path, before) in &old_files {
+            let Some(after) = new_files.get(path) else {
+                continue;
+            };
+            if let (Some(old_lines), Some(new_lines)) = (before.lines, after.lines) {
+                if old_lines != new_lines {
+                    diff.resized.push(Resize { path: path.clone(), old_lines, new_lines });
+                }
+            }
+            if before.language != after.language {
+                diff.language_changed.push(attribute_change(path, before.language, after.language));
+            }
+            if before.purpose != after.purpose {
+                diff.purpose_changed.push(attribute_change(path, before.purpose, after.purpose));
+            }
+        }
+
+        // Added files claim a removed file with the same contents, in path order
+        let mut removed_by_hash: HashMap<&str, Vec<&String>> = HashMap::new();
+        for (path, facts) in &old_files {
+            if let (false, Some(hash)) = (new_files.contains_key(path), facts.hash) {
+                removed_by_hash.entry(hash).or_default().push(path);
+            }
+        }
+        let mut renamed_from = HashSet::new();
+        for (path, facts) in &new_files {
+            if old_files.contains_key(path) {
+                continue;
+            }
+            let source = facts
+                .hash
+                .and_then(|hash| removed_by_hash.get_mut(hash))
+                .filter(|candidates| !candidates.is_empty())
+                .map(|candidates| candidates.remove(0));
+            match source {
+                Some(from) => {
+                    renamed_from.insert(from);
+                    diff.renamed.push(Rename { from: from.clone(), to: path.clone() });
+                }
+                None => diff.added.push(facts.change(path)),
+            }
+        }
+        for (path, facts) in &old_files {
+            if !new_files.contains_key(path) && !renamed_from.contains(path) {
+                diff.removed.push(facts.change(path));
+            }
+        }
+
+        let collapsed = |map: &SystemMap| -> BTreeMap<String, CollapsedChange> {
+            map.patterns_matched
+                .iter()
+                .map(|m| {
+                    let change = CollapsedChange {
+                        path: path_key(&m.path),
+                        pattern: m.pattern.clone(),
+                        file_count: m.files_collapsed,
+                    };
+                    (change.path.clone(), change)
+                })
+                .collect()
+        };
+        // Directories collapsed in both maps drop out of the new side
+        let mut new_collapsed = collapsed(new);
+        diff.collapsed_removed = collapsed(old)
+            .into_iter()
+            .filter_map(|(path, change)| new_collapsed.remove(&path).is_none().then_some(change))
+            .collect();
+        diff.collapsed_added = new_collapsed.into_values().collect();
+
+        let lines = |files: &BTreeMap<String, FileFacts>| files.values().filter_map(|f| f.lines).sum();
+        diff.totals = Totals {
+            old_files: old_files.len(),
+            new_files: new_files.len(),
+            old_lines: lines(&old_files),
+            new_lines: lines(&new_files),
+        };
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.resized.is_empty()
+            && self.language_changed.is_empty()
+            && self.purpose_changed.is_empty()
+            && self.collapsed_added.is_empty()
+            && self.collapsed_removed.is_empty()
+    }
+}
+
+fn attribute_change(path: &str, old: Option<&str>, new: Option<&str>) -> AttributeChange {
+    AttributeChange {
+        path: path.to_string(),
+        old: old.map(str::to_string),
+        new: new.map(str::to_string),
+    }
+}
+
+/// Every indexed file of a tree, by path key
+fn file_facts(tree: &FileNode) -> BTreeMap<String, FileFacts<'_>> {
+    fn visit<'a>(node: &'a FileNode, files: &mut BTreeMap<String, FileFacts<'a>>) {
+        match node {
+            FileNode::File { path, lines, language, purpose, hash, .. } => {
+                files.insert(
+                    path_key(path),
+                    FileFacts {
+                        lines: *lines,
+                        language: language.as_deref(),
+                        purpose: purpose.as_deref(),
+                        hash: hash.as_deref(),
+                    },
+                );
+            }
+            FileNode::Directory { children, .. } => {
+                for child in children {
+                    visit(child, files);
+                }
+            }
+            FileNode::Collapsed { .. } => {}
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    visit(tree, &mut files);
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn file(path: &str, lines: usize, content_hash: &str) -> FileNode {
+        let mut node = FileNode::test_file(path, lines);
+        if let FileNode::File { hash, .. } = &mut node {
+            *hash = Some(content_hash.to_string());
+        }
+        node
+    }
+
+    fn binary(path: &str, content_hash: &str) -> FileNode {
+        let mut node = file(path, 0, content_hash);
+        if let FileNode::File { lines, is_binary, .. } = &mut node {
+            *lines = None;
+            *is_binary = true;
+        }
+        node
+    }
+
+    fn map(files: Vec<FileNode>) -> SystemMap {
+        let mut map = SystemMap::new(PathBuf::from("/work/demo"));
+        if let FileNode::Directory { children, .. } = &mut map.tree {
+            *children = files;
+        }
+        map
+    }
+
+    #[test]
+    fn test_diff_matches_renames_by_hash() {
+        let old = map(vec![
+            binary("assets/logo.png", "ee"),
+            file("src/a.rs", 10, "aa"),
+            file("src/b.rs", 5, "bb"),
+            file("src/gone.rs", 3, "cc"),
+        ]);
+        let new = map(vec![
+            binary("static/logo.png", "ee"),
+            file("src/a.rs", 12, "a2"),
+            file("src/c.rs", 5, "bb"),
+            file("src/new.rs", 7, "dd"),
+        ]);
+        let diff = MapDiff::between(&old, &new);
+
+        let renames: Vec<(&str, &str)> = diff.renamed.iter().map(|r| (r.from.as_str(), r.to.as_str())).collect();
+        assert_eq!(renames, [("src/b.rs", "src/c.rs"), ("assets/logo.png", "static/logo.png")]);
+        assert_eq!(diff.added.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["src/new.rs"]);
+        assert_eq!(diff.removed.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["src/gone.rs"]);
+        assert_eq!(diff.resized[0].delta(), 2);
+        assert_eq!((diff.totals.old_lines, diff.totals.new_lines), (18, 24));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::config::{write_atomic, HistorySettings};
+
+use super::{MapDiff, SystemMap};
+
+/// Extension of snapshot files
+const SNAPSHOT_EXTENSION: &str = ".json.gz";
+
+/// Format of snapshot ids: the scan time of the snapshotted map, in UTC
+const ID_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// A stored snapshot of a previous map
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+impl Snapshot {
+    /// When the snapshotted map was scanned
+    pub fn scanned_at(&self) -> Option<DateTime<Utc>> {
+        NaiveDateTime::parse_from_str(&self.id, ID_FORMAT).ok().map(|t| t.and_utc())
+    }
+
+    pub fn load(&self) -> Result<SystemMap> {
+        let file = File::open(&self.path).with_context(|| format!("Failed to open {}", self.path.display()))?;
+        serde_json::from_reader(GzDecoder::new(BufReader::new(file)))
+            .with_context(|| format!("Invalid snapshot: {}", self.path.display()))
+    }
+}
+
+/// Snapshot the map being replaced, unless snapshots are disabled or its
+/// structure matches the map replacing it, then apply the retention limits.
+///
+/// Returns the id of the new snapshot, if one was written.
+pub fn record_snapshot(
+    dir: &Path,
+    previous: &SystemMap,
+    current: &SystemMap,
+    settings: &HistorySettings,
+) -> Result<Option<String>> {
+    if !settings.enabled || MapDiff::between(previous, current).is_empty() {
+        return Ok(None);
+    }
+    let id = save_snapshot(dir, previous)?;
+    prune_snapshots(dir, settings.keep, settings.max_age_days)?;
+    Ok(Some(id))
+}
+
+/// Write a gzip-compressed snapshot of a map, named after its scan time
+pub fn save_snapshot(dir: &Path, map: &SystemMap) -> Result<String> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    let id = map.scanned_at.format(ID_FORMAT).to_string();
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, map)?;
+    encoder.flush()?;
+    write_atomic(&dir.join(format!("{}{}", id, SNAPSHOT_EXTENSION)), &encoder.finish()?)?;
+    Ok(id)
+}
+
+/// Every snapshot in the directory, oldest first
+pub fn list_snapshots(dir: &Path) -> Result<Vec<Snapshot>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots: Vec<Snapshot> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let id = name.strip_suffix(SNAPSHOT_EXTENSION)?.to_string();
+            Some(Snapshot { id, path: entry.path() })
+        })
+        .collect();
+    snapshots.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(snapshots)
+}
+
+/// Find a snapshot by id, a unique prefix of one, or `latest`
+pub fn find_snapshot(dir: &Path, id: &str) -> Result<Snapshot> {
+    let snapshots = list_snapshots(dir)?;
+    if id == "latest" {
+        return snapshots
+            .last()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No snapshots in {}", dir.display()));
+    }
+
+    let matches: Vec<&Snapshot> = snapshots.iter().filter(|s| s.id.starts_with(id)).collect();
+    match matches.as_slice() {
+        [snapshot] => Ok((*snapshot).clone()),
+        [] => bail!("No snapshot '{}'. Run 'sysmap history list' to see them.", id),
+        _ => bail!("Snapshot id '{}' is ambiguous ({} matches)", id, matches.len()),
+    }
+}
+
+/// Delete the oldest snapshots past `keep`, and any older than `max_age_days`
+pub fn prune_snapshots(dir: &Path, keep: usize, max_age_days: Option<u32>) -> Result<usize> {
+    let snapshots = list_snapshots(dir)?;
+    let excess = snapshots.len().saturating_sub(keep);
+    let cutoff = max_age_days.map(|days| Utc::now() - Duration::days(days as i64));
+
+    let mut removed = 0;
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        let expired = cutoff.is_some_and(|cutoff| snapshot.scanned_at().is_some_and(|t| t < cutoff));
+        if i < excess || expired {
+            fs::remove_file(&snapshot.path)
+                .with_context(|| format!("Failed to remove {}", snapshot.path.display()))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_round_trip_and_retention() {
+        let dir = TempDir::new().unwrap();
+        let mut map = SystemMap::new(PathBuf::from("/work/demo"));
+        for day in 1..=4 {
+            map.scanned_at = Utc.with_ymd_and_hms(2025, 1, day, 12, 0, 0).unwrap();
+            save_snapshot(dir.path(), &map).unwrap();
+        }
+
+        assert_eq!(prune_snapshots(dir.path(), 3, None).unwrap(), 1);
+        let ids: Vec<String> = list_snapshots(dir.path()).unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, ["20250102T120000Z", "20250103T120000Z", "20250104T120000Z"]);
+
+        let latest = find_snapshot(dir.path(), "latest").unwrap();
+        assert_eq!(latest.load().unwrap().scanned_at, map.scanned_at);
+        assert_eq!(find_snapshot(dir.path(), "20250103").unwrap().id, "20250103T120000Z");
+        assert!(find_snapshot(dir.path(), "2025").is_err());
+    }
+}
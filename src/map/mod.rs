@@ -1,3 +1,7 @@
+mod diff;
+mod history;
 mod types;
 
+pub use diff::*;
+pub use history::*;
 pub use types::*;
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// The complete system map for a project
@@ -14,12 +15,24 @@ pub struct SystemMap {
     /// Detected project type information
     pub project_type: ProjectType,
 
+    /// Every directory with its own project markers, the root included
+    #[serde(default)]
+    pub projects: Vec<SubProject>,
+
     /// When this map was created/updated
     pub scanned_at: DateTime<Utc>,
 
     /// The file tree structure
     pub tree: FileNode,
 
+    /// Import relationships between files
+    #[serde(default)]
+    pub dependencies: Dependencies,
+
+    /// Git history read by the scan, if the project is in a repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitMeta>,
+
     /// Patterns that were matched and collapsed
     pub patterns_matched: Vec<MatchedPattern>,
 
@@ -28,7 +41,7 @@ pub struct SystemMap {
 }
 
 /// Information about the detected project type
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProjectType {
     /// All detected languages in the project (first is primary)
     pub languages: Vec<String>,
@@ -40,6 +53,62 @@ pub struct ProjectType {
     pub detected_from: Vec<String>,
 }
 
+/// A directory holding project markers: a package of a monorepo or
+/// workspace, or a project nested inside another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubProject {
+    /// Directory relative to the project root (empty for the root itself)
+    pub path: PathBuf,
+
+    /// Package name declared by its manifest
+    pub name: Option<String>,
+
+    /// Languages and framework detected from its markers
+    #[serde(flatten)]
+    pub project_type: ProjectType,
+
+    /// The workspace root listing this project as a member
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<PathBuf>,
+
+    /// Members, if this project is a workspace root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<PathBuf>,
+}
+
+/// Lines of a file by kind; they add up to the file's line count
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineCounts {
+    pub code: usize,
+    pub comment: usize,
+    pub blank: usize,
+}
+
+impl std::ops::AddAssign for LineCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.code += other.code;
+        self.comment += other.comment;
+        self.blank += other.blank;
+    }
+}
+
+/// Text encoding of a file, detected from its contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// UTF-8 starting with a byte order mark
+    #[serde(rename = "utf-8-bom")]
+    Utf8Bom,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    /// Not valid UTF-8, but text: read as Latin-1
+    #[serde(rename = "latin-1")]
+    Latin1,
+}
+
 /// A node in the file tree (either file, directory, or collapsed)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -49,9 +118,31 @@ pub enum FileNode {
         name: String,
         path: PathBuf,
         lines: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        counts: Option<LineCounts>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encoding: Option<Encoding>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        is_binary: bool,
+        /// Text too large to analyze: lines were counted, but not by kind,
+        /// and imports and entry points were not looked for
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        large: bool,
+        /// Produced by a code generator, bundler or package manager
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        generated: bool,
+        /// Third-party code checked into the project
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        vendored: bool,
         language: Option<String>,
         purpose: Option<String>,
         modified: Option<DateTime<Utc>>,
+        /// Hash of the contents, to match renamed files
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hash: Option<String>,
+        /// Commit history, when the scan read the git repository
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        git: Option<GitInfo>,
     },
 
     /// A directory with children
@@ -59,6 +150,9 @@ pub enum FileNode {
         name: String,
         path: PathBuf,
         children: Vec<FileNode>,
+        /// Distinct commits within the git window touching any file beneath it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        churn: Option<usize>,
     },
 
     /// A collapsed directory (e.g., node_modules, .venv)
@@ -72,6 +166,12 @@ pub enum FileNode {
 }
 
 impl FileNode {
+    /// Whether this is a generated or vendored file, left out of stats and
+    /// searches by default
+    pub fn is_generated_or_vendored(&self) -> bool {
+        matches!(self, FileNode::File { generated, vendored, .. } if *generated || *vendored)
+    }
+
     pub fn name(&self) -> &str {
         match self {
             FileNode::File { name, .. } => name,
@@ -80,7 +180,6 @@ impl FileNode {
         }
     }
 
-    #[allow(dead_code)]
     pub fn path(&self) -> &PathBuf {
         match self {
             FileNode::File { path, .. } => path,
@@ -89,6 +188,30 @@ impl FileNode {
         }
     }
 
+    /// A text file with its language guessed from the name, for tests
+    #[cfg(test)]
+    pub fn test_file(path: &str, lines: usize) -> Self {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let language = crate::patterns::LanguageDetector::default().detect(&name, None);
+        FileNode::File {
+            language: language.map(str::to_string),
+            name,
+            path,
+            lines: Some(lines),
+            counts: None,
+            encoding: None,
+            is_binary: false,
+            large: false,
+            generated: false,
+            vendored: false,
+            purpose: None,
+            modified: None,
+            hash: None,
+            git: None,
+        }
+    }
+
     pub fn is_directory(&self) -> bool {
         matches!(self, FileNode::Directory { .. })
     }
@@ -113,6 +236,67 @@ impl FileNode {
     }
 }
 
+/// Import graph parsed from source files, keyed by path relative to the root
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Dependencies {
+    /// Project files each file imports
+    #[serde(default)]
+    pub internal: BTreeMap<String, Vec<String>>,
+
+    /// Third-party modules or packages each file imports
+    #[serde(default)]
+    pub external_imports: BTreeMap<String, Vec<String>>,
+
+    /// Packages declared in manifest files (Cargo.toml, package.json, ...)
+    #[serde(default)]
+    pub packages: Vec<Package>,
+}
+
+/// A package declared in a manifest file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Package {
+    pub name: String,
+
+    /// Version constraint as written in the manifest
+    pub version: Option<String>,
+
+    pub scope: DependencyScope,
+
+    /// Manifest the package was declared in, relative to the root
+    pub source: String,
+}
+
+/// Whether a package is needed at runtime, for development, or to build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyScope {
+    Runtime,
+    Dev,
+    Build,
+}
+
+/// Commit history of a file in the git repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitInfo {
+    /// Time of the last commit touching the file
+    pub last_commit: DateTime<Utc>,
+    /// Hash of that commit
+    pub commit: String,
+    /// Author of that commit
+    pub author: String,
+    /// Commits touching the file within the window
+    pub commits: usize,
+}
+
+/// How the git history recorded in a map was read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitMeta {
+    /// The commit checked out when the map was built
+    pub head: String,
+    /// Days of history counted towards `commits` and `churn`
+    pub window_days: u32,
+}
+
 /// Record of a pattern that was matched
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchedPattern {
@@ -134,20 +318,14 @@ pub struct ScanMeta {
     /// Total directories found
     pub total_dirs: usize,
 
+    /// Entries skipped by .gitignore, .ignore and git exclude rules
+    #[serde(default)]
+    pub ignored_entries: usize,
+
     /// Time taken to scan (milliseconds)
     pub scan_time_ms: u64,
 }
 
-impl Default for ProjectType {
-    fn default() -> Self {
-        Self {
-            languages: Vec::new(),
-            framework: None,
-            detected_from: Vec::new(),
-        }
-    }
-}
-
 impl SystemMap {
     /// Create a new empty system map
     pub fn new(root: PathBuf) -> Self {
@@ -155,6 +333,7 @@ impl SystemMap {
             version: "1.0".to_string(),
             root: root.clone(),
             project_type: ProjectType::default(),
+            projects: Vec::new(),
             scanned_at: Utc::now(),
             tree: FileNode::Directory {
                 name: root
@@ -163,12 +342,16 @@ impl SystemMap {
                     .unwrap_or_else(|| "root".to_string()),
                 path: root,
                 children: Vec::new(),
+                churn: None,
             },
+            dependencies: Dependencies::default(),
+            git: None,
             patterns_matched: Vec::new(),
             meta: ScanMeta {
                 total_files: 0,
                 indexed_files: 0,
                 total_dirs: 0,
+                ignored_entries: 0,
                 scan_time_ms: 0,
             },
         }
@@ -177,8 +360,7 @@ impl SystemMap {
     /// Save the map to a JSON file
     pub fn save(&self, path: &std::path::Path) -> anyhow::Result<()> {
         let json = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, json)?;
-        Ok(())
+        crate::config::write_atomic(path, json.as_bytes())
     }
 
     /// Load a map from a JSON file
@@ -0,0 +1,331 @@
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+use crate::commands::summary::build_summary;
+use crate::config::map_path;
+use crate::map::SystemMap;
+
+use super::tools;
+
+/// Protocol revisions this server understands, newest first
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const MAP_URI: &str = "sysmap://map";
+const SUMMARY_URI: &str = "sysmap://summary";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// A JSON-RPC error response
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Answers MCP requests from the map of one project, reloading `map.json`
+/// whenever it changes on disk (e.g. under `sysmap watch`)
+pub struct Server {
+    root: PathBuf,
+    map: Option<SystemMap>,
+    loaded_at: Option<SystemTime>,
+}
+
+impl Server {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            map: None,
+            loaded_at: None,
+        }
+    }
+
+    /// Read newline-delimited messages until the input closes, writing one
+    /// line per response
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(message),
+                Err(e) => Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))),
+            };
+
+            if let Some(response) = response {
+                serde_json::to_writer(&mut output, &response)?;
+                writeln!(output)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle one message (or batch), returning the response to send, if any
+    pub fn handle(&mut self, message: Value) -> Option<Value> {
+        if let Value::Array(batch) = message {
+            let responses: Vec<Value> = batch.into_iter().filter_map(|m| self.handle(m)).collect();
+            return (!responses.is_empty()).then_some(Value::Array(responses));
+        }
+
+        let Value::Object(message) = message else {
+            return Some(error_response(Value::Null, RpcError::new(INVALID_REQUEST, "Expected an object")));
+        };
+        let id = message.get("id").cloned();
+
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            // Responses to requests we never send are ignored
+            if message.contains_key("result") || message.contains_key("error") {
+                return None;
+            }
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                RpcError::new(INVALID_REQUEST, "Missing method"),
+            ));
+        };
+
+        // Notifications (initialized, cancelled, ...) need no answer
+        let id = id?;
+
+        let params = match message.get("params") {
+            None | Some(Value::Null) => Map::new(),
+            Some(Value::Object(params)) => params.clone(),
+            Some(_) => {
+                return Some(error_response(id, RpcError::new(INVALID_PARAMS, "Params must be an object")));
+            }
+        };
+
+        Some(match self.dispatch(method, &params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    fn dispatch(&mut self, method: &str, params: &Map<String, Value>) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => {
+                let requested = params.get("protocolVersion").and_then(Value::as_str);
+                let version = requested
+                    .filter(|v| PROTOCOL_VERSIONS.contains(v))
+                    .unwrap_or(PROTOCOL_VERSIONS[0]);
+
+                Ok(json!({
+                    "protocolVersion": version,
+                    "capabilities": {
+                        "tools": {},
+                        "resources": {}
+                    },
+                    "serverInfo": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION")
+                    },
+                    "instructions": "Structure of the project: call `summary` first, then `tree`, `find`, `deps` and `file_info` to drill down. Paths are relative to the project root."
+                }))
+            }
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools::list() })),
+            "tools/call" => {
+                let name = params
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing tool name"))?;
+                if !tools::TOOL_NAMES.contains(&name) {
+                    return Err(RpcError::new(INVALID_PARAMS, format!("Unknown tool: {}", name)));
+                }
+                let args = match params.get("arguments") {
+                    Some(Value::Object(args)) => args.clone(),
+                    _ => Map::new(),
+                };
+
+                // Tool failures are reported to the model, not as protocol errors
+                let root = self.root.clone();
+                let result = self
+                    .map()
+                    .and_then(|map| tools::call(name, &args, map, &root))
+                    .and_then(|value| Ok(serde_json::to_string_pretty(&value)?));
+                Ok(match result {
+                    Ok(text) => tool_result(text, false),
+                    Err(e) => tool_result(e.to_string(), true),
+                })
+            }
+            "resources/list" => Ok(json!({
+                "resources": [
+                    {
+                        "uri": MAP_URI,
+                        "name": "map.json",
+                        "description": "The full project map: tree, project type, dependencies and scan metadata",
+                        "mimeType": "application/json"
+                    },
+                    {
+                        "uri": SUMMARY_URI,
+                        "name": "summary",
+                        "description": "Compressed project overview, as returned by the summary tool",
+                        "mimeType": "application/json"
+                    }
+                ]
+            })),
+            "resources/read" => {
+                let uri = params
+                    .get("uri")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing uri"))?;
+                if uri != MAP_URI && uri != SUMMARY_URI {
+                    return Err(RpcError::new(RESOURCE_NOT_FOUND, format!("Resource not found: {}", uri)));
+                }
+
+                let map = self.map().map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))?;
+                let text = if uri == MAP_URI {
+                    serde_json::to_string_pretty(map)
+                } else {
+                    serde_json::to_string_pretty(&build_summary(map, false))
+                }
+                .map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))?;
+
+                Ok(json!({
+                    "contents": [
+                        { "uri": uri, "mimeType": "application/json", "text": text }
+                    ]
+                }))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        }
+    }
+
+    /// The current map, reloaded if `map.json` changed since it was last read
+    fn map(&mut self) -> Result<&SystemMap> {
+        let path = map_path(&self.root);
+        let modified = modified_time(&path);
+
+        if self.map.is_none() || modified != self.loaded_at {
+            let map = SystemMap::load(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path.display(), e))?;
+            self.map = Some(map);
+            self.loaded_at = modified;
+        }
+
+        Ok(self.map.as_ref().expect("map was just loaded"))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn tool_result(text: String, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ensure_sysmap_dir;
+    use crate::scanner::{scan_incremental, ScannerConfig};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn server_for(files: &[(&str, &str)]) -> (TempDir, Server) {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let config = ScannerConfig {
+            show_progress: false,
+            ..ScannerConfig::default()
+        };
+        let output = scan_incremental(&root, &config, None).unwrap();
+        ensure_sysmap_dir(&root).unwrap();
+        output.map.save(&map_path(&root)).unwrap();
+
+        (temp, Server::new(root))
+    }
+
+    fn request(server: &mut Server, method: &str, params: Value) -> Value {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        server.handle(message).unwrap()
+    }
+
+    #[test]
+    fn test_initialize_and_list() {
+        let (_temp, mut server) = server_for(&[("main.py", "print('hi')\n")]);
+
+        let response = request(&mut server, "initialize", json!({ "protocolVersion": "2024-11-05" }));
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server.handle(notification).is_none());
+
+        let response = request(&mut server, "tools/list", json!({}));
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, tools::TOOL_NAMES);
+
+        let response = request(&mut server, "bogus", json!({}));
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_tool_calls() {
+        let (_temp, mut server) = server_for(&[
+            ("app/main.py", "from app import util\n"),
+            ("app/util.py", "X = 1\n"),
+        ]);
+
+        let response = request(&mut server, "tools/call", json!({
+            "name": "file_info",
+            "arguments": { "path": "app/util.py" }
+        }));
+        let result = &response["result"];
+        assert_eq!(result["isError"], false);
+        let info: Value = serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(info["kind"], "file");
+        assert_eq!(info["imported_by"], json!(["app/main.py"]));
+
+        let response = request(&mut server, "tools/call", json!({
+            "name": "find",
+            "arguments": { "query": 7 }
+        }));
+        assert_eq!(response["result"]["isError"], true);
+
+        let response = request(&mut server, "resources/read", json!({ "uri": MAP_URI }));
+        let text = response["result"]["contents"][0]["text"].as_str().unwrap();
+        assert!(serde_json::from_str::<SystemMap>(text).is_ok());
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+use crate::commands::deps::{build_json, resolve_target};
+use crate::commands::find::{find_matches, sort_matches, FindOutput, Filters, SortOrder};
+use crate::commands::summary::build_summary;
+use crate::commands::tree::{find_node, TreeOutput};
+use crate::deps::path_key;
+use crate::map::{FileNode, SystemMap};
+use crate::output::Entry;
+
+/// Names of the tools the server offers
+pub const TOOL_NAMES: &[&str] = &["summary", "tree", "find", "deps", "file_info"];
+
+/// Tool descriptions and input schemas for `tools/list`
+pub fn list() -> Value {
+    json!([
+        {
+            "name": "summary",
+            "description": "Compressed overview of the project: languages, framework, source and test directories, entry points, key directories, declared packages and collapsed directories.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "include_generated": { "type": "boolean", "description": "Count generated and vendored files in the statistics (default: false)" }
+                }
+            }
+        },
+        {
+            "name": "tree",
+            "description": "Directory tree of the project or of one directory, with line counts, languages and purposes. Dependency and build directories are collapsed into counts.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory relative to the project root (default: the root)" },
+                    "depth": { "type": "integer", "minimum": 0, "description": "Maximum depth (default: 3)" }
+                }
+            }
+        },
+        {
+            "name": "find",
+            "description": "Search files and directories by name, optionally filtered by extension, language or purpose.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Case-insensitive substring of the file name" },
+                    "type": { "type": "string", "description": "File extension, e.g. py or .rs" },
+                    "language": { "type": "string", "description": "Language, e.g. python or rust" },
+                    "purpose": { "type": "string", "description": "Purpose: entry, module, test or config" },
+                    "include_generated": { "type": "boolean", "description": "Also match generated and vendored files (default: false)" },
+                    "sort": { "type": "string", "enum": ["path", "recent", "churn"], "description": "Result order: tree order, most recently committed, or most commits in the git window (default: path)" }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "deps",
+            "description": "Internal import graph: the project files a file imports (or, with reverse, the files importing it), plus external packages. Without a file, the whole graph.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": { "type": "string", "description": "File relative to the project root (default: every file)" },
+                    "reverse": { "type": "boolean", "description": "Show the files importing this one" },
+                    "depth": { "type": "integer", "minimum": 1, "description": "How many levels to follow (default: 1)" }
+                }
+            }
+        },
+        {
+            "name": "file_info",
+            "description": "Everything the map records about one path: kind, lines, language, purpose and modification time, direct imports and importers for files, and immediate children for directories.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path relative to the project root" }
+                },
+                "required": ["path"]
+            }
+        }
+    ])
+}
+
+/// Run a tool against the map
+pub fn call(name: &str, args: &Map<String, Value>, map: &SystemMap, root: &Path) -> Result<Value> {
+    match name {
+        "summary" => Ok(build_summary(map, bool_arg(args, "include_generated")?.unwrap_or(false))),
+        "tree" => {
+            let depth = usize_arg(args, "depth")?.unwrap_or(3);
+            let node = match string_arg(args, "path")? {
+                Some(path) => lookup(map, &path)?,
+                None => &map.tree,
+            };
+            Ok(serde_json::to_value(TreeOutput::new(node, depth))?)
+        }
+        "find" => {
+            let query = string_arg(args, "query")?
+                .ok_or_else(|| anyhow::anyhow!("Missing argument: query"))?;
+            let filters = Filters::new(
+                &query,
+                string_arg(args, "type")?,
+                string_arg(args, "language")?,
+                string_arg(args, "purpose")?,
+            )
+            .with_generated(bool_arg(args, "include_generated")?.unwrap_or(false));
+            let mut matches = Vec::new();
+            find_matches(&map.tree, &filters, &mut |node| matches.push(node));
+            let sort = match string_arg(args, "sort")? {
+                Some(sort) => SortOrder::from_str(&sort, true)
+                    .map_err(|_| anyhow::anyhow!("Argument 'sort' must be path, recent or churn"))?,
+                None => SortOrder::Path,
+            };
+            sort_matches(&mut matches, sort);
+            Ok(serde_json::to_value(FindOutput::new(&query, &matches))?)
+        }
+        "deps" => {
+            let target = match string_arg(args, "file")? {
+                Some(file) => Some(resolve_target(root, root, Path::new(&file), &map.tree)?),
+                None => None,
+            };
+            let reverse = bool_arg(args, "reverse")?.unwrap_or(false);
+            let depth = usize_arg(args, "depth")?.unwrap_or(1);
+            Ok(build_json(&map.dependencies, target.as_deref(), reverse, depth))
+        }
+        "file_info" => {
+            let path = string_arg(args, "path")?
+                .ok_or_else(|| anyhow::anyhow!("Missing argument: path"))?;
+            file_info(map, lookup(map, &path)?)
+        }
+        _ => anyhow::bail!("Unknown tool: {}", name),
+    }
+}
+
+fn file_info(map: &SystemMap, node: &FileNode) -> Result<Value> {
+    let mut value = serde_json::to_value(Entry::from_node(node))?;
+
+    match node {
+        FileNode::File { path, .. } => {
+            let key = path_key(path);
+            let deps = &map.dependencies;
+            let imported_by: Vec<&String> = deps
+                .internal
+                .iter()
+                .filter(|(_, targets)| targets.contains(&key))
+                .map(|(source, _)| source)
+                .collect();
+
+            value["imports"] = json!(deps.internal.get(&key).cloned().unwrap_or_default());
+            value["imported_by"] = json!(imported_by);
+            value["external"] = json!(deps.external_imports.get(&key).cloned().unwrap_or_default());
+        }
+        FileNode::Directory { children, .. } => {
+            let entries: Vec<Entry> = children.iter().map(Entry::from_node).collect();
+            value["children"] = serde_json::to_value(entries)?;
+        }
+        FileNode::Collapsed { reason, file_count, dir_count, .. } => {
+            value["reason"] = json!(reason);
+            value["file_count"] = json!(file_count);
+            value["dir_count"] = json!(dir_count);
+        }
+    }
+
+    Ok(value)
+}
+
+fn lookup<'a>(map: &'a SystemMap, path: &str) -> Result<&'a FileNode> {
+    find_node(&map.tree, &PathBuf::from(path))
+        .ok_or_else(|| anyhow::anyhow!("Path not found: {}", path))
+}
+
+fn string_arg(args: &Map<String, Value>, key: &str) -> Result<Option<String>> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => anyhow::bail!("Argument '{}' must be a string", key),
+    }
+}
+
+fn usize_arg(args: &Map<String, Value>, key: &str) -> Result<Option<usize>> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .map(|n| Some(n as usize))
+            .ok_or_else(|| anyhow::anyhow!("Argument '{}' must be a non-negative integer", key)),
+    }
+}
+
+fn bool_arg(args: &Map<String, Value>, key: &str) -> Result<Option<bool>> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Bool(b)) => Ok(Some(*b)),
+        Some(_) => anyhow::bail!("Argument '{}' must be a boolean", key),
+    }
+}
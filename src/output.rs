@@ -0,0 +1,161 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::deps::path_key;
+use crate::map::{Encoding, FileNode};
+
+/// Output format for commands that can emit structured data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored, human-readable text
+    #[default]
+    Text,
+    /// Pretty-printed JSON
+    Json,
+    /// YAML
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Resolve the legacy `--json`/`--yaml` flags against `--format`
+    pub fn from_flags(format: Option<OutputFormat>, json: bool, yaml: bool) -> Self {
+        match (format, json, yaml) {
+            (Some(format), _, _) => format,
+            (None, true, _) => OutputFormat::Json,
+            (None, _, true) => OutputFormat::Yaml,
+            _ => OutputFormat::Text,
+        }
+    }
+}
+
+/// Print a value as JSON or YAML (text output is up to each command)
+pub fn print_structured<T: Serialize>(value: &T, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        _ => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+    Ok(())
+}
+
+/// Version of the structured `find` and `tree` output.
+///
+/// Bumped whenever a field is renamed, removed or changes meaning; adding
+/// fields does not change it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What kind of node an entry describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Directory,
+    Collapsed,
+}
+
+/// The stable fields describing one node of the map
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    /// Path relative to the project root, `/`-separated
+    pub path: String,
+    pub name: String,
+    pub kind: EntryKind,
+    pub lines: Option<usize>,
+    pub code: Option<usize>,
+    pub comment: Option<usize>,
+    pub blank: Option<usize>,
+    pub encoding: Option<Encoding>,
+    pub is_binary: Option<bool>,
+    pub generated: Option<bool>,
+    pub vendored: Option<bool>,
+    pub language: Option<String>,
+    pub purpose: Option<String>,
+    pub modified: Option<DateTime<Utc>>,
+    /// Time of the last commit touching the file
+    pub last_commit: Option<DateTime<Utc>>,
+    /// Author of that commit
+    pub author: Option<String>,
+    /// Commits within the git window touching the file, or anything in the directory
+    pub churn: Option<usize>,
+}
+
+impl Entry {
+    pub fn from_node(node: &FileNode) -> Self {
+        let mut entry = Self {
+            path: path_key(node.path()),
+            name: node.name().to_string(),
+            kind: EntryKind::File,
+            lines: None,
+            code: None,
+            comment: None,
+            blank: None,
+            encoding: None,
+            is_binary: None,
+            generated: None,
+            vendored: None,
+            language: None,
+            purpose: None,
+            modified: None,
+            last_commit: None,
+            author: None,
+            churn: None,
+        };
+
+        match node {
+            FileNode::File { lines, counts, encoding, is_binary, generated, vendored, language, purpose, modified, git, .. } => {
+                entry.lines = *lines;
+                entry.code = counts.map(|c| c.code);
+                entry.comment = counts.map(|c| c.comment);
+                entry.blank = counts.map(|c| c.blank);
+                entry.encoding = *encoding;
+                entry.is_binary = Some(*is_binary);
+                entry.generated = Some(*generated);
+                entry.vendored = Some(*vendored);
+                entry.language = language.clone();
+                entry.purpose = purpose.clone();
+                entry.modified = *modified;
+                entry.last_commit = git.as_ref().map(|g| g.last_commit);
+                entry.author = git.as_ref().map(|g| g.author.clone());
+                entry.churn = git.as_ref().map(|g| g.commits);
+            }
+            FileNode::Directory { churn, .. } => {
+                entry.kind = EntryKind::Directory;
+                entry.churn = *churn;
+            }
+            FileNode::Collapsed { .. } => entry.kind = EntryKind::Collapsed,
+        }
+
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::LineCounts;
+
+    #[test]
+    fn test_entry_fields_are_stable() {
+        let mut node = FileNode::test_file("src/main.rs", 12);
+        if let FileNode::File { counts, encoding, purpose, .. } = &mut node {
+            *counts = Some(LineCounts { code: 9, comment: 2, blank: 1 });
+            *encoding = Some(Encoding::Utf8);
+            *purpose = Some("entry".to_string());
+        }
+
+        let value = serde_json::to_value(Entry::from_node(&node)).unwrap();
+        let keys: Vec<&str> = value.as_object().unwrap().keys().map(|k| k.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "path", "name", "kind", "lines", "code", "comment", "blank", "encoding", "is_binary",
+                "generated", "vendored", "language", "purpose", "modified", "last_commit", "author", "churn",
+            ]
+        );
+        assert_eq!(value["path"], "src/main.rs");
+        assert_eq!(value["kind"], "file");
+        assert_eq!(value["comment"], 2);
+        assert_eq!(value["encoding"], "utf-8");
+    }
+}
@@ -1,134 +1,118 @@
 use std::collections::HashMap;
 
+use super::thresholds::CollapseThresholds;
+
 /// Pattern for directories that should be collapsed
 #[derive(Debug, Clone)]
 pub struct CollapsePattern {
-    /// Name to match (exact match on directory name)
-    pub name: &'static str,
+    /// Glob matched against the directory name, or its path if it contains a `/`
+    pub name: String,
     /// Human-readable reason for collapsing
-    pub reason: &'static str,
+    pub reason: String,
     /// Optional file that must exist inside to confirm the pattern
-    pub indicator: Option<&'static str>,
+    pub indicator: Option<String>,
 }
 
 /// Pattern for detecting project types
 #[derive(Debug, Clone)]
 pub struct ProjectPattern {
     /// Language name
-    pub language: &'static str,
+    pub language: String,
     /// Files that indicate this project type (any match)
-    pub markers: &'static [&'static str],
+    pub markers: Vec<String>,
     /// Framework detection: (framework_name, package_name_to_look_for)
-    pub frameworks: &'static [(&'static str, &'static str)],
+    pub frameworks: Vec<(String, String)>,
 }
 
 /// Pattern for detecting file purposes
 #[derive(Debug, Clone)]
 pub struct PurposePattern {
-    /// Glob-like pattern to match
-    pub pattern: &'static str,
+    /// Glob matched against the file name, or its path if it contains a `/`
+    pub pattern: String,
     /// Purpose label
-    pub purpose: &'static str,
+    pub purpose: String,
+}
+
+/// The full set of patterns used by a scan (defaults merged with config.toml)
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    pub collapse: Vec<CollapsePattern>,
+    pub ignore: Vec<String>,
+    pub project: Vec<ProjectPattern>,
+    pub purpose: Vec<PurposePattern>,
+    pub generated: Vec<String>,
+    pub vendored: Vec<String>,
+    pub thresholds: CollapseThresholds,
+}
+
+impl CollapsePattern {
+    pub fn new(name: &str, reason: &str, indicator: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            reason: reason.to_string(),
+            indicator: indicator.map(str::to_string),
+        }
+    }
+}
+
+impl ProjectPattern {
+    pub fn new(language: &str, markers: &[&str], frameworks: &[(&str, &str)]) -> Self {
+        Self {
+            language: language.to_string(),
+            markers: markers.iter().map(|m| m.to_string()).collect(),
+            frameworks: frameworks
+                .iter()
+                .map(|(name, hint)| (name.to_string(), hint.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl PurposePattern {
+    pub fn new(pattern: &str, purpose: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            purpose: purpose.to_string(),
+        }
+    }
+}
+
+impl Default for PatternSet {
+    fn default() -> Self {
+        Self {
+            collapse: default_collapse_patterns(),
+            ignore: default_ignore_patterns().into_iter().map(str::to_string).collect(),
+            project: default_project_patterns(),
+            purpose: default_purpose_patterns(),
+            generated: default_generated_patterns().into_iter().map(str::to_string).collect(),
+            vendored: default_vendored_patterns().into_iter().map(str::to_string).collect(),
+            thresholds: CollapseThresholds::default(),
+        }
+    }
 }
 
 /// Default patterns for directories to collapse
 pub fn default_collapse_patterns() -> Vec<CollapsePattern> {
     vec![
-        CollapsePattern {
-            name: "node_modules",
-            reason: "npm packages",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: ".venv",
-            reason: "Python virtualenv",
-            indicator: Some("pyvenv.cfg"),
-        },
-        CollapsePattern {
-            name: "venv",
-            reason: "Python virtualenv",
-            indicator: Some("pyvenv.cfg"),
-        },
-        CollapsePattern {
-            name: "env",
-            reason: "Python virtualenv",
-            indicator: Some("pyvenv.cfg"),
-        },
-        CollapsePattern {
-            name: ".env",
-            reason: "Python virtualenv",
-            indicator: Some("pyvenv.cfg"),
-        },
-        CollapsePattern {
-            name: "__pycache__",
-            reason: "Python bytecode cache",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: ".git",
-            reason: "Git repository",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: "target",
-            reason: "Rust build output",
-            indicator: None, // Could check for ../Cargo.toml
-        },
-        CollapsePattern {
-            name: "dist",
-            reason: "Build output",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: "build",
-            reason: "Build output",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: ".next",
-            reason: "Next.js build cache",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: ".nuxt",
-            reason: "Nuxt build cache",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: "vendor",
-            reason: "Vendored dependencies",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: ".pytest_cache",
-            reason: "Pytest cache",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: ".mypy_cache",
-            reason: "Mypy cache",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: ".ruff_cache",
-            reason: "Ruff cache",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: "coverage",
-            reason: "Test coverage data",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: ".coverage",
-            reason: "Coverage data",
-            indicator: None,
-        },
-        CollapsePattern {
-            name: "htmlcov",
-            reason: "HTML coverage report",
-            indicator: None,
-        },
+        CollapsePattern::new("node_modules", "npm packages", None),
+        CollapsePattern::new(".venv", "Python virtualenv", Some("pyvenv.cfg")),
+        CollapsePattern::new("venv", "Python virtualenv", Some("pyvenv.cfg")),
+        CollapsePattern::new("env", "Python virtualenv", Some("pyvenv.cfg")),
+        CollapsePattern::new(".env", "Python virtualenv", Some("pyvenv.cfg")),
+        CollapsePattern::new("__pycache__", "Python bytecode cache", None),
+        CollapsePattern::new(".git", "Git repository", None),
+        CollapsePattern::new("target", "Rust build output", None), // Could check for ../Cargo.toml
+        CollapsePattern::new("dist", "Build output", None),
+        CollapsePattern::new("build", "Build output", None),
+        CollapsePattern::new(".next", "Next.js build cache", None),
+        CollapsePattern::new(".nuxt", "Nuxt build cache", None),
+        CollapsePattern::new("vendor", "Vendored dependencies", None),
+        CollapsePattern::new(".pytest_cache", "Pytest cache", None),
+        CollapsePattern::new(".mypy_cache", "Mypy cache", None),
+        CollapsePattern::new(".ruff_cache", "Ruff cache", None),
+        CollapsePattern::new("coverage", "Test coverage data", None),
+        CollapsePattern::new(".coverage", "Coverage data", None),
+        CollapsePattern::new("htmlcov", "HTML coverage report", None),
     ]
 }
 
@@ -146,23 +130,75 @@ pub fn default_ignore_patterns() -> Vec<&'static str> {
     ]
 }
 
+/// Default patterns for files produced by code generators, bundlers and
+/// package managers
+pub fn default_generated_patterns() -> Vec<&'static str> {
+    vec![
+        // Protocol buffers and gRPC
+        "*.pb.go",
+        "*.pb.cc",
+        "*.pb.h",
+        "*_pb2.py",
+        "*_pb2.pyi",
+        "*_pb2_grpc.py",
+        "*_pb.js",
+        "*_pb.d.ts",
+        // Dart build_runner
+        "*.g.dart",
+        "*.freezed.dart",
+        // Other generators
+        "*_generated.go",
+        "*.designer.cs",
+        "*.generated.*",
+        // Minified bundles and source maps
+        "*.min.js",
+        "*.min.css",
+        "*.js.map",
+        "*.css.map",
+        // Lock files
+        "package-lock.json",
+        "yarn.lock",
+        "pnpm-lock.yaml",
+        "Cargo.lock",
+        "poetry.lock",
+        "Pipfile.lock",
+        "Gemfile.lock",
+        "composer.lock",
+        "go.sum",
+    ]
+}
+
+/// Default patterns for directories of third-party code checked into the
+/// project; every file beneath a match is vendored
+pub fn default_vendored_patterns() -> Vec<&'static str> {
+    vec![
+        "vendor",
+        "vendors",
+        "third_party",
+        "third-party",
+        "thirdparty",
+        "3rdparty",
+        "bower_components",
+    ]
+}
+
 /// Default patterns for detecting project types
 pub fn default_project_patterns() -> Vec<ProjectPattern> {
     vec![
-        ProjectPattern {
-            language: "rust",
-            markers: &["Cargo.toml"],
-            frameworks: &[
+        ProjectPattern::new(
+            "rust",
+            &["Cargo.toml"],
+            &[
                 ("actix", "actix-web"),
                 ("axum", "axum"),
                 ("rocket", "rocket"),
                 ("tauri", "tauri"),
             ],
-        },
-        ProjectPattern {
-            language: "python",
-            markers: &["pyproject.toml", "setup.py", "requirements.txt", "Pipfile"],
-            frameworks: &[
+        ),
+        ProjectPattern::new(
+            "python",
+            &["pyproject.toml", "setup.py", "requirements.txt", "Pipfile"],
+            &[
                 ("flask", "flask"),
                 ("django", "django"),
                 ("fastapi", "fastapi"),
@@ -170,11 +206,11 @@ pub fn default_project_patterns() -> Vec<ProjectPattern> {
                 ("pytorch", "torch"),
                 ("tensorflow", "tensorflow"),
             ],
-        },
-        ProjectPattern {
-            language: "javascript",
-            markers: &["package.json"],
-            frameworks: &[
+        ),
+        ProjectPattern::new(
+            "javascript",
+            &["package.json"],
+            &[
                 ("react", "react"),
                 ("vue", "vue"),
                 ("angular", "@angular/core"),
@@ -182,36 +218,36 @@ pub fn default_project_patterns() -> Vec<ProjectPattern> {
                 ("express", "express"),
                 ("nest", "@nestjs/core"),
             ],
-        },
-        ProjectPattern {
-            language: "typescript",
-            markers: &["tsconfig.json"],
-            frameworks: &[], // Inherits from JS detection
-        },
-        ProjectPattern {
-            language: "go",
-            markers: &["go.mod"],
-            frameworks: &[
+        ),
+        ProjectPattern::new(
+            "typescript",
+            &["tsconfig.json"],
+            &[], // Inherits from JS detection
+        ),
+        ProjectPattern::new(
+            "go",
+            &["go.mod", "go.work"],
+            &[
                 ("gin", "github.com/gin-gonic/gin"),
                 ("fiber", "github.com/gofiber/fiber"),
             ],
-        },
-        ProjectPattern {
-            language: "java",
-            markers: &["pom.xml", "build.gradle", "build.gradle.kts"],
-            frameworks: &[
+        ),
+        ProjectPattern::new(
+            "java",
+            &["pom.xml", "build.gradle", "build.gradle.kts"],
+            &[
                 ("spring", "spring-boot"),
                 ("quarkus", "quarkus"),
             ],
-        },
-        ProjectPattern {
-            language: "ruby",
-            markers: &["Gemfile"],
-            frameworks: &[
+        ),
+        ProjectPattern::new(
+            "ruby",
+            &["Gemfile"],
+            &[
                 ("rails", "rails"),
                 ("sinatra", "sinatra"),
             ],
-        },
+        ),
     ]
 }
 
@@ -219,34 +255,34 @@ pub fn default_project_patterns() -> Vec<ProjectPattern> {
 pub fn default_purpose_patterns() -> Vec<PurposePattern> {
     vec![
         // Entry points
-        PurposePattern { pattern: "main.py", purpose: "entry" },
-        PurposePattern { pattern: "app.py", purpose: "entry" },
-        PurposePattern { pattern: "__main__.py", purpose: "entry" },
-        PurposePattern { pattern: "main.rs", purpose: "entry" },
-        PurposePattern { pattern: "lib.rs", purpose: "library" },
-        PurposePattern { pattern: "index.js", purpose: "entry" },
-        PurposePattern { pattern: "index.ts", purpose: "entry" },
-        PurposePattern { pattern: "main.go", purpose: "entry" },
+        PurposePattern::new("main.py", "entry"),
+        PurposePattern::new("app.py", "entry"),
+        PurposePattern::new("__main__.py", "entry"),
+        PurposePattern::new("main.rs", "entry"),
+        PurposePattern::new("lib.rs", "library"),
+        PurposePattern::new("index.js", "entry"),
+        PurposePattern::new("index.ts", "entry"),
+        PurposePattern::new("main.go", "entry"),
         
         // Tests
-        PurposePattern { pattern: "test_*.py", purpose: "test" },
-        PurposePattern { pattern: "*_test.py", purpose: "test" },
-        PurposePattern { pattern: "*_test.rs", purpose: "test" },
-        PurposePattern { pattern: "*_test.go", purpose: "test" },
-        PurposePattern { pattern: "*.test.js", purpose: "test" },
-        PurposePattern { pattern: "*.test.ts", purpose: "test" },
-        PurposePattern { pattern: "*.spec.js", purpose: "test" },
-        PurposePattern { pattern: "*.spec.ts", purpose: "test" },
+        PurposePattern::new("test_*.py", "test"),
+        PurposePattern::new("*_test.py", "test"),
+        PurposePattern::new("*_test.rs", "test"),
+        PurposePattern::new("*_test.go", "test"),
+        PurposePattern::new("*.test.js", "test"),
+        PurposePattern::new("*.test.ts", "test"),
+        PurposePattern::new("*.spec.js", "test"),
+        PurposePattern::new("*.spec.ts", "test"),
         
         // Config
-        PurposePattern { pattern: "config.py", purpose: "config" },
-        PurposePattern { pattern: "settings.py", purpose: "config" },
-        PurposePattern { pattern: "config.js", purpose: "config" },
-        PurposePattern { pattern: "config.ts", purpose: "config" },
+        PurposePattern::new("config.py", "config"),
+        PurposePattern::new("settings.py", "config"),
+        PurposePattern::new("config.js", "config"),
+        PurposePattern::new("config.ts", "config"),
         
         // Init files
-        PurposePattern { pattern: "__init__.py", purpose: "init" },
-        PurposePattern { pattern: "mod.rs", purpose: "module" },
+        PurposePattern::new("__init__.py", "init"),
+        PurposePattern::new("mod.rs", "module"),
     ]
 }
 
@@ -299,5 +335,70 @@ pub fn extension_to_language() -> HashMap<&'static str, &'static str> {
     map.insert("md", "markdown");
     map.insert("rst", "rst");
     map.insert("txt", "text");
+    map.insert("pl", "perl");
+    map.insert("pm", "perl");
+    map.insert("mk", "make");
+    map.insert("cmake", "cmake");
+    map.insert("bzl", "starlark");
+    map.insert("groovy", "groovy");
+    map.insert("gradle", "groovy");
+    map
+}
+
+/// Language detection by exact file name, for files without a telling extension
+pub fn filename_to_language() -> HashMap<&'static str, &'static str> {
+    let mut map = HashMap::new();
+    map.insert("Makefile", "make");
+    map.insert("makefile", "make");
+    map.insert("GNUmakefile", "make");
+    map.insert("Dockerfile", "dockerfile");
+    map.insert("Containerfile", "dockerfile");
+    map.insert("Jenkinsfile", "groovy");
+    map.insert("CMakeLists.txt", "cmake");
+    map.insert("BUILD", "starlark");
+    map.insert("BUILD.bazel", "starlark");
+    map.insert("WORKSPACE", "starlark");
+    map.insert("WORKSPACE.bazel", "starlark");
+    map.insert("Tiltfile", "starlark");
+    map.insert("Rakefile", "ruby");
+    map.insert("Gemfile", "ruby");
+    map.insert("Vagrantfile", "ruby");
+    map.insert("Podfile", "ruby");
+    map.insert("Pipfile", "toml");
+    map.insert(".bashrc", "shell");
+    map.insert(".bash_profile", "shell");
+    map.insert(".zshrc", "shell");
+    map.insert(".profile", "shell");
+    map
+}
+
+/// Language detection by the interpreter named in a shebang line
+pub fn interpreter_to_language() -> HashMap<&'static str, &'static str> {
+    let mut map = HashMap::new();
+    map.insert("python", "python");
+    map.insert("pypy", "python");
+    map.insert("node", "javascript");
+    map.insert("nodejs", "javascript");
+    map.insert("deno", "typescript");
+    map.insert("bun", "javascript");
+    map.insert("ts-node", "typescript");
+    map.insert("sh", "shell");
+    map.insert("bash", "shell");
+    map.insert("dash", "shell");
+    map.insert("ksh", "shell");
+    map.insert("zsh", "shell");
+    map.insert("fish", "shell");
+    map.insert("ruby", "ruby");
+    map.insert("perl", "perl");
+    map.insert("php", "php");
+    map.insert("lua", "lua");
+    map.insert("Rscript", "r");
+    map.insert("julia", "julia");
+    map.insert("pwsh", "powershell");
+    map.insert("make", "make");
+    map.insert("groovy", "groovy");
+    map.insert("elixir", "elixir");
+    map.insert("escript", "erlang");
+    map.insert("runhaskell", "haskell");
     map
 }
@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+
+use super::defaults::{extension_to_language, filename_to_language, interpreter_to_language};
+
+/// Lines at the start and end of a file searched for editor modelines
+const MODELINE_LINES: usize = 5;
+
+/// Detects the language of a file from its name and, when read, its first
+/// and last lines.
+///
+/// In order of precedence: an editor modeline, the exact file name, the
+/// shebang interpreter, then the extension.
+pub struct LanguageDetector {
+    extensions: HashMap<&'static str, &'static str>,
+    filenames: HashMap<&'static str, &'static str>,
+    interpreters: HashMap<&'static str, &'static str>,
+    known: HashSet<&'static str>,
+}
+
+impl Default for LanguageDetector {
+    fn default() -> Self {
+        let extensions = extension_to_language();
+        let filenames = filename_to_language();
+        let interpreters = interpreter_to_language();
+        let known = extensions
+            .values()
+            .chain(filenames.values())
+            .chain(interpreters.values())
+            .copied()
+            .collect();
+
+        Self {
+            extensions,
+            filenames,
+            interpreters,
+            known,
+        }
+    }
+}
+
+impl LanguageDetector {
+    /// Detect the language of a file, using its contents when available
+    pub fn detect(&self, name: &str, text: Option<&str>) -> Option<&'static str> {
+        text.and_then(|text| self.modeline_language(text))
+            .or_else(|| self.filenames.get(name).copied())
+            .or_else(|| text.and_then(|text| self.shebang_language(text)))
+            .or_else(|| {
+                let (_, ext) = name.rsplit_once('.')?;
+                self.extensions.get(ext).copied()
+            })
+    }
+
+    /// The interpreter of a `#!` line, looking through `env` and version suffixes
+    fn shebang_language(&self, text: &str) -> Option<&'static str> {
+        let line = text.lines().next()?.strip_prefix("#!")?;
+        let mut words = line.split_whitespace();
+        let mut program = words.next()?.rsplit('/').next()?;
+        if program == "env" {
+            // Skip `env` options (-S, -i) and variable assignments
+            program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+        }
+
+        self.interpreters.get(program).copied().or_else(|| {
+            let unversioned = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+            self.interpreters.get(unversioned).copied()
+        })
+    }
+
+    /// The language named by a vim (`vim: set ft=python:`) or Emacs
+    /// (`-*- mode: python -*-`) modeline near the start or end of the file
+    fn modeline_language(&self, text: &str) -> Option<&'static str> {
+        let lines: Vec<&str> = text.lines().collect();
+        let tail = lines.len().saturating_sub(MODELINE_LINES).max(MODELINE_LINES);
+        let candidates = lines.iter().take(MODELINE_LINES).chain(lines.iter().skip(tail));
+
+        candidates
+            .filter_map(|line| vim_filetype(line).or_else(|| emacs_mode(line)))
+            .find_map(|name| self.language_named(&name.to_lowercase()))
+    }
+
+    /// Map an editor's name for a language to ours
+    fn language_named(&self, name: &str) -> Option<&'static str> {
+        let alias = match name {
+            "sh" | "bash" | "zsh" | "shell-script" => Some("shell"),
+            "py" => Some("python"),
+            "js" => Some("javascript"),
+            "ts" => Some("typescript"),
+            "rb" => Some("ruby"),
+            "c++" => Some("cpp"),
+            "cs" => Some("csharp"),
+            "makefile" => Some("make"),
+            "ps1" => Some("powershell"),
+            "bzl" => Some("starlark"),
+            _ => None,
+        };
+        alias.or_else(|| self.known.get(name).copied())
+    }
+}
+
+/// The `ft=`/`filetype=`/`syntax=` value of a vim modeline
+fn vim_filetype(line: &str) -> Option<&str> {
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| {
+        let i = line.find(marker)?;
+        // The marker must start a word, so `envi:` or `index:` don't count
+        let preceded = line[..i].chars().next_back().is_none_or(char::is_whitespace);
+        preceded.then_some(i + marker.len())
+    })?;
+
+    line[start..]
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .find_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            matches!(key, "ft" | "filetype" | "syn" | "syntax").then_some(value)
+        })
+        .filter(|value| !value.is_empty())
+}
+
+/// The major mode of an Emacs `-*- ... -*-` line
+fn emacs_mode(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (vars, _) = rest.split_once("-*-")?;
+
+    // Either just the mode (`-*- python -*-`) or `var: value;` pairs
+    if !vars.contains(':') {
+        return Some(vars.trim()).filter(|mode| !mode.is_empty());
+    }
+    vars.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once(':')?;
+        (key.trim().eq_ignore_ascii_case("mode")).then(|| value.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        let detector = LanguageDetector::default();
+
+        assert_eq!(detector.detect("main.rs", None), Some("rust"));
+        assert_eq!(detector.detect("Makefile", None), Some("make"));
+        assert_eq!(detector.detect("CMakeLists.txt", None), Some("cmake"));
+        assert_eq!(detector.detect("BUILD.bazel", None), Some("starlark"));
+        assert_eq!(detector.detect("deploy", Some("#!/usr/bin/env python3\n")), Some("python"));
+        assert_eq!(detector.detect("run", Some("#!/usr/bin/env -S node --harmony\n")), Some("javascript"));
+        assert_eq!(detector.detect("build", Some("#!/bin/bash -e\n")), Some("shell"));
+        assert_eq!(detector.detect("tool", Some("#!/opt/bin/unknown\n")), None);
+
+        // Modelines win over names and shebangs
+        assert_eq!(detector.detect("notes.txt", Some("x\n# vim: set ft=ruby ts=2:\n")), Some("ruby"));
+        assert_eq!(detector.detect("script", Some("#!/bin/sh\n# -*- mode: Python; coding: utf-8 -*-\n")), Some("python"));
+        assert_eq!(detector.detect("cfg", Some("; -*- makefile -*-\n")), Some("make"));
+        assert_eq!(detector.detect("a.py", Some("envi: ft=ruby\n")), Some("python"));
+    }
+}
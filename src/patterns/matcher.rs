@@ -1,77 +1,255 @@
 use std::path::Path;
 
-use super::defaults::{CollapsePattern, PurposePattern};
-
-/// Check if a directory name matches a collapse pattern
-pub fn should_collapse<'a>(dir_name: &str, dir_path: &Path, patterns: &'a [CollapsePattern]) -> Option<&'a CollapsePattern> {
-    for pattern in patterns {
-        if dir_name == pattern.name {
-            // If there's an indicator file required, check for it
-            if let Some(indicator) = pattern.indicator {
-                let indicator_path = dir_path.join(indicator);
-                if indicator_path.exists() {
-                    return Some(pattern);
-                }
+use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use super::defaults::{CollapsePattern, PatternSet, PurposePattern};
+
+/// Collapse, ignore, purpose, generated and vendored patterns, compiled once
+/// per scan.
+///
+/// A pattern without a `/` is matched against an entry's name; one with a `/`
+/// is matched against its path from the project root (a leading `/` only
+/// anchors it). `*` and `?` stop at `/`, `**` spans directories, and `[abc]`
+/// and `{a,b}` work as in shells.
+pub struct PatternMatcher {
+    collapse: Vec<CollapsePattern>,
+    collapse_globs: GlobList,
+    ignore: GlobList,
+    purpose: Vec<PurposePattern>,
+    purpose_globs: GlobList,
+    generated: GlobList,
+    vendored: GlobList,
+}
+
+impl PatternMatcher {
+    pub fn new(patterns: &PatternSet) -> Result<Self> {
+        Ok(Self {
+            collapse_globs: GlobList::new(patterns.collapse.iter().map(|p| p.name.as_str()))?,
+            collapse: patterns.collapse.clone(),
+            ignore: GlobList::new(patterns.ignore.iter().map(String::as_str))?,
+            purpose_globs: GlobList::new(patterns.purpose.iter().map(|p| p.pattern.as_str()))?,
+            purpose: patterns.purpose.clone(),
+            generated: GlobList::new(patterns.generated.iter().map(String::as_str))?,
+            vendored: GlobList::new(patterns.vendored.iter().map(String::as_str))?,
+        })
+    }
+
+    /// Check if an entry matches any ignore pattern (`path` is relative to
+    /// the project root and `/`-separated)
+    pub fn should_ignore(&self, name: &str, path: &str) -> bool {
+        self.ignore.is_match(name, path)
+    }
+
+    /// The first collapse pattern matching a directory whose indicator file,
+    /// if it requires one, exists inside it
+    pub fn should_collapse(&self, name: &str, path: &str, dir: &Path) -> Option<&CollapsePattern> {
+        self.collapse_globs
+            .matches(name, path)
+            .into_iter()
+            .map(|i| &self.collapse[i])
+            .find(|pattern| pattern.indicator.as_ref().is_none_or(|indicator| dir.join(indicator).exists()))
+    }
+
+    /// Detect the purpose of a file based on its name and path
+    pub fn detect_purpose(&self, name: &str, path: &str) -> Option<&str> {
+        self.match_purpose(name, path).map(|p| p.purpose.as_str())
+    }
+
+    /// Find the first purpose pattern matching a file
+    pub fn match_purpose(&self, name: &str, path: &str) -> Option<&PurposePattern> {
+        let first = self.purpose_globs.matches(name, path).into_iter().next()?;
+        Some(&self.purpose[first])
+    }
+
+    /// Check if a file matches any generated-file pattern
+    pub fn is_generated(&self, name: &str, path: &str) -> bool {
+        self.generated.is_match(name, path)
+    }
+
+    /// Check if a file is inside a directory matching a vendored pattern
+    pub fn is_vendored(&self, path: &str) -> bool {
+        // Each directory above the file: `a` and `a/b` for `a/b/c.rs`
+        path.match_indices('/').any(|(i, _)| {
+            let dir = &path[..i];
+            let name = dir.rsplit('/').next().unwrap_or(dir);
+            self.vendored.is_match(name, dir)
+        })
+    }
+}
+
+/// Globs compiled into two sets: those matched against an entry's name, and
+/// those containing a `/`, matched against its path
+struct GlobList {
+    names: GlobSet,
+    name_ids: Vec<usize>,
+    paths: GlobSet,
+    path_ids: Vec<usize>,
+}
+
+impl GlobList {
+    fn new<'p>(patterns: impl IntoIterator<Item = &'p str>) -> Result<Self> {
+        let mut names = GlobSetBuilder::new();
+        let mut name_ids = Vec::new();
+        let mut paths = GlobSetBuilder::new();
+        let mut path_ids = Vec::new();
+
+        for (i, pattern) in patterns.into_iter().enumerate() {
+            let trimmed = pattern.trim_end_matches('/');
+            let (anchored, glob) = match trimmed.strip_prefix('/') {
+                Some(rest) => (true, rest),
+                None => (trimmed.contains('/'), trimmed),
+            };
+            let glob = GlobBuilder::new(glob)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid pattern '{}': {}", pattern, e.kind()))?;
+
+            if anchored {
+                paths.add(glob);
+                path_ids.push(i);
             } else {
-                // No indicator required, match on name alone
-                return Some(pattern);
+                names.add(glob);
+                name_ids.push(i);
             }
         }
+
+        Ok(Self {
+            names: names.build()?,
+            name_ids,
+            paths: paths.build()?,
+            path_ids,
+        })
+    }
+
+    fn is_match(&self, name: &str, path: &str) -> bool {
+        self.names.is_match(name) || (!self.paths.is_empty() && self.paths.is_match(path))
     }
-    None
-}
 
-/// Check if a filename matches any ignore pattern
-pub fn should_ignore(filename: &str, patterns: &[&str]) -> bool {
-    for pattern in patterns {
-        if matches_glob(filename, pattern) {
-            return true;
+    /// Indices of the matching patterns, in pattern order
+    fn matches(&self, name: &str, path: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = self.names.matches(name).into_iter().map(|i| self.name_ids[i]).collect();
+        if !self.paths.is_empty() {
+            found.extend(self.paths.matches(path).into_iter().map(|i| self.path_ids[i]));
+            found.sort_unstable();
         }
+        found
     }
-    false
 }
 
-/// Detect the purpose of a file based on its name
-pub fn detect_purpose(filename: &str, patterns: &[PurposePattern]) -> Option<&'static str> {
-    for pattern in patterns {
-        if matches_glob(filename, pattern.pattern) {
-            return Some(pattern.purpose);
+/// Whether a file is a program entry point judging by its location and
+/// contents, for files whose name doesn't already give them a purpose
+pub fn is_entry_point(path: &Path, language: Option<&str>, text: &str) -> bool {
+    let mut lines = text.lines().map(str::trim);
+    match language {
+        Some("python") => lines.any(|l| l.starts_with("if __name__") && l.contains("__main__")),
+        Some("go") => {
+            let mut package_main = false;
+            let mut func_main = false;
+            for line in lines {
+                package_main |= line == "package main";
+                func_main |= line.starts_with("func main()");
+            }
+            package_main && func_main
+        }
+        Some("rust") => {
+            // Binaries under `src/bin/`, as files or directories with a `main.rs`
+            let dirs: Vec<&str> = path
+                .parent()
+                .into_iter()
+                .flat_map(|p| p.iter())
+                .filter_map(|c| c.to_str())
+                .collect();
+            let in_src_bin = dirs.windows(2).any(|pair| pair == ["src", "bin"]);
+            in_src_bin && lines.any(|l| l.starts_with("fn main(") || l.starts_with("async fn main("))
         }
+        _ => false,
     }
-    None
 }
 
-/// Simple glob matching (supports * wildcard)
-fn matches_glob(text: &str, pattern: &str) -> bool {
-    if pattern == text {
-        return true;
-    }
+/// Lines at the start of a file searched for a generated-code header
+const GENERATED_HEADER_LINES: usize = 10;
 
-    if pattern.starts_with('*') && pattern.ends_with('*') {
-        // *contains*
-        let middle = &pattern[1..pattern.len() - 1];
-        return text.contains(middle);
-    }
+/// Markers that open a comment line
+const COMMENT_PREFIXES: &[&str] = &["//", "#", "/*", "*", "--", "<!--", ";", "%"];
 
-    if pattern.starts_with('*') {
-        // *suffix
-        let suffix = &pattern[1..];
-        return text.ends_with(suffix);
-    }
+/// How the header comments of common generators begin (matched
+/// case-insensitively), e.g. Thrift's `Autogenerated by Thrift Compiler`
+/// or C#'s `<auto-generated>`
+const GENERATED_HEADERS: &[&str] = &[
+    "this file was automatically generated",
+    "this file is automatically generated",
+    "this file was generated by",
+    "this file is generated by",
+    "automatically generated by",
+    "autogenerated by",
+    "auto-generated by",
+    "<auto-generated",
+];
+
+/// Average line length past which JavaScript or CSS is taken to be minified
+const MINIFIED_LINE_LEN: usize = 200;
+
+/// Whether a file's contents show it was generated: a generator's header
+/// comment, or minified JavaScript or CSS
+pub fn is_generated_content(language: Option<&str>, text: &str) -> bool {
+    has_generated_header(text) || is_minified(language, text.len(), text.lines().count())
+}
+
+/// Whether the first lines of a file hold a generator's header comment
+pub fn has_generated_header(text: &str) -> bool {
+    text.lines().take(GENERATED_HEADER_LINES).any(is_generated_header)
+}
+
+/// Whether JavaScript or CSS of this size has lines too long to be written by hand
+pub fn is_minified(language: Option<&str>, len: usize, lines: usize) -> bool {
+    matches!(language, Some("javascript" | "css")) && len / lines.max(1) > MINIFIED_LINE_LEN
+}
 
-    if pattern.ends_with('*') {
-        // prefix*
-        let prefix = &pattern[..pattern.len() - 1];
-        return text.starts_with(prefix);
+/// Whether a line is a comment in one of the conventional generated-file
+/// forms: Go's `// Code generated ... DO NOT EDIT.` (and protoc's
+/// `Generated by ... DO NOT EDIT!`), a comment opening with `DO NOT EDIT`,
+/// an `@generated` tag, or a known generator header. Prose that merely
+/// mentions generated code or editing doesn't count.
+fn is_generated_header(line: &str) -> bool {
+    let line = line.trim();
+    let Some(body) = COMMENT_PREFIXES.iter().find_map(|prefix| line.strip_prefix(prefix)) else {
+        return false;
+    };
+    let body = body.trim_start_matches(['/', '*', '!', '#', ' ', '\t']);
+    let lower = body.to_lowercase();
+
+    if body.starts_with("DO NOT EDIT") || has_generated_tag(body) {
+        return true;
+    }
+    if (lower.starts_with("code generated ") || lower.starts_with("generated by ")) && body.contains("DO NOT EDIT") {
+        return true;
     }
+    GENERATED_HEADERS.iter().any(|header| lower.starts_with(header))
+}
 
-    false
+/// Whether `@generated` appears as a tag of its own, not inside a word or address
+fn has_generated_tag(text: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '@';
+    text.match_indices("@generated").any(|(i, tag)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + tag.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn globs(patterns: &[&str]) -> GlobList {
+        GlobList::new(patterns.iter().copied()).unwrap()
+    }
+
+    fn matches_glob(name: &str, pattern: &str) -> bool {
+        globs(&[pattern]).is_match(name, name)
+    }
+
     #[test]
     fn test_matches_glob() {
         assert!(matches_glob("test_foo.py", "test_*.py"));
@@ -80,14 +258,96 @@ mod tests {
         assert!(matches_glob("foo.spec.ts", "*.spec.ts"));
         assert!(!matches_glob("main.rs", "main.py"));
         assert!(!matches_glob("test_foo.py", "*_test.py"));
+
+        assert!(matches_glob("a1.py", "a?.py"));
+        assert!(matches_glob("b.py", "[abc].py"));
+        assert!(!matches_glob("d.py", "[abc].py"));
+        assert!(matches_glob("app.tsx", "*.{ts,tsx}"));
+    }
+
+    #[test]
+    fn test_path_patterns() {
+        let list = globs(&["src/**/migrations", "/build", "*.pyc"]);
+        assert_eq!(list.matches("migrations", "src/app/db/migrations"), [0]);
+        assert!(list.matches("migrations", "lib/migrations").is_empty());
+        // `*` stays within a directory, `**` spans them
+        assert!(!globs(&["src/*/x"]).is_match("x", "src/a/b/x"));
+        assert!(list.is_match("build", "build"));
+        assert!(!list.is_match("build", "web/build"));
+        assert_eq!(list.matches("x.pyc", "deep/x.pyc"), [2]);
+
+        assert!(GlobList::new(["src/[a"]).is_err());
     }
 
     #[test]
     fn test_should_ignore() {
-        let patterns = vec![".DS_Store", "*.pyc", "*~"];
-        assert!(should_ignore(".DS_Store", &patterns));
-        assert!(should_ignore("foo.pyc", &patterns));
-        assert!(should_ignore("backup~", &patterns));
-        assert!(!should_ignore("main.py", &patterns));
+        let patterns = PatternSet {
+            ignore: vec![".DS_Store".to_string(), "*.pyc".to_string(), "*~".to_string()],
+            ..PatternSet::default()
+        };
+        let matcher = PatternMatcher::new(&patterns).unwrap();
+        assert!(matcher.should_ignore(".DS_Store", ".DS_Store"));
+        assert!(matcher.should_ignore("foo.pyc", "pkg/foo.pyc"));
+        assert!(matcher.should_ignore("backup~", "backup~"));
+        assert!(!matcher.should_ignore("main.py", "main.py"));
+    }
+
+    #[test]
+    fn test_is_entry_point() {
+        let python = "def main():\n    pass\n\nif __name__ == '__main__':\n    main()\n";
+        assert!(is_entry_point(Path::new("tool/cli.py"), Some("python"), python));
+        assert!(!is_entry_point(Path::new("tool/util.py"), Some("python"), "def f():\n    pass\n"));
+
+        let go = "package main\n\nfunc main() {\n}\n";
+        assert!(is_entry_point(Path::new("cmd/server/server.go"), Some("go"), go));
+        assert!(!is_entry_point(Path::new("pkg/util.go"), Some("go"), "package util\n\nfunc main() {}\n"));
+
+        let rust = "#![allow(dead_code)]\nfn main() {}\n";
+        assert!(is_entry_point(Path::new("src/bin/migrate.rs"), Some("rust"), rust));
+        assert!(!is_entry_point(Path::new("examples/demo.rs"), Some("rust"), rust));
+    }
+
+    #[test]
+    fn test_generated_and_vendored() {
+        let matcher = PatternMatcher::new(&PatternSet::default()).unwrap();
+        assert!(matcher.is_generated("api.pb.go", "api/api.pb.go"));
+        assert!(matcher.is_generated("user_pb2.py", "proto/user_pb2.py"));
+        assert!(matcher.is_generated("model.g.dart", "lib/model.g.dart"));
+        assert!(!matcher.is_generated("main.go", "main.go"));
+
+        assert!(matcher.is_vendored("third_party/zlib/zlib.h"));
+        assert!(matcher.is_vendored("web/static/vendor/jquery.js"));
+        assert!(!matcher.is_vendored("src/vendor.rs"));
+
+        let go = "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api\n";
+        assert!(is_generated_content(Some("go"), go));
+        assert!(is_generated_content(Some("javascript"), &format!("var a={};", "x".repeat(500))));
+        assert!(!is_generated_content(Some("rust"), "fn main() {}\n"));
+    }
+
+    #[test]
+    fn test_generated_headers() {
+        for header in [
+            "# Generated by the protocol buffer compiler.  DO NOT EDIT!",
+            "# This file is automatically @generated by Cargo.",
+            " * @generated SignedSource<<abc>>",
+            "// <auto-generated>",
+            "/* automatically generated by rust-bindgen 0.69.4 */",
+            "# DO NOT EDIT THIS FILE",
+        ] {
+            assert!(is_generated_content(None, header), "{header}");
+        }
+
+        // Hand-written files that mention generated code or editing
+        for prose in [
+            "\"\"\"User records. IDs are auto-generated by the database.\"\"\"",
+            "# do not edit without updating the schema in db/",
+            "// Keys are automatically generated when missing",
+            "// Mail me at build@generated.example",
+            "/// See the @generatedCode attribute",
+            "let note = \"// Code generated by hand. DO NOT EDIT.\";",
+        ] {
+            assert!(!is_generated_content(Some("rust"), prose), "{prose}");
+        }
     }
 }
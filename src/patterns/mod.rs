@@ -1,5 +1,9 @@
 mod defaults;
+mod language;
 mod matcher;
+mod thresholds;
 
 pub use defaults::*;
+pub use language::*;
 pub use matcher::*;
+pub use thresholds::*;
@@ -0,0 +1,115 @@
+use anyhow::Result;
+
+/// `patterns_matched` name of directories collapsed by a threshold
+pub const AUTO_COLLAPSE_PATTERN: &str = "auto-collapse";
+
+/// Languages of data and documentation rather than code
+const NON_CODE_LANGUAGES: &[&str] = &["json", "yaml", "toml", "xml", "text", "markdown", "rst"];
+
+/// Limits past which a directory is collapsed whatever its name. Only the
+/// files the scan indexes count: nothing inside collapsed directories.
+#[derive(Debug, Clone, Default)]
+pub struct CollapseThresholds {
+    /// Collapse directories holding more files than this
+    pub max_files: Option<usize>,
+    /// Collapse directories whose files add up to more bytes than this
+    pub max_bytes: Option<u64>,
+    /// Collapse directories where at least this percentage of files are not code...
+    pub non_code_percent: Option<u8>,
+    /// ...once they hold at least this many files
+    pub min_files: usize,
+}
+
+impl CollapseThresholds {
+    pub fn is_enabled(&self) -> bool {
+        self.max_files.is_some() || self.max_bytes.is_some() || self.non_code_percent.is_some()
+    }
+
+    /// The reason to collapse a directory with these totals, naming the
+    /// threshold that was hit, or `None` if it is within every limit
+    pub fn exceeded(&self, files: usize, bytes: u64, code_files: usize) -> Option<String> {
+        if let Some(max) = self.max_files.filter(|max| files > *max) {
+            return Some(format!("{} files, over the {} file limit", files, max));
+        }
+        if let Some(max) = self.max_bytes.filter(|max| bytes > *max) {
+            return Some(format!("{}, over the {} size limit", format_bytes(bytes), format_bytes(max)));
+        }
+        if let Some(percent) = self.non_code_percent {
+            let non_code = files - code_files.min(files);
+            if files > 0 && files >= self.min_files && non_code * 100 >= files * percent as usize {
+                return Some(format!(
+                    "{}% non-code files, over the {}% limit",
+                    non_code * 100 / files,
+                    percent
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// Whether files of a language count as code for `non_code_percent`
+pub fn is_code_language(language: Option<&str>) -> bool {
+    language.is_some_and(|l| !NON_CODE_LANGUAGES.contains(&l))
+}
+
+/// Parse a size such as `500000`, `200KB`, `1.5 MB` or `2G` (units are powers of 1024)
+pub fn parse_size(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size '{}'", text))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => anyhow::bail!("Invalid size unit in '{}' (expected B, KB, MB, GB or TB)", text),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Format a byte count with the largest unit that keeps it at least 1
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds() {
+        let thresholds = CollapseThresholds {
+            max_files: Some(100),
+            max_bytes: Some(parse_size("1MB").unwrap()),
+            non_code_percent: Some(90),
+            min_files: 20,
+        };
+
+        assert_eq!(thresholds.exceeded(50, 1000, 40), None);
+        assert_eq!(thresholds.exceeded(150, 0, 150).unwrap(), "150 files, over the 100 file limit");
+        assert_eq!(thresholds.exceeded(10, 3 << 20, 10).unwrap(), "3.0 MB, over the 1.0 MB size limit");
+        assert_eq!(thresholds.exceeded(40, 1000, 2).unwrap(), "95% non-code files, over the 90% limit");
+        // Too few files for the non-code rule
+        assert_eq!(thresholds.exceeded(10, 1000, 0), None);
+
+        assert_eq!(parse_size("1.5 KB").unwrap(), 1536);
+        assert_eq!(parse_size("2g").unwrap(), 2 << 30);
+        assert!(parse_size("10 parsecs").is_err());
+    }
+}
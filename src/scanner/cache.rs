@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::write_atomic;
+use crate::deps::RawImport;
+
+/// Bump when the cache layout, hashing or the results derived from file
+/// contents change, to force a full rescan
+const CACHE_VERSION: u32 = 14;
+
+/// Modification stamp for a file or collapsed directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub mtime_secs: i64,
+    pub mtime_nanos: u32,
+    pub size: u64,
+
+    /// Content hash (only for files whose contents were read)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+
+    /// Imports parsed from the contents, resolved again on every scan
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<RawImport>,
+
+    /// Whether the contents mark the file as a program entry point
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub entry_point: bool,
+
+    /// Whether the contents mark the file as generated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub generated: bool,
+}
+
+impl FileStamp {
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        let (mtime_secs, mtime_nanos) = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| (d.as_secs() as i64, d.subsec_nanos()))
+            .unwrap_or((0, 0));
+
+        Self {
+            mtime_secs,
+            mtime_nanos,
+            size: metadata.len(),
+            hash: None,
+            imports: Vec::new(),
+            entry_point: false,
+            generated: false,
+        }
+    }
+
+    /// Whether mtime and size match (ignoring the content hash)
+    pub fn same_metadata(&self, other: &FileStamp) -> bool {
+        self.mtime_secs == other.mtime_secs
+            && self.mtime_nanos == other.mtime_nanos
+            && self.size == other.size
+    }
+}
+
+/// Stamps from the last scan, stored in `.sysmap/cache/checksums`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecksumCache {
+    pub version: u32,
+
+    /// Indexed files, keyed by path relative to the project root
+    pub files: BTreeMap<PathBuf, FileStamp>,
+
+    /// Collapsed directories and every directory beneath them, keyed by path
+    /// relative to the project root
+    pub dirs: BTreeMap<PathBuf, FileStamp>,
+
+    /// Directories the map descends into, stamped as their entries were
+    /// read, with the number of entries as the size
+    #[serde(default)]
+    pub scanned_dirs: BTreeMap<PathBuf, FileStamp>,
+
+    /// Totals of directories collapsed by a threshold, to judge them again
+    /// when the thresholds change
+    #[serde(default)]
+    pub auto_collapsed: BTreeMap<PathBuf, CollapsedTotals>,
+
+    /// Entries skipped by ignore rules, counted per directory so a partial
+    /// rescan can update the total
+    #[serde(default)]
+    pub ignored: BTreeMap<PathBuf, usize>,
+}
+
+/// What a directory collapsed by a threshold held when it was last scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollapsedTotals {
+    pub indexed_files: usize,
+    pub bytes: u64,
+    pub code_files: usize,
+}
+
+impl Default for ChecksumCache {
+    fn default() -> Self {
+        Self {
+            version: CACHE_VERSION,
+            files: BTreeMap::new(),
+            dirs: BTreeMap::new(),
+            scanned_dirs: BTreeMap::new(),
+            auto_collapsed: BTreeMap::new(),
+            ignored: BTreeMap::new(),
+        }
+    }
+}
+
+impl ChecksumCache {
+    /// Load the cache, returning `None` if it is missing, unreadable or outdated
+    pub fn load(path: &Path) -> Option<Self> {
+        let json = std::fs::read_to_string(path).ok()?;
+        let cache: Self = serde_json::from_str(&json).ok()?;
+        (cache.version == CACHE_VERSION).then_some(cache)
+    }
+
+    /// Save the cache, creating the cache directory if needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+        let json = serde_json::to_string(self)?;
+        write_atomic(path, json.as_bytes())
+    }
+
+    /// Stamps of a collapsed directory and every directory beneath it
+    pub fn dirs_under<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = (&'a PathBuf, &'a FileStamp)> {
+        // Paths order by component, so a directory's descendants follow it
+        self.dirs
+            .range(dir.to_path_buf()..)
+            .take_while(move |(path, _)| path.starts_with(dir))
+    }
+
+    /// Entries skipped by ignore rules across the whole scan
+    pub fn ignored_entries(&self) -> usize {
+        self.ignored.values().sum()
+    }
+
+    /// Parsed imports of every indexed file that has any
+    pub fn imports(&self) -> impl Iterator<Item = (&Path, &[RawImport])> {
+        self.files
+            .iter()
+            .filter(|(_, stamp)| !stamp.imports.is_empty())
+            .map(|(path, stamp)| (path.as_path(), stamp.imports.as_slice()))
+    }
+
+    /// Remove and return the stamps at or below a directory
+    pub fn take_under(&mut self, dir: &Path) -> ChecksumCache {
+        let mut taken = ChecksumCache::default();
+        let stamps = [
+            (&mut self.files, &mut taken.files),
+            (&mut self.dirs, &mut taken.dirs),
+            (&mut self.scanned_dirs, &mut taken.scanned_dirs),
+        ];
+        for (source, target) in stamps {
+            source.retain(|path, stamp| {
+                if path.starts_with(dir) {
+                    target.insert(path.clone(), stamp.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.auto_collapsed.retain(|path, totals| {
+            if path.starts_with(dir) {
+                taken.auto_collapsed.insert(path.clone(), *totals);
+                false
+            } else {
+                true
+            }
+        });
+        self.ignored.retain(|path, count| {
+            if path.starts_with(dir) {
+                taken.ignored.insert(path.clone(), *count);
+                false
+            } else {
+                true
+            }
+        });
+        taken
+    }
+
+    /// Add the stamps from another (partial) scan
+    pub fn merge(&mut self, other: ChecksumCache) {
+        self.files.extend(other.files);
+        self.dirs.extend(other.dirs);
+        self.scanned_dirs.extend(other.scanned_dirs);
+        self.auto_collapsed.extend(other.auto_collapsed);
+        self.ignored.extend(other.ignored);
+    }
+}
+
+/// Hash file contents for change detection
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:016x}", xxhash_rust::xxh3::xxh3_64(bytes))
+}
+
+/// Hash file contents read in chunks, matching `hash_bytes` on the whole
+pub struct ContentHasher(xxhash_rust::xxh3::Xxh3);
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self(xxhash_rust::xxh3::Xxh3::new())
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    pub fn finish(&self) -> String {
+        format!("{:016x}", self.0.digest())
+    }
+}
+
+/// Files added, removed and modified between two scans
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSummary {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+}
+
+impl ChangeSummary {
+    /// Compare the file stamps of two scans
+    pub fn between(old: &ChecksumCache, new: &ChecksumCache) -> Self {
+        let mut summary = Self::default();
+
+        for (path, stamp) in &new.files {
+            match old.files.get(path) {
+                None => summary.added.push(path.clone()),
+                Some(previous) => {
+                    let changed = match (&previous.hash, &stamp.hash) {
+                        (Some(a), Some(b)) => a != b,
+                        _ => !previous.same_metadata(stamp),
+                    };
+                    if changed {
+                        summary.modified.push(path.clone());
+                    }
+                }
+            }
+        }
+
+        summary.removed = old
+            .files
+            .keys()
+            .filter(|path| !new.files.contains_key(*path))
+            .cloned()
+            .collect();
+
+        summary
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
@@ -0,0 +1,56 @@
+use crate::patterns::is_code_language;
+
+/// Keywords that open a branch or loop in at least one supported language
+const DECISION_KEYWORDS: &[&str] = &[
+    "if", "elif", "elsif", "unless", "for", "foreach", "while", "until", "case", "when", "catch", "except",
+    "rescue", "guard", "and", "or",
+];
+
+/// Operators that short-circuit, adding a path each
+const DECISION_OPERATORS: &[&str] = &["&&", "||"];
+
+/// Comment prefixes skipped when counting, whatever the language
+const COMMENT_PREFIXES: &[&str] = &["//", "#", "/*", "*", "--", ";", "%"];
+
+/// Estimate the cyclomatic complexity of a file: one plus the number of
+/// decision points (branch and loop keywords, `&&` and `||`).
+///
+/// This reads tokens rather than parsing, so keywords inside strings are
+/// counted too; it's meant for ranking files, not as an exact measure.
+/// Returns `None` for languages that aren't code.
+pub fn estimate_complexity(text: &str, language: Option<&str>) -> Option<usize> {
+    if !is_code_language(language) {
+        return None;
+    }
+
+    let mut decisions = 0;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || COMMENT_PREFIXES.iter().any(|p| trimmed.starts_with(p)) {
+            continue;
+        }
+        decisions += trimmed
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|word| DECISION_KEYWORDS.contains(word))
+            .count();
+        decisions += DECISION_OPERATORS.iter().map(|op| trimmed.matches(op).count()).sum::<usize>();
+    }
+
+    Some(1 + decisions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_complexity() {
+        let rust = "// if this were counted\nfn check(a: bool, b: bool) {\n    if a && b {\n        for _ in 0..3 {}\n    } else if b {\n    }\n}\n";
+        assert_eq!(estimate_complexity(rust, Some("rust")), Some(5));
+
+        let python = "def f(xs):\n    # for each item\n    return [x for x in xs if x or not x]\n";
+        assert_eq!(estimate_complexity(python, Some("python")), Some(4));
+
+        assert_eq!(estimate_complexity("if: true\n", Some("yaml")), None);
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::deps::path_key;
+use crate::map::{FileNode, GitInfo, GitMeta};
+
+/// Start of each commit header in the log output (`%x1e`)
+const RECORD_SEPARATOR: char = '\x1e';
+/// Separator between the fields of a commit header (`%x1f`)
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// Commit history of the files tracked at HEAD, keyed by path relative to the root
+pub struct GitHistory {
+    pub meta: GitMeta,
+    pub files: HashMap<String, GitInfo>,
+    /// Distinct commits within the window touching tracked files beneath each
+    /// directory (the root is `""`)
+    pub dirs: HashMap<String, usize>,
+}
+
+/// Read the history of the git repository containing `root`.
+///
+/// The log is read newest first and stops once every tracked file has its
+/// last commit and the window has been passed. Returns `None` when the root
+/// isn't inside a repository, the repository has no commits, or git isn't
+/// installed.
+pub fn read_git_history(root: &Path, window_days: u32) -> Option<GitHistory> {
+    let head = current_head(root)?;
+    let listing = git_output(root, &["ls-tree", "-r", "-z", "--name-only", "HEAD"])?;
+    let tracked: HashSet<&str> = listing.split('\0').filter(|p| !p.is_empty()).collect();
+
+    let mut child = git(root)
+        .args([
+            "log",
+            "--format=%x1e%H%x1f%ct%x1f%an",
+            "--name-only",
+            "--no-renames",
+            "--relative",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let cutoff = (Utc::now() - Duration::days(window_days as i64)).timestamp();
+    let (files, dirs) = parse_log(BufReader::new(child.stdout.take()?), cutoff, &tracked);
+
+    // The log is usually abandoned before git has written all of it
+    let _ = child.kill();
+    let _ = child.wait();
+
+    Some(GitHistory {
+        meta: GitMeta {
+            head,
+            window_days,
+        },
+        files,
+        dirs,
+    })
+}
+
+/// Record the history on each file and directory of the tree
+pub fn apply_git_history(node: &mut FileNode, history: Option<&GitHistory>) {
+    match node {
+        FileNode::File { path, git, .. } => {
+            *git = history.and_then(|h| h.files.get(&path_key(path))).cloned();
+        }
+        FileNode::Directory { path, children, churn, .. } => {
+            for child in children.iter_mut() {
+                apply_git_history(child, history);
+            }
+            *churn = history.map(|h| h.dirs.get(&path_key(path)).copied().unwrap_or(0));
+        }
+        FileNode::Collapsed { .. } => {}
+    }
+}
+
+/// Give directories without recorded churn, those new since the history was
+/// read, the churn of their busiest child: the fewest distinct commits that
+/// could account for it
+pub fn fill_churn(node: &mut FileNode) -> usize {
+    match node {
+        FileNode::Directory { children, churn, .. } => {
+            let busiest = children.iter_mut().map(fill_churn).max().unwrap_or(0);
+            *churn.get_or_insert(busiest)
+        }
+        _ => node_churn(node),
+    }
+}
+
+/// Distinct commits within the window touching a node or anything beneath it
+pub fn node_churn(node: &FileNode) -> usize {
+    match node {
+        FileNode::File { git, .. } => git.as_ref().map_or(0, |g| g.commits),
+        FileNode::Directory { churn, .. } => churn.unwrap_or(0),
+        FileNode::Collapsed { .. } => 0,
+    }
+}
+
+/// Fold `git log --name-only` output into per-file history and per-directory
+/// commit counts, keeping only the tracked files.
+///
+/// Commits arrive newest first, so the first one naming a file is its last
+/// commit. Reading stops at the first commit older than `cutoff` once every
+/// tracked file has been seen.
+fn parse_log<R: BufRead>(
+    reader: R,
+    cutoff: i64,
+    tracked: &HashSet<&str>,
+) -> (HashMap<String, GitInfo>, HashMap<String, usize>) {
+    let mut files: HashMap<String, GitInfo> = HashMap::new();
+    let mut dirs: HashMap<String, usize> = HashMap::new();
+    let mut current: Option<(String, i64, String)> = None;
+    // Directories touched by the current commit, each counted once
+    let mut touched: HashSet<String> = HashSet::new();
+
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if let Some(header) = line.strip_prefix(RECORD_SEPARATOR) {
+            for dir in touched.drain() {
+                *dirs.entry(dir).or_default() += 1;
+            }
+
+            let mut fields = header.splitn(3, FIELD_SEPARATOR);
+            let (Some(hash), Some(time), Some(author)) = (fields.next(), fields.next(), fields.next()) else {
+                current = None;
+                continue;
+            };
+            let Ok(time) = time.parse::<i64>() else {
+                current = None;
+                continue;
+            };
+            if time < cutoff && files.len() >= tracked.len() {
+                break;
+            }
+            current = Some((hash.to_string(), time, author.to_string()));
+            continue;
+        }
+
+        let Some((hash, time, author)) = &current else {
+            continue;
+        };
+        if line.is_empty() || !tracked.contains(line.as_str()) {
+            continue;
+        }
+
+        let in_window = *time >= cutoff;
+        if in_window {
+            touched.insert(String::new());
+            touched.extend(line.match_indices('/').map(|(i, _)| line[..i].to_string()));
+        }
+        match files.get_mut(&line) {
+            Some(info) => info.commits += in_window as usize,
+            None => {
+                let Some(last_commit) = DateTime::from_timestamp(*time, 0) else {
+                    continue;
+                };
+                files.insert(
+                    line,
+                    GitInfo {
+                        last_commit,
+                        commit: hash.clone(),
+                        author: author.clone(),
+                        commits: in_window as usize,
+                    },
+                );
+            }
+        }
+    }
+    for dir in touched {
+        *dirs.entry(dir).or_default() += 1;
+    }
+
+    (files, dirs)
+}
+
+/// The commit checked out in the repository holding `root`
+pub fn current_head(root: &Path) -> Option<String> {
+    git_output(root, &["rev-parse", "HEAD"]).map(|head| head.trim().to_string())
+}
+
+/// A git command run in the root directory, with paths left unquoted
+fn git(root: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(root).args(["-c", "core.quotePath=false"]);
+    command
+}
+
+/// Stdout of a git command that succeeded
+fn git_output(root: &Path, args: &[&str]) -> Option<String> {
+    let output = git(root).args(args).stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log() {
+        let log = "\x1eccc\x1f300\x1fAda\n\nsrc/main.rs\nsrc/lib.rs\n\
+                   \x1ebbb\x1f200\x1fGrace\n\nsrc/main.rs\nold.rs\n\
+                   \x1eaaa\x1f100\x1fAda\n\nsrc/main.rs\nREADME.md\n\
+                   \x1e000\x1f50\x1fLinus\n\nREADME.md\n";
+        let tracked: HashSet<&str> = ["src/main.rs", "src/lib.rs", "README.md"].into_iter().collect();
+        let (files, dirs) = parse_log(log.as_bytes(), 150, &tracked);
+
+        assert_eq!(files.len(), 3);
+        let main = &files["src/main.rs"];
+        assert_eq!((main.commit.as_str(), main.author.as_str(), main.commits), ("ccc", "Ada", 2));
+        assert_eq!(main.last_commit.timestamp(), 300);
+        assert!(!files.contains_key("old.rs"));
+
+        // README's only commit is outside the window, and reading stops after it
+        let readme = &files["README.md"];
+        assert_eq!((readme.commit.as_str(), readme.commits), ("aaa", 0));
+
+        // Three file changes under src/ in two commits
+        assert_eq!((dirs["src"], dirs[""]), (2, 2));
+    }
+
+    #[test]
+    fn test_directory_churn_counts_distinct_commits() {
+        let log = "\x1eaaa\x1f100\x1fAda\n\ncrates/a/lib.rs\ncrates/a/main.rs\ncrates/b/lib.rs\ncrates/b/util.rs\n";
+        let tracked: HashSet<&str> =
+            ["crates/a/lib.rs", "crates/a/main.rs", "crates/b/lib.rs", "crates/b/util.rs"].into_iter().collect();
+        let (files, dirs) = parse_log(log.as_bytes(), 0, &tracked);
+
+        assert!(files.values().all(|f| f.commits == 1));
+        assert_eq!((dirs["crates"], dirs["crates/a"], dirs["crates/b"], dirs[""]), (1, 1, 1, 1));
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// Per-directory ignore files, in increasing order of precedence
+const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+/// Stack of ignore matchers from the project root down to the current directory.
+///
+/// Matchers are ordered from lowest to highest precedence: the global git
+/// excludes file, `.git/info/exclude`, then `.gitignore`/`.ignore` files from
+/// the outermost directory inward.
+#[derive(Clone, Default)]
+pub struct IgnoreRules {
+    enabled: bool,
+    matchers: Vec<Arc<Gitignore>>,
+}
+
+impl IgnoreRules {
+    /// Rules that never ignore anything
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Build the rules that apply to the scan root, including global excludes,
+    /// the repository's `.git/info/exclude`, and ignore files in ancestor
+    /// directories up to the repository root.
+    pub fn for_root(root: &Path) -> Self {
+        let mut rules = Self {
+            enabled: true,
+            matchers: Vec::new(),
+        };
+
+        let (global, _) = GitignoreBuilder::new(root).build_global();
+        rules.push(global);
+
+        let git_root = find_git_root(root);
+        if let Some(git_root) = &git_root {
+            let mut builder = GitignoreBuilder::new(git_root);
+            builder.add(git_root.join(".git").join("info").join("exclude"));
+            if let Ok(exclude) = builder.build() {
+                rules.push(exclude);
+            }
+        }
+
+        // Ignore files between the repository root and the scan root still apply
+        let mut ancestors: Vec<PathBuf> = Vec::new();
+        if let Some(git_root) = &git_root {
+            let mut current = root.parent();
+            while let Some(dir) = current {
+                if !dir.starts_with(git_root) {
+                    break;
+                }
+                ancestors.push(dir.to_path_buf());
+                current = dir.parent();
+            }
+        }
+        for dir in ancestors.iter().rev() {
+            rules = rules.descend(dir);
+        }
+
+        rules.descend(root)
+    }
+
+    /// Rules for a child directory, adding its own ignore files on top
+    pub fn descend(&self, dir: &Path) -> Self {
+        if !self.enabled {
+            return self.clone();
+        }
+
+        let mut rules = self.clone();
+        for file in IGNORE_FILES {
+            let path = dir.join(file);
+            if path.is_file() {
+                let (matcher, _) = Gitignore::new(&path);
+                rules.push(matcher);
+            }
+        }
+        rules
+    }
+
+    /// Whether the given path is excluded by the rules
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for matcher in self.matchers.iter().rev() {
+            match matcher.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+
+    fn push(&mut self, matcher: Gitignore) {
+        if !matcher.is_empty() {
+            self.matchers.push(Arc::new(matcher));
+        }
+    }
+}
+
+/// Find the nearest ancestor (inclusive) that contains a `.git` entry
+fn find_git_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_nested_gitignore() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n*.log\n").unwrap();
+        fs::write(root.join(".git/info/exclude"), "scratch.txt\n").unwrap();
+        fs::write(root.join("sub/.gitignore"), "!keep.log\n").unwrap();
+
+        let rules = IgnoreRules::for_root(&root);
+        assert!(rules.is_ignored(&root.join("generated"), true));
+        assert!(rules.is_ignored(&root.join("debug.log"), false));
+        assert!(rules.is_ignored(&root.join("scratch.txt"), false));
+        assert!(!rules.is_ignored(&root.join("main.rs"), false));
+
+        let sub = rules.descend(&root.join("sub"));
+        assert!(sub.is_ignored(&root.join("sub/other.log"), false));
+        assert!(!sub.is_ignored(&root.join("sub/keep.log"), false));
+    }
+}
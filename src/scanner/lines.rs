@@ -0,0 +1,239 @@
+use crate::map::LineCounts;
+
+/// Comment and string syntax of a language
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    /// String delimiters, skipped when looking for comment markers
+    quotes: &'static [char],
+    /// Python-style docstrings: a statement that is only a triple-quoted string
+    docstrings: bool,
+}
+
+const C_STYLE: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+    quotes: &['"', '\'', '`'],
+    docstrings: false,
+};
+
+const HASH: CommentSyntax = CommentSyntax {
+    line: &["#"],
+    block: &[],
+    quotes: &['"', '\''],
+    docstrings: false,
+};
+
+const MARKUP: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[("<!--", "-->")],
+    quotes: &[],
+    docstrings: false,
+};
+
+const NONE: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: &[],
+    quotes: &[],
+    docstrings: false,
+};
+
+fn syntax_for(language: &str) -> &'static CommentSyntax {
+    match language {
+        "javascript" | "typescript" | "go" | "java" | "kotlin" | "c" | "cpp" | "csharp" | "swift"
+        | "scala" | "scss" | "less" | "groovy" => &C_STYLE,
+        // Lifetimes and char literals make `'` unreliable as a string delimiter
+        "rust" => &CommentSyntax { quotes: &['"'], ..C_STYLE },
+        "php" => &CommentSyntax { line: &["//", "#"], ..C_STYLE },
+        "css" => &CommentSyntax { line: &[], ..C_STYLE },
+        "python" => &CommentSyntax { docstrings: true, ..HASH },
+        "ruby" => &CommentSyntax { block: &[("=begin", "=end")], ..HASH },
+        "shell" | "r" | "yaml" | "toml" | "elixir" | "perl" | "make" | "dockerfile" | "cmake" => &HASH,
+        "starlark" => &CommentSyntax { docstrings: true, ..HASH },
+        "powershell" => &CommentSyntax { block: &[("<#", "#>")], ..HASH },
+        "julia" => &CommentSyntax { block: &[("#=", "=#")], ..HASH },
+        "sql" => &CommentSyntax { line: &["--"], block: &[("/*", "*/")], quotes: &['\''], docstrings: false },
+        "lua" => &CommentSyntax { line: &["--"], block: &[("--[[", "]]")], quotes: &['"', '\''], docstrings: false },
+        "haskell" => &CommentSyntax { line: &["--"], block: &[("{-", "-}")], quotes: &['"'], docstrings: false },
+        "clojure" => &CommentSyntax { line: &[";"], block: &[], quotes: &['"'], docstrings: false },
+        "erlang" => &CommentSyntax { line: &["%"], block: &[], quotes: &['"'], docstrings: false },
+        "html" | "xml" | "markdown" => &MARKUP,
+        _ => &NONE,
+    }
+}
+
+/// What the scanner is inside of at the start of a line
+enum State {
+    Normal,
+    /// A block comment (or docstring), closed by the delimiter
+    Comment(&'static str),
+    /// A multi-line string, closed by the delimiter; its lines count as code
+    String(&'static str),
+}
+
+/// Count code, comment and blank lines, using the comment syntax of the
+/// language (unknown languages count every non-blank line as code)
+pub fn count_lines(text: &str, language: Option<&str>) -> LineCounts {
+    let syntax = language.map_or(&NONE, syntax_for);
+    let mut counts = LineCounts::default();
+    let mut state = State::Normal;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        let (is_code, is_comment) = match state {
+            State::Comment(end) => match trimmed.find(end) {
+                Some(i) => {
+                    let (code, _, next) = scan(&trimmed[i + end.len()..], syntax);
+                    state = next;
+                    (code, true)
+                }
+                None => (false, !trimmed.is_empty()),
+            },
+            State::String(end) => match trimmed.find(end) {
+                Some(i) => {
+                    let (code, comment, next) = scan(&trimmed[i + end.len()..], syntax);
+                    state = next;
+                    (true, comment && !code)
+                }
+                None => (!trimmed.is_empty(), false),
+            },
+            State::Normal => match docstring_start(trimmed, syntax) {
+                Some(delim) => {
+                    let after = &trimmed[trimmed.find(delim).unwrap_or(0) + delim.len()..];
+                    if !after.contains(delim) {
+                        state = State::Comment(delim);
+                    }
+                    (false, true)
+                }
+                None => {
+                    let (code, comment, next) = scan(trimmed, syntax);
+                    state = next;
+                    (code, comment)
+                }
+            },
+        };
+
+        if is_code {
+            counts.code += 1;
+        } else if is_comment {
+            counts.comment += 1;
+        } else {
+            counts.blank += 1;
+        }
+    }
+
+    counts
+}
+
+/// The delimiter of a docstring opening this line, if it is one
+fn docstring_start(trimmed: &str, syntax: &CommentSyntax) -> Option<&'static str> {
+    if !syntax.docstrings {
+        return None;
+    }
+    let unprefixed = trimmed.trim_start_matches(['r', 'R', 'u', 'U', 'b', 'B']);
+    ["\"\"\"", "'''"].into_iter().find(|delim| unprefixed.starts_with(delim))
+}
+
+/// Scan part of a line, returning whether it has code, whether it has a
+/// comment, and the state the next line starts in
+fn scan(text: &str, syntax: &CommentSyntax) -> (bool, bool, State) {
+    let mut has_code = false;
+    let mut in_quote: Option<char> = None;
+    let mut chars = text.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if let Some(quote) = in_quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == quote {
+                in_quote = None;
+            }
+            continue;
+        }
+
+        let rest = &text[i..];
+        if syntax.line.iter().any(|marker| rest.starts_with(marker)) {
+            return (has_code, true, State::Normal);
+        }
+        if let Some((start, end)) = syntax.block.iter().find(|(start, _)| rest.starts_with(start)) {
+            match rest[start.len()..].find(end) {
+                Some(j) => {
+                    let resume = i + start.len() + j + end.len();
+                    let (code, _, state) = scan(&text[resume..], syntax);
+                    return (has_code || code, true, state);
+                }
+                None => return (has_code, true, State::Comment(end)),
+            }
+        }
+        if syntax.docstrings {
+            if let Some(delim) = ["\"\"\"", "'''"].into_iter().find(|d| rest.starts_with(d)) {
+                // A triple-quoted string inside code; it may span lines
+                return match rest[3..].find(delim) {
+                    Some(j) => {
+                        let (_, comment, state) = scan(&rest[3 + j + 3..], syntax);
+                        (true, comment, state)
+                    }
+                    None => (true, false, State::String(delim)),
+                };
+            }
+        }
+
+        if syntax.quotes.contains(&c) {
+            in_quote = Some(c);
+            has_code = true;
+        } else if !c.is_whitespace() {
+            has_code = true;
+        }
+    }
+
+    (has_code, false, State::Normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(code: usize, comment: usize, blank: usize) -> LineCounts {
+        LineCounts { code, comment, blank }
+    }
+
+    #[test]
+    fn test_count_c_style() {
+        let source = r#"/*
+ * License header
+ */
+
+//! Crate docs
+use std::path::Path; // trailing comment
+
+fn glob() -> &'static str {
+    "src/**/*.rs" /* inline */
+}
+let x = 1; /* starts
+   and ends */ let y = 2;
+"#;
+        assert_eq!(count_lines(source, Some("rust")), counts(6, 4, 2));
+    }
+
+    #[test]
+    fn test_count_python_docstrings() {
+        let source = r#"#!/usr/bin/env python
+"""Module docstring
+spanning lines.
+"""
+
+def f():
+    '''One-line docstring.'''
+    text = """not a
+docstring"""
+    return text  # comment
+"#;
+        assert_eq!(count_lines(source, Some("python")), counts(4, 5, 1));
+    }
+
+    #[test]
+    fn test_count_unknown_language() {
+        assert_eq!(count_lines("a\n\n# b\n", None), counts(2, 0, 1));
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::config::SYSMAP_DIR;
+use crate::deps::{build_dependencies, collect_entry_points, collect_packages, mark_entry_points, path_key};
+use crate::map::{FileNode, SystemMap};
+use crate::patterns::PatternMatcher;
+
+use super::{
+    count_tree, detect_project_type, detect_sub_projects, fill_churn, is_skipped_hidden, scan_subtree, ChangeSummary, ChecksumCache,
+    IgnoreRules, PreviousScan, ScannerConfig,
+};
+
+/// A map kept current by rescanning the directories that filesystem events touch
+pub struct LiveMap {
+    pub map: SystemMap,
+    pub cache: ChecksumCache,
+    root: PathBuf,
+    config: ScannerConfig,
+    patterns: PatternMatcher,
+    ignore_rules: IgnoreRules,
+}
+
+/// What one batch of events changed
+#[derive(Default)]
+pub struct PatchSummary {
+    pub changes: ChangeSummary,
+    /// Directories that were rescanned, relative to the root
+    pub rescanned: Vec<PathBuf>,
+    pub reused: usize,
+}
+
+impl LiveMap {
+    /// Start from a freshly scanned map and its cache
+    pub fn new(map: SystemMap, cache: ChecksumCache, config: ScannerConfig) -> Result<Self> {
+        let root = map.root.clone();
+        let ignore_rules = root_rules(&root, &config);
+        Ok(Self {
+            map,
+            cache,
+            root,
+            patterns: PatternMatcher::new(&config.patterns)?,
+            config,
+            ignore_rules,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Every directory the map descends into; events can only change the
+    /// map inside these, so they are the ones worth watching
+    pub fn mapped_dirs(&self) -> BTreeSet<PathBuf> {
+        let mut dirs = BTreeSet::new();
+        let mut stack = vec![&self.map.tree];
+        while let Some(node) = stack.pop() {
+            if let FileNode::Directory { path, children, .. } = node {
+                dirs.insert(self.root.join(path));
+                stack.extend(children);
+            }
+        }
+        dirs
+    }
+
+    /// Apply a batch of changed paths, returning `None` if none of them
+    /// affect the map (collapsed, ignored or hidden paths)
+    pub fn apply(&mut self, paths: &[PathBuf]) -> Result<Option<PatchSummary>> {
+        let start = Instant::now();
+
+        // Root ignore files are baked into the cached rules; nested ones are
+        // read again whenever their directory is rescanned
+        let root_ignore_changed = paths.iter().any(|p| {
+            p.parent() == Some(self.root.as_path())
+                && p.file_name().is_some_and(|n| n == ".gitignore" || n == ".ignore")
+        });
+        if root_ignore_changed {
+            self.ignore_rules = root_rules(&self.root, &self.config);
+        }
+
+        let targets = self.rescan_targets(paths);
+        if targets.is_empty() {
+            return Ok(None);
+        }
+
+        let mut summary = PatchSummary::default();
+        for target in &targets {
+            let scan = {
+                let previous = PreviousScan::new(&self.map, &self.cache);
+                scan_subtree(&self.root, target, &self.config, &previous)?
+            };
+
+            let old = self.cache.take_under(target);
+            let changes = ChangeSummary::between(&old, &scan.cache);
+            summary.changes.added.extend(changes.added);
+            summary.changes.removed.extend(changes.removed);
+            summary.changes.modified.extend(changes.modified);
+            summary.reused += scan.reused;
+            self.cache.merge(scan.cache);
+
+            self.map.patterns_matched.retain(|p| !p.path.starts_with(target));
+            self.map.patterns_matched.extend(scan.patterns_matched);
+            replace_node(&mut self.map.tree, target, scan.node);
+        }
+
+        // Keep the order a full scan would produce
+        self.map.patterns_matched.sort_by(|a, b| a.path.cmp(&b.path));
+        self.refresh(start);
+
+        summary.rescanned = targets;
+        Ok(Some(summary))
+    }
+
+    /// Recompute everything derived from the tree as a whole
+    fn refresh(&mut self, start: Instant) {
+        let (total_files, indexed_files, total_dirs) = count_tree(&self.map.tree);
+        self.map.meta.total_files = total_files;
+        self.map.meta.indexed_files = indexed_files;
+        self.map.meta.total_dirs = total_dirs;
+        self.map.meta.ignored_entries = self.cache.ignored_entries();
+        self.map.meta.scan_time_ms = start.elapsed().as_millis() as u64;
+        self.map.scanned_at = Utc::now();
+
+        // Rescanned files and directories keep the history read by the last full scan
+        if self.map.git.is_some() {
+            fill_churn(&mut self.map.tree);
+        }
+
+        let entries = collect_entry_points(&self.root, &self.map.tree);
+        mark_entry_points(&mut self.map.tree, &entries);
+
+        self.map.project_type = detect_project_type(&self.root, &self.config.patterns.project);
+        self.map.projects = detect_sub_projects(&self.root, &self.map.tree, &self.config.patterns.project);
+        self.map.dependencies = build_dependencies(&self.root, &self.map.tree, self.cache.imports());
+        self.map.dependencies.packages = collect_packages(&self.root, &self.map.tree);
+    }
+
+    /// The directories to rescan for a batch of changed paths: the nearest
+    /// mapped directory containing each relevant path, without nesting
+    fn rescan_targets(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let mut targets = BTreeSet::new();
+
+        for path in paths {
+            if !self.is_relevant(path) {
+                continue;
+            }
+            let Some(parent) = path.strip_prefix(&self.root).ok().and_then(Path::parent) else {
+                continue;
+            };
+
+            // Walk down the mapped directories towards the changed path
+            let mut target = PathBuf::new();
+            let mut node = &self.map.tree;
+            for component in parent.components() {
+                let child = node
+                    .children()
+                    .and_then(|children| children.iter().find(|c| c.name() == component.as_os_str()));
+                match child {
+                    Some(child @ FileNode::Directory { .. }) if self.root.join(&target).join(component).is_dir() => {
+                        target.push(component);
+                        node = child;
+                    }
+                    _ => break,
+                }
+            }
+            targets.insert(target);
+        }
+
+        // A rescan covers everything below it
+        let mut kept: Vec<PathBuf> = Vec::new();
+        for target in targets {
+            if !kept.iter().any(|k| target.starts_with(k)) {
+                kept.push(target);
+            }
+        }
+        kept
+    }
+
+    /// Whether a path could change the map, applying the same collapse,
+    /// ignore, hidden and gitignore rules as the scanner
+    fn is_relevant(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let components: Vec<_> = relative.iter().collect();
+        if components.is_empty() || components[0] == SYSMAP_DIR {
+            return false;
+        }
+
+        let mut node = Some(&self.map.tree);
+        let mut rules = self.ignore_rules.clone();
+        let mut current = self.root.clone();
+
+        for (i, component) in components.iter().enumerate() {
+            let name = component.to_string_lossy();
+            let is_last = i + 1 == components.len();
+            current.push(component);
+            let key = path_key(&components[..=i].iter().collect::<PathBuf>());
+
+            node = node
+                .and_then(|n| n.children())
+                .and_then(|children| children.iter().find(|c| c.name() == name));
+
+            if self.patterns.should_ignore(&name, &key) {
+                return false;
+            }
+
+            // Events inside collapsed directories (node_modules, target, ...)
+            // never change the map; the directory itself may be new
+            if matches!(node, Some(FileNode::Collapsed { .. })) {
+                return false;
+            }
+            if !is_last && self.patterns.should_collapse(&name, &key, &current).is_some() {
+                return false;
+            }
+
+            if is_skipped_hidden(&name) {
+                return false;
+            }
+
+            let is_dir = !is_last || current.is_dir();
+            // An ignored entry of a mapped directory still counts towards
+            // its ignored entries; nothing beneath it does
+            if rules.is_ignored(&current, is_dir) {
+                return is_last;
+            }
+            if !is_last {
+                rules = rules.descend(&current);
+            }
+        }
+
+        true
+    }
+}
+
+fn root_rules(root: &Path, config: &ScannerConfig) -> IgnoreRules {
+    if config.respect_gitignore {
+        IgnoreRules::for_root(root)
+    } else {
+        IgnoreRules::disabled()
+    }
+}
+
+/// Replace the node at a relative directory path
+fn replace_node(tree: &mut FileNode, target: &Path, replacement: FileNode) {
+    let mut node = tree;
+    for component in target.components() {
+        let child = node
+            .children_mut()
+            .and_then(|children| children.iter_mut().find(|c| c.name() == component.as_os_str()));
+        match child {
+            Some(child) => node = child,
+            None => return,
+        }
+    }
+    *node = replacement;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::scan_incremental;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_apply_rescans_touched_directory() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join("src/main.py"), "import util\n").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "\n").unwrap();
+
+        let config = ScannerConfig {
+            show_progress: false,
+            ..ScannerConfig::default()
+        };
+        let output = scan_incremental(&root, &config, None).unwrap();
+        let mut live = LiveMap::new(output.map, output.cache, config).unwrap();
+
+        // Events inside collapsed directories are dropped
+        fs::write(root.join("node_modules/pkg/other.js"), "\n").unwrap();
+        assert!(live.apply(&[root.join("node_modules/pkg/other.js")]).unwrap().is_none());
+
+        fs::write(root.join("src/util.py"), "X = 1\n").unwrap();
+        let summary = live.apply(&[root.join("src/util.py")]).unwrap().unwrap();
+        assert_eq!(summary.rescanned, vec![PathBuf::from("src")]);
+        assert_eq!(summary.changes.added, vec![PathBuf::from("src/util.py")]);
+        assert_eq!(live.map.meta.indexed_files, 2);
+        assert_eq!(live.map.dependencies.internal["src/main.py"], vec!["src/util.py"]);
+        assert_eq!(live.map.patterns_matched.len(), 1);
+
+        // Only directories the map descends into are watched
+        assert_eq!(live.mapped_dirs(), BTreeSet::from([root.clone(), root.join("src")]));
+
+        // Ignored entries are counted again for the rescanned directory
+        fs::write(root.join("src/.gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("src/debug.log"), "\n").unwrap();
+        live.apply(&[root.join("src/debug.log")]).unwrap();
+        assert_eq!(live.map.meta.ignored_entries, 1);
+        fs::remove_file(root.join("src/debug.log")).unwrap();
+        live.apply(&[root.join("src/debug.log")]).unwrap();
+        assert_eq!(live.map.meta.ignored_entries, 0);
+    }
+}
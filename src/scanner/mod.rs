@@ -1,7 +1,25 @@
+mod cache;
+mod complexity;
+mod git;
+mod gitignore;
+mod lines;
+mod live;
 mod walker;
 mod project;
+mod sniff;
+mod staleness;
 mod stats;
+mod workspace;
 
+pub use cache::*;
+pub use complexity::*;
+pub use git::*;
+pub use gitignore::*;
+pub use lines::*;
+pub use live::*;
 pub use walker::*;
 pub use project::*;
+pub use sniff::*;
+pub use staleness::*;
 pub use stats::*;
+pub use workspace::*;
@@ -2,19 +2,18 @@ use std::fs;
 use std::path::Path;
 
 use crate::map::ProjectType;
-use crate::patterns::default_project_patterns;
+use crate::patterns::ProjectPattern;
 
 /// Detect the project type from marker files
-pub fn detect_project_type(root: &Path) -> ProjectType {
-    let patterns = default_project_patterns();
+pub fn detect_project_type(root: &Path, patterns: &[ProjectPattern]) -> ProjectType {
     let mut detected = ProjectType::default();
     let mut found_languages: Vec<String> = Vec::new();
 
-    for pattern in &patterns {
-        for marker in pattern.markers {
+    for pattern in patterns {
+        for marker in &pattern.markers {
             let marker_path = root.join(marker);
             if marker_path.exists() {
-                let lang = pattern.language.to_string();
+                let lang = pattern.language.clone();
                 
                 // Track all languages found
                 if !found_languages.contains(&lang) {
@@ -25,7 +24,7 @@ pub fn detect_project_type(root: &Path) -> ProjectType {
 
                 // Try to detect framework from the marker file contents
                 if detected.framework.is_none() {
-                    if let Some(framework) = detect_framework(&marker_path, pattern.frameworks) {
+                    if let Some(framework) = detect_framework(&marker_path, &pattern.frameworks) {
                         detected.framework = Some(framework);
                     }
                 }
@@ -54,7 +53,7 @@ pub fn detect_project_type(root: &Path) -> ProjectType {
 }
 
 /// Try to detect framework from file contents
-fn detect_framework(marker_path: &Path, frameworks: &[(&str, &str)]) -> Option<String> {
+fn detect_framework(marker_path: &Path, frameworks: &[(String, String)]) -> Option<String> {
     if frameworks.is_empty() {
         return None;
     }
@@ -65,7 +64,7 @@ fn detect_framework(marker_path: &Path, frameworks: &[(&str, &str)]) -> Option<S
     for (framework_name, package_hint) in frameworks {
         // Simple check: does the file contain the package name?
         if contents_lower.contains(&package_hint.to_lowercase()) {
-            return Some(framework_name.to_string());
+            return Some(framework_name.clone());
         }
     }
 
@@ -75,6 +74,7 @@ fn detect_framework(marker_path: &Path, frameworks: &[(&str, &str)]) -> Option<S
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::patterns::default_project_patterns;
     use tempfile::TempDir;
 
     #[test]
@@ -82,7 +82,7 @@ mod tests {
         let temp = TempDir::new().unwrap();
         fs::write(temp.path().join("pyproject.toml"), "[project]\nname = \"test\"").unwrap();
         
-        let detected = detect_project_type(temp.path());
+        let detected = detect_project_type(temp.path(), &default_project_patterns());
         assert_eq!(detected.languages.first(), Some(&"python".to_string()));
     }
 
@@ -91,7 +91,7 @@ mod tests {
         let temp = TempDir::new().unwrap();
         fs::write(temp.path().join("Cargo.toml"), "[package]\nname = \"test\"").unwrap();
         
-        let detected = detect_project_type(temp.path());
+        let detected = detect_project_type(temp.path(), &default_project_patterns());
         assert_eq!(detected.languages.first(), Some(&"rust".to_string()));
     }
 
@@ -103,7 +103,7 @@ mod tests {
             "[project]\ndependencies = [\"flask\"]"
         ).unwrap();
         
-        let detected = detect_project_type(temp.path());
+        let detected = detect_project_type(temp.path(), &default_project_patterns());
         assert_eq!(detected.languages.first(), Some(&"python".to_string()));
         assert_eq!(detected.framework, Some("flask".to_string()));
     }
@@ -0,0 +1,201 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::map::Encoding;
+
+/// Leading bytes inspected to tell text from binary
+pub const SNIFF_LEN: usize = 8192;
+
+/// Extensions of formats that are text; only consulted when the contents
+/// are ambiguous (not UTF-8, but without NUL bytes)
+const TEXT_EXTENSIONS: &[&str] = &[
+    "py", "rs", "js", "ts", "jsx", "tsx", "go", "java", "kt", "rb", "php",
+    "c", "cpp", "cc", "h", "hpp", "cs", "swift", "scala", "clj", "ex", "exs",
+    "erl", "hs", "lua", "r", "jl", "sql", "sh", "bash", "zsh", "fish", "ps1",
+    "yaml", "yml", "toml", "json", "xml", "html", "css", "scss", "sass", "less",
+    "md", "rst", "txt", "cfg", "ini", "conf", "env", "proto", "graphql", "gql",
+    "vue", "svelte", "csv", "tsv", "log", "tex", "bat", "cmd",
+];
+
+/// Extensions of binary formats whose leading bytes may look like 8-bit text
+const BINARY_EXTENSIONS: &[&str] = &[
+    "pdf", "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "zip", "gz", "tgz",
+    "bz2", "xz", "7z", "jar", "class", "exe", "dll", "so", "dylib", "o", "a",
+    "wasm", "pyc", "woff", "woff2", "ttf", "otf", "mp3", "mp4", "mov", "avi",
+    "sqlite", "db",
+];
+
+/// Classify a file from its leading bytes, returning its text encoding, or
+/// `None` for binary files
+pub fn sniff(head: &[u8], path: &Path) -> Option<Encoding> {
+    // Byte order marks come first: UTF-16 text is full of NUL bytes
+    if head.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Some(Encoding::Utf8Bom);
+    }
+    if head.starts_with(&[0xFF, 0xFE]) {
+        return Some(Encoding::Utf16Le);
+    }
+    if head.starts_with(&[0xFE, 0xFF]) {
+        return Some(Encoding::Utf16Be);
+    }
+
+    if head.contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => return Some(Encoding::Utf8),
+        // A character cut in half by the end of the sniffed bytes
+        Err(e) if e.error_len().is_none() => return Some(Encoding::Utf8),
+        Err(_) => {}
+    }
+
+    // Neither UTF-8 nor NUL bytes: a legacy 8-bit encoding, or binary
+    match extension_hint(path) {
+        Some(true) => Some(Encoding::Latin1),
+        Some(false) => None,
+        None => {
+            let control = head.iter().filter(|&&b| is_control(b)).count();
+            (control * 10 <= head.len()).then_some(Encoding::Latin1)
+        }
+    }
+}
+
+/// Decode file contents, replacing invalid sequences
+pub fn decode(bytes: &[u8], encoding: Encoding) -> Cow<'_, str> {
+    match encoding {
+        Encoding::Utf8 => String::from_utf8_lossy(bytes),
+        Encoding::Utf8Bom => String::from_utf8_lossy(bytes.get(3..).unwrap_or_default()),
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = bytes.get(2..).unwrap_or_default().chunks_exact(2).map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if encoding == Encoding::Utf16Le {
+                    u16::from_le_bytes(pair)
+                } else {
+                    u16::from_be_bytes(pair)
+                }
+            });
+            Cow::Owned(
+                char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect(),
+            )
+        }
+        Encoding::Latin1 => Cow::Owned(bytes.iter().map(|&b| b as char).collect()),
+    }
+}
+
+/// Counts the lines of text fed in chunks, for files too large to decode whole
+pub struct LineStream {
+    encoding: Encoding,
+    /// Byte order mark bytes still to skip
+    skip: usize,
+    /// First byte of a UTF-16 code unit split across chunks
+    half: Option<u8>,
+    newlines: usize,
+    /// Whether the last line has contents but no newline yet
+    open: bool,
+}
+
+impl LineStream {
+    pub fn new(encoding: Encoding) -> Self {
+        let skip = match encoding {
+            Encoding::Utf8Bom => 3,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+            Encoding::Utf8 | Encoding::Latin1 => 0,
+        };
+        Self { encoding, skip, half: None, newlines: 0, open: false }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let skipped = self.skip.min(bytes.len());
+        self.skip -= skipped;
+        for &byte in &bytes[skipped..] {
+            let newline = match self.encoding {
+                Encoding::Utf16Le | Encoding::Utf16Be => {
+                    let Some(first) = self.half.take() else {
+                        self.half = Some(byte);
+                        continue;
+                    };
+                    let unit = if self.encoding == Encoding::Utf16Le {
+                        u16::from_le_bytes([first, byte])
+                    } else {
+                        u16::from_be_bytes([first, byte])
+                    };
+                    unit == u16::from(b'\n')
+                }
+                _ => byte == b'\n',
+            };
+            if newline {
+                self.newlines += 1;
+            }
+            self.open = !newline;
+        }
+    }
+
+    /// Lines so far, counted like `str::lines`
+    pub fn lines(&self) -> usize {
+        self.newlines + self.open as usize
+    }
+}
+
+/// Whether the extension names a text (`Some(true)`) or binary (`Some(false)`) format
+fn extension_hint(path: &Path) -> Option<bool> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    if TEXT_EXTENSIONS.contains(&ext.as_str()) {
+        Some(true)
+    } else if BINARY_EXTENSIONS.contains(&ext.as_str()) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Control characters that do not occur in text (tab, newlines, form feed
+/// and escape do)
+fn is_control(byte: u8) -> bool {
+    (byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B)) || byte == 0x7F
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_by_contents() {
+        let path = Path::new("Makefile");
+        assert_eq!(sniff(b"all:\n\tcargo build\n", path), Some(Encoding::Utf8));
+        assert_eq!(sniff("caf\u{e9}\n".as_bytes(), path), Some(Encoding::Utf8));
+        assert_eq!(sniff(b"\xEF\xBB\xBFname\n", path), Some(Encoding::Utf8Bom));
+        assert_eq!(sniff(b"\xFF\xFEa\0b\0", path), Some(Encoding::Utf16Le));
+        assert_eq!(sniff(b"caf\xE9\n", path), Some(Encoding::Latin1));
+        assert_eq!(sniff(b"\x01\x02\x03\xFF\x04", path), None);
+
+        // Contents win over the extension
+        assert_eq!(sniff(b"{\"a\": \0}", Path::new("data.json")), None);
+        assert_eq!(sniff(b"\x89PNG\r\n\x1A\n\xFF", Path::new("logo.png")), None);
+        assert_eq!(sniff(b"caf\xE9\x01\x02", Path::new("notes.txt")), Some(Encoding::Latin1));
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(b"\xEF\xBB\xBFa\nb", Encoding::Utf8Bom), "a\nb");
+        assert_eq!(decode(b"\xFE\xFF\0h\0i", Encoding::Utf16Be), "hi");
+        assert_eq!(decode(b"caf\xE9", Encoding::Latin1), "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_line_stream_across_chunks() {
+        let mut lines = LineStream::new(Encoding::Utf8);
+        for chunk in [&b"a\nb"[..], b"\n\n", b"c"] {
+            lines.feed(chunk);
+        }
+        assert_eq!(lines.lines(), "a\nb\n\nc".lines().count());
+
+        // Code units and the byte order mark may be split between chunks
+        let mut lines = LineStream::new(Encoding::Utf16Le);
+        for chunk in [&b"\xFF"[..], b"\xFEa", b"\0\n", b"\0\n\0"] {
+            lines.feed(chunk);
+        }
+        assert_eq!(lines.lines(), 2);
+    }
+}
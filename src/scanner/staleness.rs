@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::map::{FileNode, SystemMap};
+
+use super::{current_head, ChecksumCache, FileStamp};
+
+/// Most directories whose mtime is checked, nearest the root first
+const MAX_DIRECTORIES: usize = 256;
+
+/// Files whose mtime is compared with the one recorded in the map
+const SAMPLED_FILES: usize = 64;
+
+/// Filesystem timestamps tick more coarsely than the clock the scan time
+/// comes from (up to two seconds on FAT), so a directory modified this close
+/// to the scan may change again without its mtime moving
+const RACY_WINDOW_SECS: i64 = 2;
+
+/// Why a saved map no longer matches the project it was scanned from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Staleness {
+    /// A different commit is checked out than when the map was built
+    HeadMoved { recorded: String, current: String },
+    /// Entries were added, removed or renamed in a directory since the scan
+    DirectoryChanged(PathBuf),
+    /// A file was modified since the scan
+    FileChanged(PathBuf),
+    /// A file or directory in the map no longer exists
+    Missing(PathBuf),
+}
+
+impl fmt::Display for Staleness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |path: &Path| {
+            if path.as_os_str().is_empty() { ".".to_string() } else { path.display().to_string() }
+        };
+        match self {
+            Staleness::HeadMoved { recorded, current } => write!(
+                f,
+                "HEAD moved from {} to {}",
+                &recorded[..recorded.len().min(7)],
+                &current[..current.len().min(7)]
+            ),
+            Staleness::DirectoryChanged(path) => write!(f, "{}/ changed since the last scan", show(path)),
+            Staleness::FileChanged(path) => write!(f, "{} changed since the last scan", show(path)),
+            Staleness::Missing(path) => write!(f, "{} no longer exists", show(path)),
+        }
+    }
+}
+
+/// Cheaply check whether a saved map still matches the project.
+///
+/// Directory stamps from the scan reveal added, removed and renamed
+/// entries; a sample of files spread over the tree catches edits in place;
+/// and when the map recorded a git HEAD, so does a different checkout. Only
+/// the first difference found is reported. Without the scan's cache, mtimes
+/// are compared with the scan time instead.
+pub fn check_staleness(root: &Path, map: &SystemMap, cache: Option<&ChecksumCache>) -> Option<Staleness> {
+    let mut directories = Vec::new();
+    let mut files = Vec::new();
+    let mut queue = VecDeque::from([&map.tree]);
+    while let Some(node) = queue.pop_front() {
+        match node {
+            FileNode::Directory { path, children, .. } => {
+                if directories.len() < MAX_DIRECTORIES {
+                    directories.push(path);
+                }
+                queue.extend(children);
+            }
+            FileNode::File { path, modified: Some(modified), .. } => files.push((path, modified)),
+            _ => {}
+        }
+    }
+
+    for path in directories {
+        let absolute = root.join(path);
+        let Ok(metadata) = absolute.metadata() else {
+            return Some(Staleness::Missing(path.clone()));
+        };
+        let changed = match cache.and_then(|c| c.scanned_dirs.get(path)) {
+            Some(stamp) => dir_changed(&absolute, &metadata, stamp, map.scanned_at),
+            None => modified_time(&metadata).is_some_and(|time| time > map.scanned_at),
+        };
+        if changed {
+            return Some(Staleness::DirectoryChanged(path.clone()));
+        }
+    }
+
+    let step = files.len().div_ceil(SAMPLED_FILES).max(1);
+    for (path, recorded) in files.into_iter().step_by(step) {
+        let Ok(metadata) = root.join(path).metadata() else {
+            return Some(Staleness::Missing(path.clone()));
+        };
+        let changed = match cache.and_then(|c| c.files.get(path)) {
+            Some(stamp) => !stamp.same_metadata(&FileStamp::from_metadata(&metadata)),
+            None => modified_time(&metadata) != Some(*recorded),
+        };
+        if changed {
+            return Some(Staleness::FileChanged(path.clone()));
+        }
+    }
+
+    let recorded = &map.git.as_ref()?.head;
+    match current_head(root) {
+        Some(current) if current != *recorded => Some(Staleness::HeadMoved { recorded: recorded.clone(), current }),
+        _ => None,
+    }
+}
+
+/// Whether a directory's entries changed since it was stamped: its mtime
+/// moved, or, while that can't be trusted, its number of entries did
+fn dir_changed(path: &Path, metadata: &Metadata, stamp: &FileStamp, scanned_at: DateTime<Utc>) -> bool {
+    let current = FileStamp::from_metadata(metadata);
+    if current.mtime_secs != stamp.mtime_secs || current.mtime_nanos != stamp.mtime_nanos {
+        return true;
+    }
+    let racy = modified_time(metadata).is_some_and(|time| time >= scanned_at - Duration::seconds(RACY_WINDOW_SECS));
+    racy && fs::read_dir(path).map_or(true, |entries| entries.count() as u64 != stamp.size)
+}
+
+fn modified_time(metadata: &Metadata) -> Option<DateTime<Utc>> {
+    metadata.modified().ok().map(DateTime::<Utc>::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{scan_incremental, ScannerConfig};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_staleness_after_changes() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let config = ScannerConfig { show_progress: false, read_git: false, ..ScannerConfig::default() };
+        let output = scan_incremental(&root, &config, None).unwrap();
+        let cache = Some(&output.cache);
+        assert_eq!(check_staleness(&root, &output.map, cache), None);
+
+        // Writing in place leaves the directory's mtime alone
+        fs::write(root.join("src/main.rs"), "fn main() { run(); }\n").unwrap();
+        assert_eq!(
+            check_staleness(&root, &output.map, cache),
+            Some(Staleness::FileChanged(PathBuf::from("src/main.rs")))
+        );
+
+        // Added right after the scan, likely within the same filesystem clock tick
+        fs::write(root.join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+        assert_eq!(
+            check_staleness(&root, &output.map, cache),
+            Some(Staleness::DirectoryChanged(PathBuf::from("src")))
+        );
+    }
+}
@@ -1,6 +1,10 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use crate::map::FileNode;
+
+use super::FileStamp;
+
 /// Count files and directories within a path
 pub fn count_dir_contents(path: &Path) -> (usize, usize) {
     let mut file_count = 0;
@@ -17,6 +21,29 @@ pub fn count_dir_contents(path: &Path) -> (usize, usize) {
     (file_count, dir_count)
 }
 
+/// Count files and directories within a path like `count_dir_contents`,
+/// stamping the path and every directory beneath it (keyed by full path)
+pub fn count_and_stamp_dir(path: &Path) -> (usize, usize, Vec<(PathBuf, FileStamp)>) {
+    let mut file_count = 0;
+    let mut dir_count = 0;
+    let mut stamps = Vec::new();
+
+    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_file() {
+            file_count += 1;
+        } else if entry.file_type().is_dir() {
+            if entry.path() != path {
+                dir_count += 1;
+            }
+            if let Ok(metadata) = entry.metadata() {
+                stamps.push((entry.into_path(), FileStamp::from_metadata(&metadata)));
+            }
+        }
+    }
+
+    (file_count, dir_count, stamps)
+}
+
 /// Count lines in a directory (sum of all text files) - for future use
 #[allow(dead_code)]
 pub fn count_lines_in_dir(path: &Path) -> usize {
@@ -51,3 +78,17 @@ pub fn count_lines_in_dir(path: &Path) -> usize {
 
     total
 }
+
+/// Count (total files, indexed files, directories) in a scanned tree, the
+/// same way the scanner tallies them
+pub fn count_tree(node: &FileNode) -> (usize, usize, usize) {
+    match node {
+        FileNode::File { .. } => (1, 1, 0),
+        FileNode::Collapsed { file_count, .. } => (*file_count, 0, 1),
+        FileNode::Directory { children, .. } => {
+            children.iter().map(count_tree).fold((0, 0, 1), |acc, child| {
+                (acc.0 + child.0, acc.1 + child.1, acc.2 + child.2)
+            })
+        }
+    }
+}
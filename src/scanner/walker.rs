@@ -1,20 +1,34 @@
-use std::collections::HashMap;
-use std::fs;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 
-use crate::map::{FileNode, MatchedPattern, ScanMeta, SystemMap};
+use crate::config::SysmapConfig;
+use crate::deps::{
+    build_dependencies, collect_entry_points, collect_packages, mark_entry_points, parse_imports, path_key,
+    supports_language, RawImport,
+};
+use crate::map::{Encoding, FileNode, GitInfo, LineCounts, MatchedPattern, ScanMeta, SystemMap};
 use crate::patterns::{
-    self, default_collapse_patterns, default_ignore_patterns, default_purpose_patterns,
-    extension_to_language, should_collapse, should_ignore, CollapsePattern,
+    has_generated_header, is_code_language, is_entry_point, is_generated_content, is_minified, CollapsePattern, CollapseThresholds, LanguageDetector, PatternMatcher, PatternSet,
+    AUTO_COLLAPSE_PATTERN,
+};
+
+use super::{
+    apply_git_history, count_and_stamp_dir, count_dir_contents, count_lines, decode, detect_project_type, detect_sub_projects, hash_bytes,
+    read_git_history, sniff, ChecksumCache, CollapsedTotals, ContentHasher, FileStamp, IgnoreRules, LineStream, SNIFF_LEN,
 };
 
-use super::{detect_project_type, count_dir_contents};
+/// Text files larger than this are read a chunk at a time and only hashed
+/// and line-counted
+const MAX_ANALYZED_SIZE: u64 = 1024 * 1024;
 
 /// Scanner configuration
 pub struct ScannerConfig {
@@ -22,8 +36,16 @@ pub struct ScannerConfig {
     pub show_progress: bool,
     /// Maximum depth to scan
     pub max_depth: Option<usize>,
-    /// Whether to respect gitignore
+    /// Whether to respect .gitignore, .ignore and git exclude files
     pub respect_gitignore: bool,
+    /// Whether to read commit history when the root is in a git repository
+    pub read_git: bool,
+    /// Days of history counted as recent churn
+    pub git_window_days: u32,
+    /// Collapse, ignore, project and purpose patterns
+    pub patterns: PatternSet,
+    /// Worker threads for scanning (0 = one per CPU core)
+    pub threads: usize,
 }
 
 impl Default for ScannerConfig {
@@ -32,19 +54,173 @@ impl Default for ScannerConfig {
             show_progress: true,
             max_depth: Some(20),
             respect_gitignore: true,
+            read_git: true,
+            git_window_days: 90,
+            patterns: PatternSet::default(),
+            threads: 0,
         }
     }
 }
 
-/// Scan a directory and build a SystemMap
-pub fn scan_directory(root: &Path, config: &ScannerConfig) -> Result<SystemMap> {
+impl ScannerConfig {
+    /// Build a scanner configuration from the user's config.toml
+    pub fn from_settings(settings: &SysmapConfig) -> Result<Self> {
+        Ok(Self {
+            max_depth: Some(settings.scan.max_depth),
+            respect_gitignore: settings.scan.respect_gitignore,
+            read_git: settings.git.enabled,
+            git_window_days: settings.git.window_days,
+            patterns: settings.patterns()?,
+            ..Self::default()
+        })
+    }
+}
+
+/// Results of a previous scan that unchanged files can reuse
+pub struct PreviousScan<'a> {
+    files: HashMap<&'a Path, &'a FileNode>,
+    collapsed: HashMap<&'a Path, &'a FileNode>,
+    /// Churn recorded on each directory
+    churn: HashMap<&'a Path, usize>,
+    /// Directories collapsed by a threshold rather than a named pattern
+    auto_collapsed: HashSet<&'a Path>,
+    cache: &'a ChecksumCache,
+}
+
+impl<'a> PreviousScan<'a> {
+    pub fn new(map: &'a SystemMap, cache: &'a ChecksumCache) -> Self {
+        let mut previous = Self {
+            files: HashMap::new(),
+            collapsed: HashMap::new(),
+            churn: HashMap::new(),
+            auto_collapsed: map
+                .patterns_matched
+                .iter()
+                .filter(|m| m.pattern == AUTO_COLLAPSE_PATTERN)
+                .map(|m| m.path.as_path())
+                .collect(),
+            cache,
+        };
+        previous.index(&map.tree);
+        previous
+    }
+
+    fn index(&mut self, node: &'a FileNode) {
+        match node {
+            FileNode::File { path, .. } => {
+                self.files.insert(path, node);
+            }
+            FileNode::Directory { path, children, churn, .. } => {
+                if let Some(churn) = churn {
+                    self.churn.insert(path, *churn);
+                }
+                for child in children {
+                    self.index(child);
+                }
+            }
+            FileNode::Collapsed { path, .. } => {
+                self.collapsed.insert(path, node);
+            }
+        }
+    }
+
+    /// Previous node and stamp for a file whose mtime and size are unchanged
+    fn unchanged_file(&self, path: &Path, stamp: &FileStamp) -> Option<(&'a FileNode, &'a FileStamp)> {
+        let cached = self.cache.files.get(path)?;
+        if !cached.same_metadata(stamp) {
+            return None;
+        }
+        self.files.get(path).map(|node| (*node, cached))
+    }
+
+    /// Previous node and stamp for a file whose contents hash is unchanged
+    fn same_content(&self, path: &Path, hash: &str) -> Option<(&'a FileNode, &'a FileStamp)> {
+        let cached = self.cache.files.get(path)?;
+        if cached.hash.as_deref() != Some(hash) {
+            return None;
+        }
+        self.files.get(path).map(|node| (*node, cached))
+    }
+
+    /// Previous counts for a collapsed directory none of whose directories changed
+    fn unchanged_collapsed(&self, path: &Path, absolute: &Path) -> Option<(usize, usize)> {
+        match self.unchanged_collapsed_node(path, absolute)? {
+            FileNode::Collapsed { file_count, dir_count, .. } => Some((*file_count, *dir_count)),
+            _ => None,
+        }
+    }
+
+    /// Previous node of a directory collapsed by a threshold none of whose directories changed
+    fn unchanged_auto_collapsed(&self, path: &Path, absolute: &Path) -> Option<&'a FileNode> {
+        if !self.auto_collapsed.contains(path) {
+            return None;
+        }
+        self.unchanged_collapsed_node(path, absolute)
+    }
+
+    /// Git history recorded for a file by the previous scan
+    fn git_info(&self, path: &Path) -> Option<GitInfo> {
+        match self.files.get(path)? {
+            FileNode::File { git, .. } => git.clone(),
+            _ => None,
+        }
+    }
+
+    /// Churn recorded on a directory by the previous scan
+    fn dir_churn(&self, path: &Path) -> Option<usize> {
+        self.churn.get(path).copied()
+    }
+
+    /// Previous node of a collapsed directory, if it and every directory
+    /// stamped beneath it keep their mtimes. Adding or removing an entry only
+    /// touches the mtime of its own directory, so all of them are compared.
+    fn unchanged_collapsed_node(&self, path: &Path, absolute: &Path) -> Option<&'a FileNode> {
+        self.cache.dirs.get(path)?;
+        let unchanged = self.cache.dirs_under(path).all(|(dir, cached)| {
+            let current = dir.strip_prefix(path).map(|rest| absolute.join(rest));
+            current
+                .ok()
+                .and_then(|current| current.metadata().ok())
+                .is_some_and(|metadata| cached.same_metadata(&FileStamp::from_metadata(&metadata)))
+        });
+        if !unchanged {
+            return None;
+        }
+        self.collapsed.get(path).copied()
+    }
+
+    /// Totals recorded for a directory collapsed by a threshold
+    fn collapsed_totals(&self, path: &Path) -> Option<CollapsedTotals> {
+        self.cache.auto_collapsed.get(path).copied()
+    }
+
+    /// Carry the stamps of a reused collapsed directory over to the new cache
+    fn copy_dir_stamps(&self, path: &Path, stamps: &mut ChecksumCache) {
+        for (dir, stamp) in self.cache.dirs_under(path) {
+            stamps.dirs.insert(dir.clone(), stamp.clone());
+        }
+        for (dir, count) in self.cache.ignored.range(path.to_path_buf()..).take_while(|(dir, _)| dir.starts_with(path)) {
+            stamps.ignored.insert(dir.clone(), *count);
+        }
+    }
+}
+
+/// A scanned map plus the file stamps needed for the next incremental update
+pub struct ScanOutput {
+    pub map: SystemMap,
+    pub cache: ChecksumCache,
+    /// Files whose previous results were reused without reading them again
+    pub reused: usize,
+}
+
+/// Scan a directory, reusing results for files unchanged since a previous scan
+pub fn scan_incremental(
+    root: &Path,
+    config: &ScannerConfig,
+    previous: Option<&PreviousScan>,
+) -> Result<ScanOutput> {
     let start = Instant::now();
     let root = root.canonicalize()?;
-    
-    let collapse_patterns = default_collapse_patterns();
-    let ignore_patterns = default_ignore_patterns();
-    let purpose_patterns = default_purpose_patterns();
-    let ext_to_lang = extension_to_language();
 
     // Set up progress bar
     let progress = if config.show_progress {
@@ -60,242 +236,895 @@ pub fn scan_directory(root: &Path, config: &ScannerConfig) -> Result<SystemMap>
         None
     };
 
+    let ignore_rules = if config.respect_gitignore {
+        IgnoreRules::for_root(&root)
+    } else {
+        IgnoreRules::disabled()
+    };
+
     let mut map = SystemMap::new(root.clone());
-    let mut patterns_matched: Vec<MatchedPattern> = Vec::new();
-    let mut total_files = 0usize;
-    let mut total_dirs = 0usize;
-    let mut indexed_files = 0usize;
-
-    // Build the tree recursively
-    let tree = scan_dir_recursive(
-        &root,
-        &root,
-        &collapse_patterns,
-        &ignore_patterns,
-        &purpose_patterns,
-        &ext_to_lang,
-        &mut patterns_matched,
-        &mut total_files,
-        &mut total_dirs,
-        &mut indexed_files,
-        0,
-        config.max_depth.unwrap_or(20),
-        config.respect_gitignore,
-        &progress,
-    )?;
-
-    if let Some(pb) = &progress {
+    let scanner = DirScanner::new(&root, config, previous, progress)?;
+    let mut tally = ScanTally::default();
+
+    // Build the tree recursively, spreading directories and files over the pool
+    let mut tree = thread_pool(config)?.install(|| scanner.scan_dir(&root, 0, &ignore_rules, &mut tally))?;
+
+    if let Some(pb) = &scanner.progress {
         pb.finish_and_clear();
     }
 
+    // Entry points declared by manifests, which may live anywhere in the tree
+    let entries = collect_entry_points(&root, &tree);
+    mark_entry_points(&mut tree, &entries);
+
     // Detect project type
-    let project_type = detect_project_type(&root);
+    let project_type = detect_project_type(&root, &config.patterns.project);
+    map.projects = detect_sub_projects(&root, &tree, &config.patterns.project);
+
+    // Commit history and churn, when the project is in a git repository
+    let history = config
+        .read_git
+        .then(|| read_git_history(&root, config.git_window_days))
+        .flatten();
+    apply_git_history(&mut tree, history.as_ref());
+    map.git = history.map(|h| h.meta);
+
+    // Resolve imports now that every file in the project is known
+    map.dependencies = build_dependencies(&root, &tree, tally.stamps.imports());
+    map.dependencies.packages = collect_packages(&root, &tree);
 
     map.tree = tree;
     map.project_type = project_type;
-    map.patterns_matched = patterns_matched;
+    map.patterns_matched = tally.patterns_matched;
     map.meta = ScanMeta {
-        total_files,
-        indexed_files,
-        total_dirs,
+        total_files: tally.total_files,
+        indexed_files: tally.indexed_files,
+        total_dirs: tally.total_dirs,
+        ignored_entries: tally.stamps.ignored_entries(),
         scan_time_ms: start.elapsed().as_millis() as u64,
     };
 
-    Ok(map)
+    Ok(ScanOutput {
+        map,
+        cache: tally.stamps,
+        reused: tally.reused,
+    })
+}
+
+/// A directory of an existing map, scanned again
+pub struct SubtreeScan {
+    pub node: FileNode,
+    pub patterns_matched: Vec<MatchedPattern>,
+    /// Stamps for the files and collapsed directories inside the subtree
+    pub cache: ChecksumCache,
+    pub reused: usize,
 }
 
-fn scan_dir_recursive(
-    path: &Path,
+/// Rescan one directory (relative to the root) of an existing map, reusing
+/// results for files unchanged since the previous scan
+pub fn scan_subtree(
     root: &Path,
-    collapse_patterns: &[CollapsePattern],
-    ignore_patterns: &[&str],
-    purpose_patterns: &[patterns::PurposePattern],
-    ext_to_lang: &HashMap<&str, &str>,
-    patterns_matched: &mut Vec<MatchedPattern>,
-    total_files: &mut usize,
-    total_dirs: &mut usize,
-    indexed_files: &mut usize,
-    depth: usize,
+    dir: &Path,
+    config: &ScannerConfig,
+    previous: &PreviousScan,
+) -> Result<SubtreeScan> {
+    // Rebuild the ignore rules in effect inside the directory
+    let mut ignore_rules = if config.respect_gitignore {
+        IgnoreRules::for_root(root)
+    } else {
+        IgnoreRules::disabled()
+    };
+    let mut path = root.to_path_buf();
+    for component in dir.components() {
+        path.push(component);
+        ignore_rules = ignore_rules.descend(&path);
+    }
+
+    let scanner = DirScanner::new(root, config, Some(previous), None)?;
+    let mut tally = ScanTally::default();
+    let depth = dir.components().count();
+    let node = thread_pool(config)?.install(|| scanner.scan_dir(&path, depth, &ignore_rules, &mut tally))?;
+
+    Ok(SubtreeScan {
+        node,
+        patterns_matched: tally.patterns_matched,
+        cache: tally.stamps,
+        reused: tally.reused,
+    })
+}
+
+fn thread_pool(config: &ScannerConfig) -> Result<ThreadPool> {
+    Ok(ThreadPoolBuilder::new().num_threads(config.threads).build()?)
+}
+
+/// Whether a hidden entry is skipped by the scan (a few dotfiles are kept)
+pub fn is_skipped_hidden(name: &str) -> bool {
+    name.starts_with('.') && !matches!(name, ".env.example" | ".gitignore" | ".dockerignore")
+}
+
+/// Paths of the open directories in a scanned tree
+fn collect_dir_paths(children: &[FileNode], paths: &mut Vec<PathBuf>) {
+    for child in children {
+        if let FileNode::Directory { path, children, .. } = child {
+            paths.push(path.clone());
+            collect_dir_paths(children, paths);
+        }
+    }
+}
+
+/// Directories nested anywhere under a list of scanned entries
+fn nested_dirs(children: &[FileNode]) -> usize {
+    children
+        .iter()
+        .map(|child| match child {
+            FileNode::File { .. } => 0,
+            FileNode::Directory { children, .. } => 1 + nested_dirs(children),
+            FileNode::Collapsed { dir_count, .. } => 1 + dir_count,
+        })
+        .sum()
+}
+
+/// Settings and previous results shared by every worker of a scan
+struct DirScanner<'a> {
+    root: &'a Path,
     max_depth: usize,
-    respect_gitignore: bool,
-    progress: &Option<ProgressBar>,
-) -> Result<FileNode> {
-    let dir_name = path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "root".to_string());
-
-    *total_dirs += 1;
-
-    if let Some(pb) = progress {
-        pb.set_message(format!("Scanning: {}", path.display()));
-    }
-
-    // Check if this directory should be collapsed
-    if let Some(pattern) = should_collapse(&dir_name, path, collapse_patterns) {
-        let (file_count, dir_count) = count_dir_contents(path);
-        *total_files += file_count;
-        
-        patterns_matched.push(MatchedPattern {
-            pattern: pattern.name.to_string(),
-            path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+    progress: Option<ProgressBar>,
+    patterns: PatternMatcher,
+    thresholds: &'a CollapseThresholds,
+    previous: Option<&'a PreviousScan<'a>>,
+    languages: LanguageDetector,
+}
+
+/// Counts and stamps gathered while scanning part of the tree.
+///
+/// Each directory entry is scanned into its own tally, and the tallies are
+/// merged in entry order, so the output doesn't depend on the thread count.
+#[derive(Default)]
+struct ScanTally {
+    patterns_matched: Vec<MatchedPattern>,
+    stamps: ChecksumCache,
+    total_files: usize,
+    total_dirs: usize,
+    indexed_files: usize,
+    reused: usize,
+    /// Size of the indexed files
+    bytes: u64,
+    /// Indexed files in a programming language, rather than data or docs
+    code_files: usize,
+}
+
+impl ScanTally {
+    fn merge(&mut self, other: ScanTally) {
+        self.patterns_matched.extend(other.patterns_matched);
+        self.stamps.merge(other.stamps);
+        self.total_files += other.total_files;
+        self.total_dirs += other.total_dirs;
+        self.indexed_files += other.indexed_files;
+        self.reused += other.reused;
+        self.bytes += other.bytes;
+        self.code_files += other.code_files;
+    }
+}
+
+/// A directory entry waiting to be scanned
+enum Pending<'p> {
+    Dir(PathBuf, IgnoreRules),
+    File(PathBuf, String),
+    Collapsed(PathBuf, String, &'p CollapsePattern),
+}
+
+impl<'a> DirScanner<'a> {
+    fn new(
+        root: &'a Path,
+        config: &'a ScannerConfig,
+        previous: Option<&'a PreviousScan<'a>>,
+        progress: Option<ProgressBar>,
+    ) -> Result<Self> {
+        Ok(Self {
+            root,
+            max_depth: config.max_depth.unwrap_or(20),
+            progress,
+            patterns: PatternMatcher::new(&config.patterns)?,
+            thresholds: &config.patterns.thresholds,
+            previous,
+            languages: LanguageDetector::default(),
+        })
+    }
+
+    fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(self.root).unwrap_or(path).to_path_buf()
+    }
+
+    fn scan_dir(&self, path: &Path, depth: usize, ignore_rules: &IgnoreRules, tally: &mut ScanTally) -> Result<FileNode> {
+        let dir_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "root".to_string());
+
+        tally.total_dirs += 1;
+
+        if let Some(pb) = &self.progress {
+            pb.set_message(format!("Scanning: {}", path.display()));
+        }
+
+        // Don't go deeper than max_depth
+        if depth >= self.max_depth {
+            let (file_count, dir_count) = count_dir_contents(path);
+            tally.total_files += file_count;
+            return Ok(FileNode::Collapsed {
+                name: dir_name,
+                path: self.relative(path),
+                reason: "max depth reached".to_string(),
+                file_count,
+                dir_count,
+            });
+        }
+
+        // A directory collapsed by a threshold stays collapsed while unchanged
+        if let Some(node) = self.reuse_auto_collapsed(path, tally) {
+            return Ok(node);
+        }
+
+        // Read directory contents, stamping the directory first so anything
+        // added while it is scanned shows up as a change
+        let metadata = path.metadata().ok();
+        let mut entries: Vec<_> = fs::read_dir(path)?
+            .filter_map(|e| e.ok())
+            .collect();
+        if let Some(metadata) = metadata {
+            let stamp = FileStamp {
+                size: entries.len() as u64,
+                ..FileStamp::from_metadata(&metadata)
+            };
+            tally.stamps.scanned_dirs.insert(self.relative(path), stamp);
+        }
+
+        // Sort entries for consistent output
+        entries.sort_by_key(|e| e.file_name());
+
+        let mut pending = Vec::new();
+        let mut ignored = 0;
+        for entry in entries {
+            let entry_path = entry.path();
+            let entry_name = entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry_path.is_dir();
+            let entry_key = path_key(&self.relative(&entry_path));
+
+            // Skip ignored files
+            if self.patterns.should_ignore(&entry_name, &entry_key) {
+                continue;
+            }
+
+            // Collapse known directories (.git, .venv, node_modules) even when
+            // they are hidden or gitignored, so they still show up in the map
+            if is_dir {
+                if let Some(pattern) = self.patterns.should_collapse(&entry_name, &entry_key, &entry_path) {
+                    pending.push(Pending::Collapsed(entry_path, entry_name, pattern));
+                    continue;
+                }
+            }
+
+            // Skip hidden files (except specific important ones like .env.example)
+            if is_skipped_hidden(&entry_name) {
+                continue;
+            }
+
+            // Skip entries excluded by .gitignore, .ignore or git excludes
+            if ignore_rules.is_ignored(&entry_path, is_dir) {
+                ignored += 1;
+                continue;
+            }
+
+            if is_dir {
+                let child_rules = ignore_rules.descend(&entry_path);
+                pending.push(Pending::Dir(entry_path, child_rules));
+            } else if entry_path.is_file() {
+                pending.push(Pending::File(entry_path, entry_name));
+            }
+        }
+        if ignored > 0 {
+            tally.stamps.ignored.insert(self.relative(path), ignored);
+        }
+
+        // Scan the entries in parallel; collecting keeps them in order
+        let scanned: Vec<Result<(FileNode, ScanTally)>> = pending
+            .into_par_iter()
+            .map(|entry| {
+                let mut entry_tally = ScanTally::default();
+                let node = match entry {
+                    Pending::Dir(path, rules) => self.scan_dir(&path, depth + 1, &rules, &mut entry_tally)?,
+                    Pending::File(path, name) => self.scan_file(&path, name, &mut entry_tally),
+                    Pending::Collapsed(path, name, pattern) => self.collapse(&path, name, pattern, &mut entry_tally),
+                };
+                Ok((node, entry_tally))
+            })
+            .collect();
+
+        let mut children = Vec::with_capacity(scanned.len());
+        for result in scanned {
+            let (node, entry_tally) = result?;
+            tally.merge(entry_tally);
+            children.push(node);
+        }
+
+        // Collapse directories past a threshold once their totals are known
+        // (the root always stays open)
+        if depth > 0 {
+            if let Some(reason) = self.thresholds.exceeded(tally.indexed_files, tally.bytes, tally.code_files) {
+                return Ok(self.auto_collapse(path, dir_name, reason, &children, tally));
+            }
+        }
+
+        let relative = self.relative(path);
+        Ok(FileNode::Directory {
+            name: dir_name,
+            churn: self.previous.and_then(|p| p.dir_churn(&relative)),
+            path: relative,
+            children,
+        })
+    }
+
+    /// Replace a scanned directory with a collapsed node, dropping the files,
+    /// stamps and matches gathered inside it
+    fn auto_collapse(
+        &self,
+        path: &Path,
+        name: String,
+        reason: String,
+        children: &[FileNode],
+        tally: &mut ScanTally,
+    ) -> FileNode {
+        let relative = self.relative(path);
+        let file_count = tally.total_files;
+        let dir_count = nested_dirs(children);
+        let totals = CollapsedTotals {
+            indexed_files: tally.indexed_files,
+            bytes: tally.bytes,
+            code_files: tally.code_files,
+        };
+
+        // Stamp every directory beneath it, keeping those of collapsed
+        // directories inside, so a change anywhere gets it scanned again
+        let mut stamps = ChecksumCache {
+            dirs: std::mem::take(&mut tally.stamps.dirs),
+            ignored: std::mem::take(&mut tally.stamps.ignored),
+            ..ChecksumCache::default()
+        };
+        let mut scanned_dirs = vec![relative.clone()];
+        collect_dir_paths(children, &mut scanned_dirs);
+        for dir in scanned_dirs {
+            if let Ok(metadata) = self.root.join(&dir).metadata() {
+                stamps.dirs.insert(dir, FileStamp::from_metadata(&metadata));
+            }
+        }
+        stamps.auto_collapsed.insert(relative.clone(), totals);
+
+        // Count what's left the way a named collapse would
+        *tally = ScanTally {
+            total_files: file_count,
+            total_dirs: 1,
+            reused: tally.reused,
+            stamps,
+            ..ScanTally::default()
+        };
+        tally.patterns_matched.push(MatchedPattern {
+            pattern: AUTO_COLLAPSE_PATTERN.to_string(),
+            path: relative.clone(),
             files_collapsed: file_count,
             dirs_collapsed: dir_count,
         });
 
-        return Ok(FileNode::Collapsed {
-            name: dir_name,
-            path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
-            reason: pattern.reason.to_string(),
+        FileNode::Collapsed {
+            name,
+            path: relative,
+            reason,
             file_count,
             dir_count,
+        }
+    }
+
+    /// The previous node of a directory collapsed by a threshold, if none of
+    /// its directories changed and its totals still exceed the thresholds
+    fn reuse_auto_collapsed(&self, path: &Path, tally: &mut ScanTally) -> Option<FileNode> {
+        if !self.thresholds.is_enabled() {
+            return None;
+        }
+        let relative = self.relative(path);
+        let previous = self.previous?;
+        let node = previous.unchanged_auto_collapsed(&relative, path)?;
+        let FileNode::Collapsed { name, file_count, dir_count, .. } = node else {
+            return None;
+        };
+        let totals = previous.collapsed_totals(&relative)?;
+        let reason = self.thresholds.exceeded(totals.indexed_files, totals.bytes, totals.code_files)?;
+
+        tally.total_files += file_count;
+        tally.patterns_matched.push(MatchedPattern {
+            pattern: AUTO_COLLAPSE_PATTERN.to_string(),
+            path: relative.clone(),
+            files_collapsed: *file_count,
+            dirs_collapsed: *dir_count,
         });
+        previous.copy_dir_stamps(&relative, &mut tally.stamps);
+        tally.stamps.auto_collapsed.insert(relative.clone(), totals);
+        Some(FileNode::Collapsed {
+            name: name.clone(),
+            path: relative,
+            reason,
+            file_count: *file_count,
+            dir_count: *dir_count,
+        })
     }
 
-    // Don't go deeper than max_depth
-    if depth >= max_depth {
-        let (file_count, dir_count) = count_dir_contents(path);
-        return Ok(FileNode::Collapsed {
-            name: dir_name,
-            path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
-            reason: "max depth reached".to_string(),
+    fn collapse(&self, path: &Path, name: String, pattern: &CollapsePattern, tally: &mut ScanTally) -> FileNode {
+        let relative = self.relative(path);
+
+        // Counting a large collapsed directory is the expensive part of a scan,
+        // so reuse the previous counts while none of its directories changed
+        let reused = self
+            .previous
+            .and_then(|previous| Some((previous, previous.unchanged_collapsed(&relative, path)?)));
+        let (file_count, dir_count) = match reused {
+            Some((previous, counts)) => {
+                previous.copy_dir_stamps(&relative, &mut tally.stamps);
+                counts
+            }
+            None => {
+                let (file_count, dir_count, stamps) = count_and_stamp_dir(path);
+                for (dir, stamp) in stamps {
+                    tally.stamps.dirs.insert(self.relative(&dir), stamp);
+                }
+                (file_count, dir_count)
+            }
+        };
+
+        tally.total_files += file_count;
+        tally.total_dirs += 1;
+
+        tally.patterns_matched.push(MatchedPattern {
+            pattern: pattern.name.clone(),
+            path: self.relative(path),
+            files_collapsed: file_count,
+            dirs_collapsed: dir_count,
+        });
+
+        FileNode::Collapsed {
+            name,
+            path: self.relative(path),
+            reason: pattern.reason.clone(),
             file_count,
             dir_count,
-        });
+        }
     }
 
-    // Read directory contents
-    let mut children = Vec::new();
-    let mut entries: Vec<_> = fs::read_dir(path)?
-        .filter_map(|e| e.ok())
-        .collect();
-    
-    // Sort entries for consistent output
-    entries.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-
-    for entry in entries {
-        let entry_path = entry.path();
-        let entry_name = entry.file_name().to_string_lossy().to_string();
-
-        // Skip ignored files
-        if should_ignore(&entry_name, ignore_patterns) {
-            continue;
-        }
-
-        // Skip hidden files (except specific important ones like .env.example)
-        if entry_name.starts_with('.') 
-            && !matches!(entry_name.as_str(), ".env.example" | ".gitignore" | ".dockerignore") 
-        {
-            // Still check for collapsible directories like .git, .venv
-            if entry_path.is_dir() {
-                if let Some(pattern) = should_collapse(&entry_name, &entry_path, collapse_patterns) {
-                    let (file_count, dir_count) = count_dir_contents(&entry_path);
-                    *total_files += file_count;
-                    *total_dirs += 1;
-                    
-                    patterns_matched.push(MatchedPattern {
-                        pattern: pattern.name.to_string(),
-                        path: entry_path.strip_prefix(root).unwrap_or(&entry_path).to_path_buf(),
-                        files_collapsed: file_count,
-                        dirs_collapsed: dir_count,
-                    });
-
-                    children.push(FileNode::Collapsed {
-                        name: entry_name,
-                        path: entry_path.strip_prefix(root).unwrap_or(&entry_path).to_path_buf(),
-                        reason: pattern.reason.to_string(),
-                        file_count,
-                        dir_count,
-                    });
-                }
+    fn scan_file(&self, path: &Path, name: String, tally: &mut ScanTally) -> FileNode {
+        tally.total_files += 1;
+        tally.indexed_files += 1;
+
+        let relative = self.relative(path);
+
+        // Get file metadata
+        let metadata = path.metadata().ok();
+        let modified = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .map(DateTime::<Utc>::from);
+        let mut stamp = metadata.as_ref().map(FileStamp::from_metadata);
+
+        // Analyze file contents, reusing the previous results when unchanged
+        let content = self.read_content(path, &relative, &name, stamp.as_mut(), tally);
+        tally.bytes += metadata.as_ref().map_or(0, |m| m.len());
+        tally.code_files += is_code_language(content.language.as_deref()) as usize;
+
+        // Detect purpose from the name, then from the contents
+        let key = path_key(&relative);
+        let purpose = self
+            .patterns
+            .detect_purpose(&name, &key)
+            .or(content.entry_point.then_some("entry"))
+            .map(|s| s.to_string());
+        let generated = content.generated || self.patterns.is_generated(&name, &key);
+        let vendored = self.patterns.is_vendored(&key);
+
+        if let Some(mut stamp) = stamp {
+            stamp.imports = content.imports;
+            stamp.entry_point = content.entry_point;
+            stamp.generated = content.generated;
+            tally.stamps.files.insert(relative.clone(), stamp);
+        }
+        let hash = tally.stamps.files.get(&relative).and_then(|s| s.hash.clone());
+        let git = self.previous.and_then(|p| p.git_info(&relative));
+
+        FileNode::File {
+            name,
+            path: relative,
+            lines: content.lines,
+            counts: content.counts,
+            encoding: content.encoding,
+            is_binary: content.is_binary,
+            large: content.large,
+            generated,
+            vendored,
+            language: content.language,
+            purpose,
+            modified,
+            hash,
+            git,
+        }
+    }
+
+    fn read_content(
+        &self,
+        path: &Path,
+        relative: &Path,
+        name: &str,
+        stamp: Option<&mut FileStamp>,
+        tally: &mut ScanTally,
+    ) -> ContentInfo {
+        // Files that can't be read are known by their name alone
+        let unread = ContentInfo {
+            language: self.languages.detect(name, None).map(str::to_string),
+            ..ContentInfo::default()
+        };
+        let Some(stamp) = stamp else {
+            return unread;
+        };
+
+        if let Some(previous) = self.previous {
+            if let Some((node, cached)) = previous.unchanged_file(relative, stamp) {
+                tally.reused += 1;
+                stamp.hash = cached.hash.clone();
+                return ContentInfo::from_previous(node, cached);
             }
-            continue;
-        }
-
-        if entry_path.is_dir() {
-            let child = scan_dir_recursive(
-                &entry_path,
-                root,
-                collapse_patterns,
-                ignore_patterns,
-                purpose_patterns,
-                ext_to_lang,
-                patterns_matched,
-                total_files,
-                total_dirs,
-                indexed_files,
-                depth + 1,
-                max_depth,
-                respect_gitignore,
-                progress,
-            )?;
-            children.push(child);
-        } else if entry_path.is_file() {
-            *total_files += 1;
-            *indexed_files += 1;
-
-            // Get file metadata
-            let metadata = entry_path.metadata().ok();
-            let modified = metadata
-                .as_ref()
-                .and_then(|m| m.modified().ok())
-                .map(|t| DateTime::<Utc>::from(t));
-
-            // Detect language from extension
-            let language = entry_path
-                .extension()
-                .and_then(|e| e.to_str())
-                .and_then(|e| ext_to_lang.get(e))
-                .map(|s| s.to_string());
-
-            // Detect purpose
-            let purpose = patterns::detect_purpose(&entry_name, purpose_patterns)
-                .map(|s| s.to_string());
-
-            // Count lines for code files
-            let lines = if is_text_file(&entry_path) {
-                count_lines(&entry_path).ok()
-            } else {
-                None
-            };
+        }
 
-            children.push(FileNode::File {
-                name: entry_name,
-                path: entry_path.strip_prefix(root).unwrap_or(&entry_path).to_path_buf(),
-                lines,
-                language,
-                purpose,
-                modified,
-            });
+        // Sniff the leading bytes first, so binaries are never read in full
+        let Ok(mut file) = File::open(path) else {
+            return unread;
+        };
+        let mut bytes = Vec::new();
+        if (&mut file).take(SNIFF_LEN as u64).read_to_end(&mut bytes).is_err() {
+            return unread;
+        }
+        let Some(encoding) = sniff(&bytes, path) else {
+            // Binaries are hashed to match renames, but never decoded
+            if let Ok(hash) = stream_rest(&mut file, &bytes, |_| {}) {
+                stamp.hash = Some(hash);
+            }
+            return ContentInfo { is_binary: true, ..unread };
+        };
+        let large = stamp.size > MAX_ANALYZED_SIZE;
+        let (hash, streamed_lines) = if large {
+            let mut lines = LineStream::new(encoding);
+            match stream_rest(&mut file, &bytes, |chunk| lines.feed(chunk)) {
+                Ok(hash) => (hash, lines.lines()),
+                Err(_) => return unread,
+            }
+        } else {
+            if file.read_to_end(&mut bytes).is_err() {
+                return unread;
+            }
+            (hash_bytes(&bytes), 0)
+        };
+
+        // Touched but identical files keep their previous results
+        let unchanged = self.previous.and_then(|p| p.same_content(relative, &hash));
+        stamp.hash = Some(hash);
+        if let Some((node, cached)) = unchanged {
+            tally.reused += 1;
+            return ContentInfo::from_previous(node, cached);
+        }
+
+        if large {
+            ContentInfo::skim(&bytes, encoding, streamed_lines, stamp.size, name, &self.languages)
+        } else {
+            ContentInfo::analyze(&bytes, encoding, relative, name, &self.languages)
         }
     }
+}
 
-    Ok(FileNode::Directory {
-        name: dir_name,
-        path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
-        children,
-    })
+/// Hash the rest of a file a chunk at a time, starting from the head
+/// already read, passing every chunk on as it goes
+fn stream_rest(file: &mut File, head: &[u8], mut on_chunk: impl FnMut(&[u8])) -> std::io::Result<String> {
+    let mut hasher = ContentHasher::new();
+    hasher.update(head);
+    on_chunk(head);
+
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        on_chunk(&buffer[..read]);
+    }
+    Ok(hasher.finish())
 }
 
-/// Check if a file is likely a text file based on extension
-fn is_text_file(path: &Path) -> bool {
-    let text_extensions = [
-        "py", "rs", "js", "ts", "jsx", "tsx", "go", "java", "kt", "rb", "php",
-        "c", "cpp", "cc", "h", "hpp", "cs", "swift", "scala", "clj", "ex", "exs",
-        "erl", "hs", "lua", "r", "jl", "sql", "sh", "bash", "zsh", "fish", "ps1",
-        "yaml", "yml", "toml", "json", "xml", "html", "css", "scss", "sass", "less",
-        "md", "rst", "txt", "cfg", "ini", "conf", "env",
-    ];
-
-    path.extension()
-        .and_then(|e| e.to_str())
-        .map(|e| text_extensions.contains(&e.to_lowercase().as_str()))
-        .unwrap_or(false)
+/// Results derived from a file's contents, reusable while the file is unchanged
+#[derive(Default)]
+struct ContentInfo {
+    lines: Option<usize>,
+    counts: Option<LineCounts>,
+    encoding: Option<Encoding>,
+    is_binary: bool,
+    /// Too large to analyze beyond counting lines
+    large: bool,
+    language: Option<String>,
+    imports: Vec<RawImport>,
+    entry_point: bool,
+    /// Generated according to the contents (name patterns are checked separately)
+    generated: bool,
+}
+
+impl ContentInfo {
+    fn analyze(bytes: &[u8], encoding: Encoding, path: &Path, name: &str, languages: &LanguageDetector) -> Self {
+        let text = decode(bytes, encoding);
+        let language = languages.detect(name, Some(&text));
+        let imports = match language {
+            Some(language) if supports_language(language) => parse_imports(language, &text),
+            _ => Vec::new(),
+        };
+
+        let counts = count_lines(&text, language);
+        Self {
+            lines: Some(counts.code + counts.comment + counts.blank),
+            counts: Some(counts),
+            encoding: Some(encoding),
+            is_binary: false,
+            large: false,
+            language: language.map(str::to_string),
+            imports,
+            entry_point: is_entry_point(path, language, &text),
+            generated: is_generated_content(language, &text),
+        }
+    }
+
+    /// What can be told of a large file from its line count and first bytes
+    fn skim(head: &[u8], encoding: Encoding, lines: usize, size: u64, name: &str, languages: &LanguageDetector) -> Self {
+        let text = decode(head, encoding);
+        let language = languages.detect(name, Some(&text));
+        Self {
+            lines: Some(lines),
+            encoding: Some(encoding),
+            large: true,
+            language: language.map(str::to_string),
+            generated: has_generated_header(&text) || is_minified(language, size as usize, lines),
+            ..Self::default()
+        }
+    }
+
+    fn from_previous(node: &FileNode, stamp: &FileStamp) -> Self {
+        let FileNode::File { lines, counts, encoding, is_binary, large, language, .. } = node else {
+            return Self::default();
+        };
+        Self {
+            lines: *lines,
+            counts: *counts,
+            encoding: *encoding,
+            is_binary: *is_binary,
+            large: *large,
+            language: language.clone(),
+            imports: stamp.imports.clone(),
+            entry_point: stamp.entry_point,
+            generated: stamp.generated,
+        }
+    }
 }
 
-/// Count lines in a file
-fn count_lines(path: &Path) -> Result<usize> {
-    let file = fs::File::open(path)?;
-    let reader = BufReader::new(file);
-    Ok(reader.lines().count())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_respects_gitignore() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("generated/deep")).unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join(".gitignore"), "generated/\nnode_modules/\n").unwrap();
+        fs::write(root.join("generated/deep/out.rs"), "fn x() {}\n").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "\n").unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let config = ScannerConfig {
+            show_progress: false,
+            ..ScannerConfig::default()
+        };
+        let map = scan_incremental(root, &config, None).unwrap().map;
+        let names: Vec<&str> = map.tree.children().unwrap().iter().map(|c| c.name()).collect();
+
+        assert!(!names.contains(&"generated"));
+        // Collapse patterns win over ignore rules
+        assert!(names.contains(&"node_modules"));
+        assert!(names.contains(&"main.rs"));
+        assert_eq!(map.meta.ignored_entries, 1);
+
+        let config = ScannerConfig {
+            show_progress: false,
+            respect_gitignore: false,
+            ..ScannerConfig::default()
+        };
+        let map = scan_incremental(root, &config, None).unwrap().map;
+        let names: Vec<&str> = map.tree.children().unwrap().iter().map(|c| c.name()).collect();
+        assert!(names.contains(&"generated"));
+        assert_eq!(map.meta.ignored_entries, 0);
+    }
+
+    #[test]
+    fn test_parallel_scan_is_deterministic() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        for dir in ["a", "b/c", "b/d", "node_modules/x", "e/f/g"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for i in 0..20 {
+            fs::write(root.join(format!("a/mod_{i}.py")), "import os\n").unwrap();
+            fs::write(root.join(format!("b/c/file_{i}.rs")), "fn f() {}\n").unwrap();
+        }
+        fs::write(root.join("b/d/main.go"), "package main\n").unwrap();
+        fs::write(root.join("e/f/g/deep.js"), "require('./x')\n").unwrap();
+        fs::write(root.join("node_modules/x/index.js"), "\n").unwrap();
+
+        let scan = |threads| {
+            let config = ScannerConfig {
+                show_progress: false,
+                threads,
+                ..ScannerConfig::default()
+            };
+            let output = scan_incremental(root, &config, None).unwrap();
+            (
+                serde_json::to_string(&output.map.tree).unwrap(),
+                serde_json::to_string(&output.map.patterns_matched).unwrap(),
+                serde_json::to_string(&output.cache).unwrap(),
+                output.map.meta.total_files,
+                output.map.meta.total_dirs,
+            )
+        };
+
+        let sequential = scan(1);
+        for _ in 0..3 {
+            assert_eq!(scan(8), sequential);
+        }
+    }
+
+    #[test]
+    fn test_collapsed_directory_recounted_after_deep_change() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join("node_modules/pkg/a.js"), "\n").unwrap();
+
+        let config = ScannerConfig {
+            show_progress: false,
+            ..ScannerConfig::default()
+        };
+        let collapsed_files = |map: &SystemMap| map.patterns_matched[0].files_collapsed;
+        let first = scan_incremental(root, &config, None).unwrap();
+        assert_eq!(collapsed_files(&first.map), 1);
+
+        // Only node_modules/pkg's mtime changes, not node_modules'
+        fs::write(root.join("node_modules/pkg/b.js"), "\n").unwrap();
+        let previous = PreviousScan::new(&first.map, &first.cache);
+        let second = scan_incremental(root, &config, Some(&previous)).unwrap();
+        assert_eq!(collapsed_files(&second.map), 2);
+
+        let previous = PreviousScan::new(&second.map, &second.cache);
+        let third = scan_incremental(root, &config, Some(&previous)).unwrap();
+        assert_eq!(third.cache.dirs.len(), 2);
+        assert_eq!(collapsed_files(&third.map), 2);
+    }
+
+    #[test]
+    fn test_large_file_is_only_counted() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let contents = format!("import util\nif __name__ == \"__main__\":\n{}", "x = 1\n".repeat(200_000));
+        fs::write(root.join("big.py"), &contents).unwrap();
+        fs::write(root.join("util.py"), "X = 1\n").unwrap();
+
+        let config = ScannerConfig {
+            show_progress: false,
+            ..ScannerConfig::default()
+        };
+        let map = scan_incremental(root, &config, None).unwrap().map;
+        let FileNode::File { lines, counts, large, language, purpose, hash, .. } = &map.tree.children().unwrap()[0] else {
+            panic!("expected a file");
+        };
+        assert!(*large);
+        assert_eq!(*lines, Some(200_002));
+        assert_eq!(*counts, None);
+        assert_eq!(language.as_deref(), Some("python"));
+        assert_eq!(*purpose, None);
+        assert_eq!(hash.as_deref(), Some(hash_bytes(contents.as_bytes()).as_str()));
+        assert!(map.dependencies.internal.is_empty());
+    }
+
+    #[test]
+    fn test_threshold_collapse() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        for dir in ["src", "fixtures/cases", "data"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for i in 0..12 {
+            fs::write(root.join(format!("fixtures/cases/case_{i}.rs")), "fn f() {}\n").unwrap();
+        }
+        for i in 0..6 {
+            fs::write(root.join(format!("data/row_{i}.json")), "{}\n").unwrap();
+        }
+        fs::write(root.join("data/load.py"), "import json\n").unwrap();
+        for i in 0..8 {
+            fs::write(root.join(format!("src/mod_{i}.rs")), "fn f() {}\n").unwrap();
+        }
+
+        let mut config = ScannerConfig {
+            show_progress: false,
+            ..ScannerConfig::default()
+        };
+        config.patterns.thresholds = CollapseThresholds {
+            max_files: Some(10),
+            non_code_percent: Some(80),
+            min_files: 5,
+            ..CollapseThresholds::default()
+        };
+        let output = scan_incremental(root, &config, None).unwrap();
+        let map = &output.map;
+        let children = map.tree.children().unwrap();
+
+        // The innermost directory over the limit collapses, not its parent
+        let FileNode::Directory { children: fixtures, .. } = &children[1] else {
+            panic!("fixtures should stay open");
+        };
+        let FileNode::Collapsed { reason, file_count, .. } = &fixtures[0] else {
+            panic!("fixtures/cases should collapse");
+        };
+        assert_eq!(reason, "12 files, over the 10 file limit");
+        assert_eq!(*file_count, 12);
+
+        let FileNode::Collapsed { reason, .. } = &children[0] else {
+            panic!("data should collapse");
+        };
+        assert_eq!(reason, "85% non-code files, over the 80% limit");
+        assert!(matches!(children[2], FileNode::Directory { .. }));
+
+        assert_eq!(map.meta.total_files, 27);
+        assert_eq!(map.meta.indexed_files, 8);
+        assert_eq!(map.patterns_matched.len(), 2);
+        assert!(map.patterns_matched.iter().all(|m| m.pattern == AUTO_COLLAPSE_PATTERN));
+
+        // An unchanged collapsed directory is reused without being scanned
+        let previous = PreviousScan::new(map, &output.cache);
+        let again = scan_incremental(root, &config, Some(&previous)).unwrap();
+        let json = |map: &SystemMap| serde_json::to_string(&map.tree).unwrap();
+        assert_eq!(json(&again.map), json(map));
+        assert_eq!(again.map.patterns_matched.len(), 2);
+        assert_eq!(again.reused, 8);
+
+        let rescan = |previous: &ScanOutput, config: &ScannerConfig| {
+            let previous = PreviousScan::new(&previous.map, &previous.cache);
+            scan_incremental(root, config, Some(&previous)).unwrap()
+        };
+        let collapsed = |output: &ScanOutput| -> Vec<(String, usize)> {
+            output.map.patterns_matched.iter().map(|m| (path_key(&m.path), m.files_collapsed)).collect()
+        };
+        fs::create_dir(root.join("data/more")).unwrap();
+        fs::write(root.join("data/more/row.json"), "{}\n").unwrap();
+        let changed = rescan(&again, &config);
+        assert_eq!(collapsed(&changed), [("data".to_string(), 8), ("fixtures/cases".to_string(), 12)]);
+
+        // A file added deeper down only changes its own directory's mtime
+        fs::write(root.join("data/more/row_2.json"), "{}\n").unwrap();
+        let changed = rescan(&changed, &config);
+        assert_eq!(collapsed(&changed), [("data".to_string(), 9), ("fixtures/cases".to_string(), 12)]);
+
+        // Unchanged directories are judged again when the thresholds change
+        config.patterns.thresholds.max_files = Some(20);
+        let previous = PreviousScan::new(&changed.map, &changed.cache);
+        let relaxed = scan_incremental(root, &config, Some(&previous)).unwrap();
+        assert_eq!(relaxed.map.patterns_matched.len(), 1);
+        assert_eq!(relaxed.map.meta.indexed_files, 20);
+    }
 }
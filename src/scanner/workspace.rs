@@ -0,0 +1,282 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::deps::path_key;
+use crate::map::{FileNode, SubProject};
+use crate::patterns::ProjectPattern;
+
+use super::detect_project_type;
+
+/// Member globs declared by a workspace root, relative to its directory
+#[derive(Debug, Default, PartialEq)]
+struct Workspace {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+/// Detect every directory of the tree holding a project marker, with its
+/// package name and workspace membership.
+///
+/// Workspaces are read from `Cargo.toml` `[workspace]`, `package.json`
+/// `workspaces`, `pnpm-workspace.yaml`, `go.work` and `pyproject.toml`
+/// `[tool.uv.workspace]`; manifests that can't be parsed declare nothing.
+pub fn detect_sub_projects(root: &Path, tree: &FileNode, patterns: &[ProjectPattern]) -> Vec<SubProject> {
+    let markers: HashSet<&str> = patterns
+        .iter()
+        .flat_map(|p| p.markers.iter().map(String::as_str))
+        .collect();
+    let mut dirs = Vec::new();
+    collect_project_dirs(tree, &markers, &mut dirs);
+
+    let mut projects: Vec<SubProject> = dirs
+        .iter()
+        .map(|(dir, files)| {
+            let abs = root.join(dir);
+            SubProject {
+                path: dir.to_path_buf(),
+                name: package_name(&abs, files),
+                project_type: detect_project_type(&abs, patterns),
+                workspace: None,
+                members: Vec::new(),
+            }
+        })
+        .collect();
+
+    for (dir, files) in &dirs {
+        let workspace = read_workspace(&root.join(dir), files);
+        if workspace.include.is_empty() {
+            continue;
+        }
+        let Ok(matcher) = MemberMatcher::new(&workspace) else {
+            continue;
+        };
+
+        let members: Vec<PathBuf> = projects
+            .iter()
+            .filter(|p| p.path != *dir)
+            .filter(|p| {
+                p.path
+                    .strip_prefix(dir)
+                    .is_ok_and(|relative| matcher.is_member(&path_key(relative)))
+            })
+            .map(|p| p.path.clone())
+            .collect();
+
+        // Nested workspaces: the first (outermost) root to list a member keeps it
+        for project in projects.iter_mut() {
+            if members.contains(&project.path) && project.workspace.is_none() {
+                project.workspace = Some(dir.to_path_buf());
+            }
+        }
+        if let Some(project) = projects.iter_mut().find(|p| p.path == *dir) {
+            project.members = members;
+        }
+    }
+
+    projects
+}
+
+/// Directories containing a project marker, with the names of their files
+fn collect_project_dirs<'a>(node: &'a FileNode, markers: &HashSet<&str>, dirs: &mut Vec<(&'a Path, Vec<&'a str>)>) {
+    let FileNode::Directory { path, children, .. } = node else {
+        return;
+    };
+
+    let files: Vec<&str> = children
+        .iter()
+        .filter(|c| matches!(c, FileNode::File { .. }))
+        .map(|c| c.name())
+        .collect();
+    if files.iter().any(|name| markers.contains(name)) {
+        dirs.push((path, files));
+    }
+
+    for child in children {
+        collect_project_dirs(child, markers, dirs);
+    }
+}
+
+/// The package name declared by the first manifest in the directory that has one
+fn package_name(dir: &Path, files: &[&str]) -> Option<String> {
+    let read = |name: &str| {
+        files
+            .contains(&name)
+            .then(|| fs::read_to_string(dir.join(name)).ok())
+            .flatten()
+    };
+
+    if let Some(doc) = read("Cargo.toml").and_then(|c| c.parse::<toml::Table>().ok()) {
+        if let Some(name) = doc.get("package").and_then(|p| p.get("name")).and_then(|n| n.as_str()) {
+            return Some(name.to_string());
+        }
+    }
+    if let Some(doc) = read("package.json").and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok()) {
+        if let Some(name) = doc.get("name").and_then(|n| n.as_str()) {
+            return Some(name.to_string());
+        }
+    }
+    if let Some(doc) = read("pyproject.toml").and_then(|c| c.parse::<toml::Table>().ok()) {
+        let name = doc
+            .get("project")
+            .and_then(|p| p.get("name"))
+            .or_else(|| doc.get("tool").and_then(|t| t.get("poetry")).and_then(|p| p.get("name")));
+        if let Some(name) = name.and_then(|n| n.as_str()) {
+            return Some(name.to_string());
+        }
+    }
+    read("go.mod").and_then(|contents| {
+        contents
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("module "))
+            .map(|module| module.trim().trim_matches('"').to_string())
+    })
+}
+
+/// The workspace members declared by the manifests in a directory
+fn read_workspace(dir: &Path, files: &[&str]) -> Workspace {
+    let mut workspace = Workspace::default();
+    for name in files {
+        let Ok(contents) = fs::read_to_string(dir.join(name)) else {
+            continue;
+        };
+        if let Ok(found) = parse_workspace(name, &contents) {
+            workspace.include.extend(found.include);
+            workspace.exclude.extend(found.exclude);
+        }
+    }
+    workspace
+}
+
+/// Parse the workspace members a manifest declares, if any
+fn parse_workspace(name: &str, contents: &str) -> Result<Workspace> {
+    let mut workspace = Workspace::default();
+    let strings = |value: Option<&toml::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect()
+    };
+
+    match name {
+        "Cargo.toml" => {
+            let doc: toml::Table = contents.parse()?;
+            let table = doc.get("workspace");
+            workspace.include = strings(table.and_then(|w| w.get("members")));
+            workspace.exclude = strings(table.and_then(|w| w.get("exclude")));
+        }
+        "pyproject.toml" => {
+            let doc: toml::Table = contents.parse()?;
+            let table = doc.get("tool").and_then(|t| t.get("uv")).and_then(|u| u.get("workspace"));
+            workspace.include = strings(table.and_then(|w| w.get("members")));
+            workspace.exclude = strings(table.and_then(|w| w.get("exclude")));
+        }
+        "package.json" => {
+            let doc: serde_json::Value = serde_json::from_str(contents)?;
+            // Either a list of globs or `{ "packages": [...] }` (yarn)
+            let globs = doc
+                .get("workspaces")
+                .map(|w| w.get("packages").unwrap_or(w))
+                .and_then(|w| w.as_array());
+            for glob in globs.into_iter().flatten().filter_map(|g| g.as_str()) {
+                match glob.strip_prefix('!') {
+                    Some(excluded) => workspace.exclude.push(excluded.to_string()),
+                    None => workspace.include.push(glob.to_string()),
+                }
+            }
+        }
+        "pnpm-workspace.yaml" => {
+            let doc: serde_yaml::Value = serde_yaml::from_str(contents)?;
+            let globs = doc.get("packages").and_then(|p| p.as_sequence());
+            for glob in globs.into_iter().flatten().filter_map(|g| g.as_str()) {
+                match glob.strip_prefix('!') {
+                    Some(excluded) => workspace.exclude.push(excluded.to_string()),
+                    None => workspace.include.push(glob.to_string()),
+                }
+            }
+        }
+        "go.work" => {
+            // `use ./dir` or a `use ( ... )` block
+            let mut in_block = false;
+            for line in contents.lines() {
+                let line = line.split("//").next().unwrap_or("").trim();
+                if in_block {
+                    if line == ")" {
+                        in_block = false;
+                    } else if !line.is_empty() {
+                        workspace.include.push(line.to_string());
+                    }
+                } else if let Some(rest) = line.strip_prefix("use") {
+                    match rest.trim() {
+                        "(" => in_block = true,
+                        dir if !dir.is_empty() && rest.starts_with(char::is_whitespace) => {
+                            workspace.include.push(dir.to_string())
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(workspace)
+}
+
+/// Member globs compiled for matching paths relative to the workspace root
+struct MemberMatcher {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl MemberMatcher {
+    fn new(workspace: &Workspace) -> Result<Self> {
+        let build = |globs: &[String]| -> Result<GlobSet> {
+            let mut set = GlobSetBuilder::new();
+            for glob in globs {
+                let glob = glob.trim_start_matches("./").trim_end_matches('/');
+                set.add(GlobBuilder::new(glob).literal_separator(true).build()?);
+            }
+            Ok(set.build()?)
+        };
+        Ok(Self {
+            include: build(&workspace.include)?,
+            exclude: build(&workspace.exclude)?,
+        })
+    }
+
+    fn is_member(&self, relative: &str) -> bool {
+        self.include.is_match(relative) && !self.exclude.is_match(relative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_workspace() {
+        let cargo = "[workspace]\nmembers = [\"crates/*\", \"tools/cli\"]\nexclude = [\"crates/legacy\"]\n";
+        let workspace = parse_workspace("Cargo.toml", cargo).unwrap();
+        assert_eq!(workspace.include, ["crates/*", "tools/cli"]);
+        assert_eq!(workspace.exclude, ["crates/legacy"]);
+
+        let package = r#"{"name": "root", "workspaces": {"packages": ["packages/*", "!packages/old"]}}"#;
+        let workspace = parse_workspace("package.json", package).unwrap();
+        assert_eq!(workspace.include, ["packages/*"]);
+        assert_eq!(workspace.exclude, ["packages/old"]);
+
+        let go_work = "go 1.22\n\nuse (\n\t./api\n\t./web // frontend\n)\nuse ./tools\n";
+        assert_eq!(parse_workspace("go.work", go_work).unwrap().include, ["./api", "./web", "./tools"]);
+
+        let matcher = MemberMatcher::new(&parse_workspace("Cargo.toml", cargo).unwrap()).unwrap();
+        assert!(matcher.is_member("crates/core"));
+        assert!(!matcher.is_member("crates/legacy"));
+        assert!(!matcher.is_member("crates/core/nested"));
+    }
+}